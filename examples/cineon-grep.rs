@@ -0,0 +1,174 @@
+use std::fs::{self, File};
+use std::io::prelude::*;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use cineon::Cineon;
+use structopt::StructOpt;
+
+/// Bytes read before the header is known to fit
+const HEADER_SIZE: usize = 2048;
+
+#[derive(StructOpt, Debug)]
+#[structopt(name = "cineon-grep")]
+/// Searches the text fields of cineon headers
+struct Opt {
+    /// Text to search for
+    pattern: String,
+    /// Files and directories, searched recursively
+    #[structopt(parse(from_os_str), default_value = ".")]
+    paths: Vec<PathBuf>,
+    /// Ignore case
+    #[structopt(short = "i")]
+    ignore_case: bool,
+    /// Only search these fields (filename, label, source, device, model,
+    /// serial, format, frame_id, slate)
+    #[structopt(short = "f", long = "field")]
+    fields: Vec<String>,
+    /// Characters of context around each match
+    #[structopt(short = "C", default_value = "20")]
+    context: usize,
+    /// Number of threads
+    #[structopt(short = "j")]
+    jobs: Option<usize>,
+}
+
+/// Collects the cineon files below `path`.
+fn collect_files(path: &Path, files: &mut Vec<PathBuf>) {
+    if path.is_dir() {
+        let entries = match fs::read_dir(path) {
+            Ok(entries) => entries,
+            Err(error) => return eprintln!("{}: {}", path.display(), error),
+        };
+        for entry in entries.flatten() {
+            collect_files(&entry.path(), files);
+        }
+    } else if path
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("cin"))
+    {
+        files.push(path.to_owned());
+    }
+}
+
+/// Reads the header of a cineon file, up to the image data.
+fn read_header(path: &Path) -> std::io::Result<Vec<u8>> {
+    let mut file = File::open(path)?;
+    let mut content = vec![0; HEADER_SIZE];
+    file.read_exact(&mut content)?;
+    let offset = if content[0] == 0x80 {
+        u32::from_be_bytes([content[4], content[5], content[6], content[7]])
+    } else {
+        u32::from_le_bytes([content[4], content[5], content[6], content[7]])
+    } as usize;
+    if offset > HEADER_SIZE {
+        file.take((offset - HEADER_SIZE) as u64)
+            .read_to_end(&mut content)?;
+    }
+    Ok(content)
+}
+
+/// Text fields of a header, by name.
+fn text_fields(input: &[u8]) -> Option<Vec<(&'static str, String)>> {
+    let header = Cineon.parse_header(input).ok()?;
+    let origination_info = header.origination_info;
+    let mut fields = vec![
+        ("filename", header.file_info.filename),
+        ("label", header.image_info.label_text),
+        ("source", origination_info.source_image_file_name),
+        ("device", origination_info.input_device),
+        ("model", origination_info.input_device_model_number),
+        ("serial", origination_info.input_device_serial_number),
+    ];
+    if let Some(film_info) = header.film_info {
+        fields.push(("format", film_info.format));
+        fields.push(("frame_id", film_info.frame_id));
+        fields.push(("slate", film_info.slate_info));
+    }
+    Some(fields)
+}
+
+/// Matching lines for every field of `path` containing the pattern.
+fn search(path: &Path, opt: &Opt) -> Vec<String> {
+    let input = match read_header(path) {
+        Ok(input) => input,
+        Err(error) => return vec![format!("{}: {}", path.display(), error)],
+    };
+    let fields = match text_fields(&input) {
+        Some(fields) => fields,
+        None => return Vec::new(),
+    };
+    let fold = |text: &str| {
+        if opt.ignore_case {
+            text.to_lowercase()
+        } else {
+            text.to_owned()
+        }
+    };
+    let pattern = fold(&opt.pattern);
+    fields
+        .into_iter()
+        .filter(|(name, _)| opt.fields.is_empty() || opt.fields.iter().any(|f| f == name))
+        .filter_map(|(name, value)| {
+            // Case folding may change lengths, so context is taken in chars
+            let folded = fold(&value);
+            let start = folded.find(&pattern)?;
+            let start = folded[..start].chars().count();
+            let length = pattern.chars().count();
+            let chars: Vec<char> = value.chars().collect();
+            let from = start.saturating_sub(opt.context);
+            let to = (start + length + opt.context).min(chars.len());
+            let snippet = |range: std::ops::Range<usize>| -> String {
+                chars[range.start.min(chars.len())..range.end.min(chars.len())]
+                    .iter()
+                    .collect()
+            };
+            Some(format!(
+                "{}: {}: {}[{}]{}",
+                path.display(),
+                name,
+                snippet(from..start),
+                snippet(start..start + length),
+                snippet(start + length..to)
+            ))
+        })
+        .collect()
+}
+
+fn main() {
+    let opt = Arc::new(Opt::from_args());
+
+    let mut files = Vec::new();
+    for path in opt.paths.iter() {
+        collect_files(path, &mut files);
+    }
+    let files = Arc::new(Mutex::new(files));
+
+    let jobs = opt
+        .jobs
+        .unwrap_or_else(|| thread::available_parallelism().map_or(1, |jobs| jobs.get()));
+    let workers: Vec<_> = (0..jobs.max(1))
+        .map(|_| {
+            let files = Arc::clone(&files);
+            let opt = Arc::clone(&opt);
+            thread::spawn(move || loop {
+                let path = match files.lock().unwrap().pop() {
+                    Some(path) => path,
+                    None => break,
+                };
+                let lines = search(&path, &opt);
+                if !lines.is_empty() {
+                    let stdout = std::io::stdout();
+                    let mut stdout = stdout.lock();
+                    for line in lines {
+                        writeln!(stdout, "{}", line).unwrap();
+                    }
+                }
+            })
+        })
+        .collect();
+    for worker in workers {
+        worker.join().unwrap();
+    }
+}
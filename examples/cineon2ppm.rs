@@ -3,7 +3,7 @@ use std::io::prelude::*;
 use std::io::BufReader;
 use std::path::PathBuf;
 
-use cineon::{Cineon, ImageData};
+use cineon::{Cineon, DecodedImage, Samples};
 use structopt::StructOpt;
 
 #[derive(StructOpt, Debug)]
@@ -26,11 +26,29 @@ fn main() -> std::io::Result<()> {
     let mut content = Vec::new();
     buf_reader.read_to_end(&mut content)?;
 
-    let ImageData { header, pixels } = Cineon::default().parse_image(&content).unwrap();
-
-    let width = header.image_info.channel[0].pixels_per_line;
-    let height = header.image_info.channel[0].lines_per_element;
-    let bit_depth = header.image_info.channel[0].bit_depth;
+    let DecodedImage {
+        width,
+        height,
+        channels,
+        bit_depth,
+        samples,
+        ..
+    } = Cineon.decode(&content).unwrap();
+
+    // Monochrome images repeat their only channel on every PPM channel
+    let repeat = if channels == 1 { 3 } else { 1 };
+    let pixels: Vec<u8> = match samples {
+        Samples::U8(samples) => samples
+            .iter()
+            .flat_map(|sample| std::iter::repeat_n(*sample, repeat))
+            .collect(),
+        Samples::U16(samples) => samples
+            .iter()
+            .flat_map(|sample| std::iter::repeat_n(*sample, repeat))
+            .flat_map(|sample| sample.to_be_bytes())
+            .collect(),
+        _ => panic!("Only unsigned integer images can be converted to PPM"),
+    };
 
     let mut output = File::create(opt.output).unwrap();
 
@@ -38,7 +56,7 @@ fn main() -> std::io::Result<()> {
     writeln!(&mut output, "{} {}", width, height).unwrap();
     writeln!(&mut output, "{}", (1 << bit_depth) - 1).unwrap();
 
-    output.write(&pixels).unwrap();
+    output.write_all(&pixels).unwrap();
 
     Ok(())
 }
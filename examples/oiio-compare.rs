@@ -0,0 +1,138 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use cineon::Cineon;
+use structopt::StructOpt;
+
+#[derive(StructOpt, Debug)]
+#[structopt(name = "oiio-compare")]
+/// Compares the decoding of cineon images with the one of oiiotool
+struct Opt {
+    /// Directory searched recursively for cineon images
+    #[structopt(parse(from_os_str))]
+    input: PathBuf,
+    /// Largest difference allowed between 16-bit samples
+    #[structopt(short = "t", default_value = "1")]
+    tolerance: u16,
+}
+
+/// Collects the cineon files below `path`.
+fn collect_files(path: &Path, files: &mut Vec<PathBuf>) {
+    if path.is_dir() {
+        if let Ok(entries) = fs::read_dir(path) {
+            for entry in entries.flatten() {
+                collect_files(&entry.path(), files);
+            }
+        }
+    } else if path
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("cin"))
+    {
+        files.push(path.to_owned());
+    }
+}
+
+/// Samples of a binary 16-bit PPM image.
+fn read_ppm16(content: &[u8]) -> Result<Vec<u16>, String> {
+    // Magic, width, height and maximum value, separated by whitespace
+    let mut fields = Vec::new();
+    let mut position = 0;
+    while fields.len() < 4 {
+        while content
+            .get(position)
+            .is_some_and(|byte| byte.is_ascii_whitespace())
+        {
+            position += 1;
+        }
+        let start = position;
+        while content
+            .get(position)
+            .is_some_and(|byte| !byte.is_ascii_whitespace())
+        {
+            position += 1;
+        }
+        if start == position {
+            return Err("truncated PPM header".to_owned());
+        }
+        fields.push(String::from_utf8_lossy(&content[start..position]).into_owned());
+    }
+    if fields[0] != "P6" || fields[3] != "65535" {
+        return Err(format!("unexpected PPM header {:?}", fields));
+    }
+    Ok(content[position + 1..]
+        .chunks_exact(2)
+        .map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]]))
+        .collect())
+}
+
+/// Largest difference between the RGB samples decoded by this crate and
+/// by oiiotool.
+fn compare(path: &Path) -> Result<u16, String> {
+    let content = fs::read(path).map_err(|error| error.to_string())?;
+    let decoder = Cineon
+        .decoder(&content)
+        .map_err(|error| format!("{:?}", error))?
+        .normalize_orientation(true);
+    let layout = decoder.layout();
+    let mut ours = vec![0; 4 * layout.width as usize * layout.height as usize];
+    decoder
+        .decode_rgba16(&mut ours)
+        .map_err(|error| format!("{:?}", error))?;
+
+    let reference = std::env::temp_dir().join(format!("oiio-compare-{}.ppm", std::process::id()));
+    let status = Command::new("oiiotool")
+        .arg(path)
+        .args(["-d", "uint16", "-o"])
+        .arg(&reference)
+        .status()
+        .map_err(|error| format!("cannot run oiiotool: {}", error))?;
+    if !status.success() {
+        return Err(format!("oiiotool failed with {}", status));
+    }
+    let theirs = read_ppm16(&fs::read(&reference).map_err(|error| error.to_string())?)?;
+    let _ = fs::remove_file(&reference);
+
+    if theirs.len() * 4 != ours.len() * 3 {
+        return Err("image sizes differ".to_owned());
+    }
+    Ok(ours
+        .chunks_exact(4)
+        .zip(theirs.chunks_exact(3))
+        .flat_map(|(ours, theirs)| {
+            ours.iter()
+                .zip(theirs.iter())
+                .map(|(a, b)| (*a as i32 - *b as i32).unsigned_abs() as u16)
+        })
+        .max()
+        .unwrap_or(0))
+}
+
+fn main() {
+    let opt = Opt::from_args();
+
+    let mut files = Vec::new();
+    collect_files(&opt.input, &mut files);
+    files.sort();
+
+    let mut failures = 0;
+    for path in files.iter() {
+        match compare(path) {
+            Ok(difference) if difference <= opt.tolerance => {
+                println!("ok    {} (max difference {})", path.display(), difference)
+            }
+            Ok(difference) => {
+                failures += 1;
+                println!("DIFF  {} (max difference {})", path.display(), difference)
+            }
+            Err(error) => {
+                failures += 1;
+                println!("ERROR {}: {}", path.display(), error)
+            }
+        }
+    }
+    println!("{} files, {} failures", files.len(), failures);
+    if failures > 0 {
+        std::process::exit(1);
+    }
+}
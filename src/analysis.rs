@@ -0,0 +1,402 @@
+//! Measurements over decoded images.
+
+use crate::color::{ColorSpace, DENSITY_PER_CODE};
+use crate::image::{DecodedImage, Region, Samples};
+use crate::CineonError;
+
+/// Printing density represented by the whole code range of a 10-bit image
+const DENSITY_RANGE: f64 = 1023. * DENSITY_PER_CODE;
+
+/// Number of levels of a waveform, each covering an equal share of the
+/// code range
+pub const WAVEFORM_LEVELS: u32 = 256;
+
+/// Statistics of a channel over a region
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChannelStats {
+    /// Mean code value
+    pub mean: f64,
+    /// Standard deviation of code values
+    pub std_dev: f64,
+    /// Mean printing density
+    pub mean_density: f64,
+    /// Standard deviation of printing densities
+    pub std_dev_density: f64,
+}
+
+/// Statistics of every channel over a region
+#[derive(Debug, Clone, PartialEq)]
+pub struct RegionStats {
+    /// Measured region
+    pub region: Region,
+    /// Statistics, one per channel
+    pub channels: Vec<ChannelStats>,
+}
+
+/// Printing density of a code value, following the Cineon convention of
+/// 0.002 density per 10-bit code value, scaled to the bit depth of the
+/// image. Floating point samples are taken as normalized code values.
+fn density_per_code(image: &DecodedImage) -> f64 {
+    match image.samples {
+        Samples::F32(_) | Samples::F64(_) => DENSITY_RANGE,
+        _ => DENSITY_RANGE / ((1u64 << image.bit_depth) - 1) as f64,
+    }
+}
+
+/// Computes the mean and standard deviation of every channel within each
+/// region, in code values and printing densities, as needed to read step
+/// wedges exposed for scanner calibration.
+pub fn region_stats(
+    image: &DecodedImage,
+    regions: &[Region],
+) -> Result<Vec<RegionStats>, CineonError> {
+    let channels = image.channels as usize;
+    let density = density_per_code(image);
+    regions
+        .iter()
+        .map(|region| {
+            if !region.fits(image.width, image.height) {
+                return Err(CineonError::OutOfBounds);
+            }
+            let mut sums = vec![(0f64, 0f64); channels];
+            for y in region.rows() {
+                for x in region.columns() {
+                    let pixel = (y as usize * image.width as usize + x as usize) * channels;
+                    for (channel, (sum, squares)) in sums.iter_mut().enumerate() {
+                        let value = image.samples.get(pixel + channel);
+                        *sum += value;
+                        *squares += value * value;
+                    }
+                }
+            }
+            let count = (region.width as f64 * region.height as f64).max(1.);
+            let channels = sums
+                .into_iter()
+                .map(|(sum, squares)| {
+                    let mean = sum / count;
+                    let std_dev = (squares / count - mean * mean).max(0.).sqrt();
+                    ChannelStats {
+                        mean,
+                        std_dev,
+                        mean_density: mean * density,
+                        std_dev_density: std_dev * density,
+                    }
+                })
+                .collect();
+            Ok(RegionStats {
+                region: *region,
+                channels,
+            })
+        })
+        .collect()
+}
+
+/// Value of every sample normalized to the code range, negative and
+/// overflowing values being clamped.
+fn normalized(image: &DecodedImage) -> impl Fn(usize) -> f64 + '_ {
+    let scale = match image.samples {
+        Samples::F32(_) | Samples::F64(_) => 1.,
+        Samples::I8(_) | Samples::I16(_) => 1. / ((1u64 << (image.bit_depth - 1)) - 1) as f64,
+        _ => 1. / ((1u64 << image.bit_depth) - 1) as f64,
+    };
+    move |index| (image.samples.get(index) * scale).clamp(0., 1.)
+}
+
+/// Brightness of a scope cell holding `count` samples, relative to the
+/// fullest one.
+fn intensity(count: u32, peak: u32) -> u8 {
+    if count == 0 {
+        0
+    } else {
+        (255. * (count as f64 / peak as f64).sqrt()).round().max(1.) as u8
+    }
+}
+
+/// Distribution of sample levels across the columns of an image, per
+/// channel
+#[derive(Debug, Clone, PartialEq)]
+pub struct Waveform {
+    /// Number of columns the image is split into
+    pub columns: u32,
+    /// Number of channels
+    pub channels: u32,
+    /// Samples per channel, level and column, with level 0 being the
+    /// lowest code values
+    pub counts: Vec<u32>,
+}
+
+impl Waveform {
+    /// Number of samples of `channel` at `level` within `column`.
+    pub fn count(&self, channel: u32, level: u32, column: u32) -> u32 {
+        self.counts[((channel * WAVEFORM_LEVELS + level) * self.columns + column) as usize]
+    }
+
+    /// Renders the waveform as an 8-bit image of `columns`x`WAVEFORM_LEVELS`
+    /// pixels, one channel per image channel, with high levels at the top.
+    pub fn render(&self) -> DecodedImage {
+        let peak = self.counts.iter().copied().max().unwrap_or(0);
+        let mut samples = Vec::with_capacity(self.counts.len());
+        for row in 0..WAVEFORM_LEVELS {
+            let level = WAVEFORM_LEVELS - 1 - row;
+            for column in 0..self.columns {
+                for channel in 0..self.channels {
+                    samples.push(intensity(self.count(channel, level, column), peak));
+                }
+            }
+        }
+        DecodedImage {
+            width: self.columns,
+            height: WAVEFORM_LEVELS,
+            channels: self.channels,
+            bit_depth: 8,
+            samples: Samples::U8(samples),
+            color_space: ColorSpace::Srgb,
+        }
+    }
+}
+
+/// Builds the waveform of every channel, gathering the pixels of the image
+/// into `columns` columns of equal width.
+pub fn waveform(image: &DecodedImage, columns: u32) -> Result<Waveform, CineonError> {
+    if columns == 0 || columns > image.width {
+        return Err(CineonError::OutOfBounds);
+    }
+    let channels = image.channels;
+    let value = normalized(image);
+    let mut waveform = Waveform {
+        columns,
+        channels,
+        counts: vec![0; (channels * WAVEFORM_LEVELS * columns) as usize],
+    };
+    let max_level = (WAVEFORM_LEVELS - 1) as f64;
+    for y in 0..image.height as usize {
+        for x in 0..image.width as usize {
+            let column = (x as u64 * columns as u64 / image.width as u64) as u32;
+            let pixel = (y * image.width as usize + x) * channels as usize;
+            for channel in 0..channels {
+                let level = (value(pixel + channel as usize) * max_level).round() as u32;
+                let index = (channel * WAVEFORM_LEVELS + level) * columns + column;
+                waveform.counts[index as usize] += 1;
+            }
+        }
+    }
+    Ok(waveform)
+}
+
+/// Distribution of the chroma of RGB pixels
+#[derive(Debug, Clone, PartialEq)]
+pub struct Vectorscope {
+    /// Side of the square grid
+    pub size: u32,
+    /// Pixels per cell, row by row, with blue difference growing to the
+    /// right and red difference to the top
+    pub counts: Vec<u32>,
+}
+
+impl Vectorscope {
+    /// Renders the vectorscope as a single-channel 8-bit image of
+    /// `size`x`size` pixels.
+    pub fn render(&self) -> DecodedImage {
+        let peak = self.counts.iter().copied().max().unwrap_or(0);
+        DecodedImage {
+            width: self.size,
+            height: self.size,
+            channels: 1,
+            bit_depth: 8,
+            samples: Samples::U8(
+                self.counts
+                    .iter()
+                    .map(|count| intensity(*count, peak))
+                    .collect(),
+            ),
+            color_space: ColorSpace::Srgb,
+        }
+    }
+}
+
+/// Plots the Rec. 709 color differences of every pixel of an RGB image
+/// on a `size`x`size` grid, neutral pixels falling in the center.
+pub fn vectorscope(image: &DecodedImage, size: u32) -> Result<Vectorscope, CineonError> {
+    if image.channels != 3 {
+        return Err(CineonError::UnsupportedFormat);
+    }
+    if size == 0 {
+        return Err(CineonError::OutOfBounds);
+    }
+    let value = normalized(image);
+    let mut counts = vec![0; size as usize * size as usize];
+    let last = (size - 1) as f64;
+    for pixel in 0..image.width as usize * image.height as usize {
+        let (r, g, b) = (value(3 * pixel), value(3 * pixel + 1), value(3 * pixel + 2));
+        let luma = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+        let cb = (b - luma) / 1.8556;
+        let cr = (r - luma) / 1.5748;
+        let x = ((cb + 0.5) * last).round() as usize;
+        let y = ((0.5 - cr) * last).round() as usize;
+        counts[y * size as usize + x] += 1;
+    }
+    Ok(Vectorscope { size, counts })
+}
+
+/// Distribution of the code values of every channel
+#[derive(Debug, Clone, PartialEq)]
+pub struct Histogram {
+    /// Number of bins, each covering an equal share of the code range
+    pub bins: u32,
+    /// Samples per bin, one table per channel, with bin 0 holding the
+    /// lowest code values
+    pub counts: Vec<Vec<u64>>,
+}
+
+/// Counts the samples of every channel falling into each of `bins` equal
+/// shares of the code range, reals being taken from 0.0 to 1.0 and
+/// clamped.
+pub fn histogram(image: &DecodedImage, bins: u32) -> Result<Histogram, CineonError> {
+    if bins == 0 {
+        return Err(CineonError::OutOfBounds);
+    }
+    let channels = image.channels as usize;
+    let value = normalized(image);
+    let mut counts = vec![vec![0; bins as usize]; channels];
+    let last = bins as usize - 1;
+    for index in 0..image.samples.len() {
+        let bin = ((value(index) * bins as f64) as usize).min(last);
+        counts[index % channels][bin] += 1;
+    }
+    Ok(Histogram { bins, counts })
+}
+
+/// Summary of the code values of a channel
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChannelSummary {
+    /// Lowest code value
+    pub min: f64,
+    /// Highest code value
+    pub max: f64,
+    /// Mean code value
+    pub mean: f64,
+    /// Code values at the requested percentiles, in the same order
+    pub percentiles: Vec<f64>,
+}
+
+/// Summarizes the code values of every channel of the whole image, with
+/// the nearest-rank value at each of `percentiles`, from 0 to 100, so that
+/// badly exposed scans can be flagged during ingest.
+pub fn channel_summary(
+    image: &DecodedImage,
+    percentiles: &[f64],
+) -> Result<Vec<ChannelSummary>, CineonError> {
+    if percentiles.iter().any(|p| !(0. ..=100.).contains(p)) {
+        return Err(CineonError::OutOfBounds);
+    }
+    let channels = image.channels as usize;
+    (0..channels)
+        .map(|channel| {
+            let mut values: Vec<f64> = (channel..image.samples.len())
+                .step_by(channels)
+                .map(|index| image.samples.get(index))
+                .collect();
+            if values.is_empty() {
+                return Err(CineonError::OutOfBounds);
+            }
+            values.sort_unstable_by(f64::total_cmp);
+            let count = values.len();
+            let rank = |percentile: f64| {
+                let rank = (percentile / 100. * count as f64).ceil() as usize;
+                values[rank.clamp(1, count) - 1]
+            };
+            Ok(ChannelSummary {
+                min: values[0],
+                max: values[count - 1],
+                mean: values.iter().sum::<f64>() / count as f64,
+                percentiles: percentiles.iter().map(|p| rank(*p)).collect(),
+            })
+        })
+        .collect()
+}
+
+/// 10-bit code values of the Cineon reference black and white
+const REFERENCE_LEVELS: (f64, f64) = (95., 685.);
+
+/// Samples of a channel beyond the reference levels
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChannelClipping {
+    /// Samples at or below the reference black
+    pub crushed: u64,
+    /// Samples at or above the reference white
+    pub blown: u64,
+}
+
+/// Samples and pixels of an image beyond the reference levels
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClippingReport {
+    /// Counts, one per channel
+    pub channels: Vec<ChannelClipping>,
+    /// Pixels with any channel at or beyond a reference level
+    pub clipped_pixels: u64,
+    /// Pixels of the image
+    pub pixels: u64,
+}
+
+impl ClippingReport {
+    /// Share of clipped pixels, from 0 to 100.
+    pub fn clipped_percentage(&self) -> f64 {
+        if self.pixels == 0 {
+            0.
+        } else {
+            100. * self.clipped_pixels as f64 / self.pixels as f64
+        }
+    }
+}
+
+/// Counts the samples of every channel at or below the reference black
+/// and at or above the reference white, given as `(black, white)` code
+/// values by `levels`, so that blown-out frames can be flagged.
+///
+/// Without levels, integer samples use the Cineon reference code values,
+/// 95 and 685 in 10 bits, scaled to the bit depth, while reals use 0.0
+/// and 1.0.
+pub fn clipping(
+    image: &DecodedImage,
+    levels: Option<(f64, f64)>,
+) -> Result<ClippingReport, CineonError> {
+    let (black, white) = levels.unwrap_or_else(|| match image.samples {
+        Samples::F32(_) | Samples::F64(_) => (0., 1.),
+        _ => {
+            let scale = ((1u64 << image.bit_depth.min(16)) - 1) as f64 / 1023.;
+            (REFERENCE_LEVELS.0 * scale, REFERENCE_LEVELS.1 * scale)
+        }
+    });
+    if black >= white {
+        return Err(CineonError::OutOfBounds);
+    }
+    let channels = image.channels as usize;
+    let mut counts = vec![
+        ChannelClipping {
+            crushed: 0,
+            blown: 0,
+        };
+        channels
+    ];
+    let mut clipped_pixels = 0;
+    let pixels = image.samples.len().checked_div(channels).unwrap_or(0);
+    for pixel in 0..pixels {
+        let mut clipped = false;
+        for (channel, count) in counts.iter_mut().enumerate() {
+            let value = image.samples.get(pixel * channels + channel);
+            if value <= black {
+                count.crushed += 1;
+                clipped = true;
+            } else if value >= white {
+                count.blown += 1;
+                clipped = true;
+            }
+        }
+        clipped_pixels += clipped as u64;
+    }
+    Ok(ClippingReport {
+        channels: counts,
+        clipped_pixels,
+        pixels: pixels as u64,
+    })
+}
@@ -0,0 +1,253 @@
+//! Bit-level access to packed sample data.
+//!
+//! Cineon stores samples MSB-first inside words whose byte order follows the
+//! magic number. These primitives are what the crate unpacks pixels with and
+//! are public so that unusual vendor packings can be decoded the same way.
+
+use crate::CineonError;
+
+/// Byte order of the words holding packed samples
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ByteOrder {
+    /// Most significant byte first
+    Big,
+    /// Least significant byte first
+    Little,
+}
+
+#[inline(always)]
+fn mask(count: u32) -> u64 {
+    if count == 64 {
+        u64::MAX
+    } else {
+        (1 << count) - 1
+    }
+}
+
+#[inline(always)]
+fn check_word_bits(word_bits: u32) -> Result<(), CineonError> {
+    if !matches!(word_bits, 8 | 16 | 32 | 64) {
+        return Err(CineonError::UnsupportedFormat);
+    }
+    Ok(())
+}
+
+/// Reads MSB-first bit fields out of a sequence of words.
+pub struct BitReader<'a> {
+    input: &'a [u8],
+    byte_order: ByteOrder,
+    word_bits: u32,
+    position: usize,
+    word: u64,
+    bits_left: u32,
+}
+
+impl<'a> BitReader<'a> {
+    /// Creates a reader over 32-bit words.
+    pub fn new(input: &'a [u8], byte_order: ByteOrder) -> Self {
+        Self {
+            input,
+            byte_order,
+            word_bits: 32,
+            position: 0,
+            word: 0,
+            bits_left: 0,
+        }
+    }
+
+    /// Creates a reader over words of `word_bits` bits, which must be 8,
+    /// 16, 32 or 64.
+    pub fn with_word_bits(
+        input: &'a [u8],
+        byte_order: ByteOrder,
+        word_bits: u32,
+    ) -> Result<Self, CineonError> {
+        check_word_bits(word_bits)?;
+        Ok(Self {
+            word_bits,
+            ..Self::new(input, byte_order)
+        })
+    }
+
+    /// Reads the next `count` bits (at most 64), crossing word boundaries
+    /// when needed.
+    pub fn read_bits(&mut self, count: u32) -> Result<u64, CineonError> {
+        if count > 64 {
+            return Err(CineonError::UnsupportedFormat);
+        }
+        let mut value: u64 = 0;
+        let mut remaining = count;
+        while remaining > 0 {
+            if self.bits_left == 0 {
+                self.load_word()?;
+            }
+            let take = remaining.min(self.bits_left);
+            let bits = (self.word >> (self.bits_left - take)) & mask(take);
+            value = value.checked_shl(take).unwrap_or(0) | bits;
+            self.bits_left -= take;
+            remaining -= take;
+        }
+        Ok(value)
+    }
+
+    /// Discards the next `count` bits.
+    pub fn skip_bits(&mut self, count: u32) -> Result<(), CineonError> {
+        let mut remaining = count;
+        while remaining > 0 {
+            let step = remaining.min(64);
+            self.read_bits(step)?;
+            remaining -= step;
+        }
+        Ok(())
+    }
+
+    /// Discards the bits left in the current word, so that the next read
+    /// starts on a word boundary.
+    pub fn align_to_word(&mut self) {
+        self.bits_left = 0;
+    }
+
+    /// Number of unread bits in the current word.
+    pub fn bits_left_in_word(&self) -> u32 {
+        self.bits_left
+    }
+
+    /// Number of bytes loaded from the input so far.
+    pub fn bytes_consumed(&self) -> usize {
+        self.position
+    }
+
+    fn load_word(&mut self) -> Result<(), CineonError> {
+        let size = (self.word_bits / 8) as usize;
+        let bytes = self
+            .input
+            .get(self.position..self.position + size)
+            .ok_or(CineonError::ParserError)?;
+        self.word = match self.byte_order {
+            ByteOrder::Big => bytes.iter().fold(0, |acc, &b| (acc << 8) | b as u64),
+            ByteOrder::Little => bytes.iter().rev().fold(0, |acc, &b| (acc << 8) | b as u64),
+        };
+        self.position += size;
+        self.bits_left = self.word_bits;
+        Ok(())
+    }
+}
+
+/// Writes MSB-first bit fields into a sequence of words.
+pub struct BitWriter {
+    output: Vec<u8>,
+    byte_order: ByteOrder,
+    word_bits: u32,
+    word: u64,
+    bits_used: u32,
+}
+
+impl BitWriter {
+    /// Creates a writer producing 32-bit words.
+    pub fn new(byte_order: ByteOrder) -> Self {
+        Self {
+            output: Vec::new(),
+            byte_order,
+            word_bits: 32,
+            word: 0,
+            bits_used: 0,
+        }
+    }
+
+    /// Creates a writer producing words of `word_bits` bits, which must be
+    /// 8, 16, 32 or 64.
+    pub fn with_word_bits(byte_order: ByteOrder, word_bits: u32) -> Result<Self, CineonError> {
+        check_word_bits(word_bits)?;
+        Ok(Self {
+            word_bits,
+            ..Self::new(byte_order)
+        })
+    }
+
+    /// Writes the low `count` bits (at most 64) of `value`, crossing word
+    /// boundaries when needed.
+    pub fn write_bits(&mut self, value: u64, count: u32) -> Result<(), CineonError> {
+        if count > 64 {
+            return Err(CineonError::UnsupportedFormat);
+        }
+        let mut remaining = count;
+        while remaining > 0 {
+            let space = self.word_bits - self.bits_used;
+            let take = remaining.min(space);
+            let bits = (value >> (remaining - take)) & mask(take);
+            self.word |= bits << (space - take);
+            self.bits_used += take;
+            remaining -= take;
+            if self.bits_used == self.word_bits {
+                self.flush_word();
+            }
+        }
+        Ok(())
+    }
+
+    /// Pads the current word with zero bits, so that the next write starts
+    /// on a word boundary.
+    pub fn align_to_word(&mut self) {
+        if self.bits_used > 0 {
+            self.flush_word();
+        }
+    }
+
+    /// Returns the written bytes, padding the last word with zero bits.
+    pub fn into_bytes(mut self) -> Vec<u8> {
+        self.align_to_word();
+        self.output
+    }
+
+    fn flush_word(&mut self) {
+        let size = (self.word_bits / 8) as usize;
+        let bytes = self.word.to_be_bytes();
+        let word = &bytes[bytes.len() - size..];
+        match self.byte_order {
+            ByteOrder::Big => self.output.extend(word.iter()),
+            ByteOrder::Little => self.output.extend(word.iter().rev()),
+        }
+        self.word = 0;
+        self.bits_used = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ten_bit_longword_round_trip() {
+        for &order in &[ByteOrder::Big, ByteOrder::Little] {
+            let mut writer = BitWriter::new(order);
+            for value in &[1023u64, 512, 1] {
+                writer.write_bits(*value, 10).unwrap();
+            }
+            writer.align_to_word();
+            writer.write_bits(7, 10).unwrap();
+            let bytes = writer.into_bytes();
+            assert_eq!(bytes.len(), 8);
+
+            let mut reader = BitReader::new(&bytes, order);
+            assert_eq!(reader.read_bits(10).unwrap(), 1023);
+            assert_eq!(reader.read_bits(10).unwrap(), 512);
+            assert_eq!(reader.read_bits(10).unwrap(), 1);
+            assert_eq!(reader.bits_left_in_word(), 2);
+            reader.align_to_word();
+            assert_eq!(reader.read_bits(10).unwrap(), 7);
+            assert!(reader.read_bits(32).is_err());
+        }
+    }
+
+    #[test]
+    fn little_endian_words() {
+        let bytes = [0x34, 0x12];
+        let mut reader = BitReader::with_word_bits(&bytes, ByteOrder::Little, 16).unwrap();
+        assert_eq!(reader.read_bits(4).unwrap(), 0x1);
+        assert_eq!(reader.read_bits(12).unwrap(), 0x234);
+        assert!(BitReader::with_word_bits(&bytes, ByteOrder::Little, 24).is_err());
+        assert!(BitWriter::with_word_bits(ByteOrder::Big, 0).is_err());
+        assert!(reader.read_bits(65).is_err());
+        assert!(BitWriter::new(ByteOrder::Big).write_bits(0, 65).is_err());
+    }
+}
@@ -0,0 +1,185 @@
+//! Slate frames rendered from header metadata.
+
+use crate::color::ColorSpace;
+use crate::header::Header;
+use crate::image::{DecodedImage, Samples};
+
+/// Glyph width in pixels, spacing excluded
+const GLYPH_WIDTH: u32 = 5;
+/// Glyph height in pixels, spacing excluded
+const GLYPH_HEIGHT: u32 = 7;
+/// Horizontal space taken by a character
+const CELL_WIDTH: u32 = GLYPH_WIDTH + 1;
+/// Vertical space taken by a line of text
+const CELL_HEIGHT: u32 = GLYPH_HEIGHT + 3;
+
+/// Rows of a 5x7 glyph, the leftmost pixel being the fifth bit.
+fn glyph(c: char) -> [u8; 7] {
+    match c.to_ascii_uppercase() {
+        'A' => [0x0e, 0x11, 0x11, 0x1f, 0x11, 0x11, 0x11],
+        'B' => [0x1e, 0x11, 0x11, 0x1e, 0x11, 0x11, 0x1e],
+        'C' => [0x0e, 0x11, 0x10, 0x10, 0x10, 0x11, 0x0e],
+        'D' => [0x1e, 0x11, 0x11, 0x11, 0x11, 0x11, 0x1e],
+        'E' => [0x1f, 0x10, 0x10, 0x1e, 0x10, 0x10, 0x1f],
+        'F' => [0x1f, 0x10, 0x10, 0x1e, 0x10, 0x10, 0x10],
+        'G' => [0x0e, 0x11, 0x10, 0x17, 0x11, 0x11, 0x0f],
+        'H' => [0x11, 0x11, 0x11, 0x1f, 0x11, 0x11, 0x11],
+        'I' => [0x0e, 0x04, 0x04, 0x04, 0x04, 0x04, 0x0e],
+        'J' => [0x07, 0x02, 0x02, 0x02, 0x02, 0x12, 0x0c],
+        'K' => [0x11, 0x12, 0x14, 0x18, 0x14, 0x12, 0x11],
+        'L' => [0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x1f],
+        'M' => [0x11, 0x1b, 0x15, 0x15, 0x11, 0x11, 0x11],
+        'N' => [0x11, 0x11, 0x19, 0x15, 0x13, 0x11, 0x11],
+        'O' => [0x0e, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0e],
+        'P' => [0x1e, 0x11, 0x11, 0x1e, 0x10, 0x10, 0x10],
+        'Q' => [0x0e, 0x11, 0x11, 0x11, 0x15, 0x12, 0x0d],
+        'R' => [0x1e, 0x11, 0x11, 0x1e, 0x14, 0x12, 0x11],
+        'S' => [0x0f, 0x10, 0x10, 0x0e, 0x01, 0x01, 0x1e],
+        'T' => [0x1f, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04],
+        'U' => [0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0e],
+        'V' => [0x11, 0x11, 0x11, 0x11, 0x11, 0x0a, 0x04],
+        'W' => [0x11, 0x11, 0x11, 0x15, 0x15, 0x15, 0x0a],
+        'X' => [0x11, 0x11, 0x0a, 0x04, 0x0a, 0x11, 0x11],
+        'Y' => [0x11, 0x11, 0x11, 0x0a, 0x04, 0x04, 0x04],
+        'Z' => [0x1f, 0x01, 0x02, 0x04, 0x08, 0x10, 0x1f],
+        '0' => [0x0e, 0x11, 0x13, 0x15, 0x19, 0x11, 0x0e],
+        '1' => [0x04, 0x0c, 0x04, 0x04, 0x04, 0x04, 0x0e],
+        '2' => [0x0e, 0x11, 0x01, 0x02, 0x04, 0x08, 0x1f],
+        '3' => [0x1f, 0x02, 0x04, 0x02, 0x01, 0x11, 0x0e],
+        '4' => [0x02, 0x06, 0x0a, 0x12, 0x1f, 0x02, 0x02],
+        '5' => [0x1f, 0x10, 0x1e, 0x01, 0x01, 0x11, 0x0e],
+        '6' => [0x06, 0x08, 0x10, 0x1e, 0x11, 0x11, 0x0e],
+        '7' => [0x1f, 0x01, 0x02, 0x04, 0x08, 0x08, 0x08],
+        '8' => [0x0e, 0x11, 0x11, 0x0e, 0x11, 0x11, 0x0e],
+        '9' => [0x0e, 0x11, 0x11, 0x0f, 0x01, 0x02, 0x0c],
+        ' ' => [0x00; 7],
+        ':' => [0x00, 0x0c, 0x0c, 0x00, 0x0c, 0x0c, 0x00],
+        '-' => [0x00, 0x00, 0x00, 0x1f, 0x00, 0x00, 0x00],
+        '+' => [0x00, 0x04, 0x04, 0x1f, 0x04, 0x04, 0x00],
+        '=' => [0x00, 0x00, 0x1f, 0x00, 0x1f, 0x00, 0x00],
+        '/' => [0x01, 0x01, 0x02, 0x04, 0x08, 0x10, 0x10],
+        '.' => [0x00, 0x00, 0x00, 0x00, 0x00, 0x0c, 0x0c],
+        ',' => [0x00, 0x00, 0x00, 0x00, 0x0c, 0x04, 0x08],
+        '_' => [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x1f],
+        '(' => [0x02, 0x04, 0x08, 0x08, 0x08, 0x04, 0x02],
+        ')' => [0x08, 0x04, 0x02, 0x02, 0x02, 0x04, 0x08],
+        '#' => [0x0a, 0x0a, 0x1f, 0x0a, 0x1f, 0x0a, 0x0a],
+        _ => [0x0e, 0x11, 0x01, 0x02, 0x04, 0x00, 0x04],
+    }
+}
+
+/// Text lines describing the frame.
+fn card_lines(header: &Header) -> Vec<String> {
+    let file_info = &header.file_info;
+    let image_info = &header.image_info;
+    let origination_info = &header.origination_info;
+    let channel = &image_info.channel[0];
+
+    let mut lines = vec![
+        format!("FILE: {}", file_info.filename),
+        format!(
+            "SIZE: {}X{} {} ELEMENTS {} BIT",
+            channel.pixels_per_line,
+            channel.lines_per_element,
+            image_info.number_of_elements,
+            channel.bit_depth
+        ),
+    ];
+    // Industry sections left blank by their writer hold no edge code
+    let film_info = header
+        .film_info
+        .as_ref()
+        .filter(|film_info| !film_info.is_blank());
+    if let Some(film_info) = film_info {
+        lines.push(format!("KEYKODE: {}", film_info.keycode()));
+        lines.push(match film_info.frame_rate {
+            Some(frame_rate) => {
+                format!("FRAME: {} AT {} FPS", film_info.frame_position, frame_rate)
+            }
+            None => format!("FRAME: {}", film_info.frame_position),
+        });
+        lines.push(format!("SLATE: {}", film_info.slate_info));
+    }
+    lines.push(format!(
+        "CREATED: {} {}",
+        file_info.creation_date, file_info.creation_time
+    ));
+    lines.push(format!(
+        "SOURCE: {} {}",
+        origination_info.source_date, origination_info.source_time
+    ));
+    lines
+}
+
+/// Renders the header metadata as a single-channel 8-bit plane of
+/// `size` (width, height) pixels, with text at 255 over 0, suitable for
+/// overlaying on a frame.
+///
+/// The plane holds display values, tagged as sRGB, rather than printing
+/// density code values.
+pub fn metadata_overlay(header: &Header, size: (u32, u32)) -> DecodedImage {
+    let (width, height) = size;
+    let lines = card_lines(header);
+    let columns = lines
+        .iter()
+        .map(|line| line.chars().count())
+        .max()
+        .unwrap_or(1) as u32;
+
+    // The largest integer scale fitting every line, with a one cell margin
+    let scale = (width / (CELL_WIDTH * (columns + 2)))
+        .min(height / (CELL_HEIGHT * (lines.len() as u32 + 2)))
+        .max(1);
+    let margin_x = CELL_WIDTH * scale;
+    let margin_y = CELL_HEIGHT * scale;
+
+    let mut plane = vec![0u8; width as usize * height as usize];
+    for (row, line) in lines.iter().enumerate() {
+        let top = margin_y + row as u32 * CELL_HEIGHT * scale;
+        for (column, c) in line.chars().enumerate() {
+            let left = margin_x + column as u32 * CELL_WIDTH * scale;
+            for (gy, bits) in glyph(c).iter().enumerate() {
+                for gx in 0..GLYPH_WIDTH {
+                    if bits & (0x10 >> gx) == 0 {
+                        continue;
+                    }
+                    for dy in 0..scale {
+                        for dx in 0..scale {
+                            let x = left + gx * scale + dx;
+                            let y = top + gy as u32 * scale + dy;
+                            if x < width && y < height {
+                                plane[y as usize * width as usize + x as usize] = 255;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    DecodedImage {
+        width,
+        height,
+        channels: 1,
+        bit_depth: 8,
+        samples: Samples::U8(plane),
+        color_space: ColorSpace::Srgb,
+    }
+}
+
+/// Renders the header metadata (file name, dimensions, keykode, slate and
+/// dates) as an 8-bit RGB card of `size` (width, height) pixels, white
+/// text over black and tagged as sRGB, to be used as a slate frame of
+/// review movies.
+pub fn metadata_card(header: &Header, size: (u32, u32)) -> DecodedImage {
+    let overlay = metadata_overlay(header, size);
+    let samples = match overlay.samples {
+        Samples::U8(plane) => plane.iter().flat_map(|v| [*v; 3]).collect(),
+        _ => unreachable!("overlays are 8-bit"),
+    };
+    DecodedImage {
+        channels: 3,
+        samples: Samples::U8(samples),
+        ..overlay
+    }
+}
@@ -0,0 +1,159 @@
+//! ASC color decision lists.
+
+use crate::color::REC709_LUMA;
+use crate::image::{DecodedImage, Samples};
+use crate::CineonError;
+
+/// ASC CDL grade, applied as slope, offset and power per channel, then
+/// saturation
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cdl {
+    /// Identifier of the correction, as found in `.ccc` collections
+    pub id: Option<String>,
+    /// Per channel multiplier
+    pub slope: [f32; 3],
+    /// Per channel addend
+    pub offset: [f32; 3],
+    /// Per channel exponent
+    pub power: [f32; 3],
+    /// Saturation, 1.0 leaving it untouched
+    pub saturation: f32,
+}
+
+impl Default for Cdl {
+    /// Grade leaving images untouched.
+    fn default() -> Self {
+        Self {
+            id: None,
+            slope: [1.; 3],
+            offset: [0.; 3],
+            power: [1.; 3],
+            saturation: 1.,
+        }
+    }
+}
+
+/// Offset of the first start tag of `tag` elements in `xml`, skipping tags
+/// that merely begin with the same name.
+fn start_tag(xml: &str, tag: &str) -> Option<usize> {
+    let pattern = format!("<{}", tag);
+    let mut offset = 0;
+    while let Some(found) = xml[offset..].find(&pattern) {
+        let start = offset + found;
+        let next = xml[start + pattern.len()..].chars().next();
+        if next.is_some_and(|c| c == '>' || c.is_whitespace()) {
+            return Some(start);
+        }
+        offset = start + pattern.len();
+    }
+    None
+}
+
+/// Text of the first `<tag>` element of `xml`, if any.
+fn element<'a>(xml: &'a str, tag: &str) -> Option<&'a str> {
+    let start = xml.find(&format!("<{}>", tag))? + tag.len() + 2;
+    let end = xml[start..].find(&format!("</{}>", tag))? + start;
+    Some(xml[start..end].trim())
+}
+
+/// Value of `attribute` in the start tag opening `xml`, if any.
+fn attribute(xml: &str, attribute: &str) -> Option<String> {
+    let tag = &xml[..xml.find('>')?];
+    let start = tag.find(&format!("{}=\"", attribute))? + attribute.len() + 2;
+    let end = tag[start..].find('"')? + start;
+    Some(tag[start..end].to_owned())
+}
+
+/// Reals separated by whitespace.
+fn numbers<const N: usize>(text: &str) -> Result<[f32; N], CineonError> {
+    let mut values = [0.; N];
+    let mut fields = text.split_whitespace();
+    for value in values.iter_mut() {
+        *value = fields
+            .next()
+            .and_then(|field| field.parse().ok())
+            .ok_or(CineonError::ParserError)?;
+    }
+    match fields.next() {
+        Some(_) => Err(CineonError::ParserError),
+        None => Ok(values),
+    }
+}
+
+impl Cdl {
+    /// Reads every `<ColorCorrection>` of a `.cdl`, `.cc` or `.ccc` file,
+    /// in order. Missing nodes keep the values of [`Cdl::default`].
+    pub fn parse_xml(xml: &str) -> Result<Vec<Self>, CineonError> {
+        let mut corrections = Vec::new();
+        let mut rest = xml;
+        while let Some(start) = start_tag(rest, "ColorCorrection") {
+            let end = rest[start..]
+                .find("</ColorCorrection>")
+                .ok_or(CineonError::ParserError)?
+                + start;
+            let correction = &rest[start..end];
+            let mut cdl = Self {
+                id: attribute(correction, "id"),
+                ..Self::default()
+            };
+            if let Some(sop) = element(correction, "SOPNode") {
+                if let Some(slope) = element(sop, "Slope") {
+                    cdl.slope = numbers(slope)?;
+                }
+                if let Some(offset) = element(sop, "Offset") {
+                    cdl.offset = numbers(offset)?;
+                }
+                if let Some(power) = element(sop, "Power") {
+                    cdl.power = numbers(power)?;
+                }
+            }
+            if let Some(sat) = element(correction, "SatNode") {
+                if let Some(saturation) = element(sat, "Saturation") {
+                    cdl.saturation = numbers::<1>(saturation)?[0];
+                }
+            }
+            corrections.push(cdl);
+            rest = &rest[end..];
+        }
+        if corrections.is_empty() {
+            return Err(CineonError::ParserError);
+        }
+        Ok(corrections)
+    }
+
+    /// Grade of an RGB triplet, negative values being clamped to zero
+    /// before the power.
+    pub fn grade(&self, rgb: [f32; 3]) -> [f32; 3] {
+        let mut graded = [0.; 3];
+        for k in 0..3 {
+            graded[k] = (rgb[k] * self.slope[k] + self.offset[k])
+                .max(0.)
+                .powf(self.power[k]);
+        }
+        let luma: f32 = graded
+            .iter()
+            .zip(REC709_LUMA.iter())
+            .map(|(v, w)| v * *w as f32)
+            .sum();
+        graded.map(|value| luma + self.saturation * (value - luma))
+    }
+
+    /// Applies the grade to every pixel of an RGB or RGBA image of reals,
+    /// such as one decoded to float, mattes being kept as they are.
+    pub fn apply(&self, image: &DecodedImage) -> Result<DecodedImage, CineonError> {
+        let channels = image.channels as usize;
+        let samples = match &image.samples {
+            Samples::F32(samples) if channels == 3 || channels == 4 => samples,
+            _ => return Err(CineonError::UnsupportedFormat),
+        };
+        let mut output = samples.clone();
+        for pixel in output.chunks_exact_mut(channels) {
+            let rgb = self.grade([pixel[0], pixel[1], pixel[2]]);
+            pixel[..3].copy_from_slice(&rgb);
+        }
+        Ok(DecodedImage {
+            samples: Samples::F32(output),
+            ..*image
+        })
+    }
+}
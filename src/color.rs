@@ -0,0 +1,459 @@
+//! Conversions of code values for display.
+
+use crate::header::{Designator, ImageInfo, Metric};
+use crate::CineonError;
+
+/// Encoding of the samples of an image, updated by every conversion so
+/// that transforms are neither skipped nor applied twice
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum ColorSpace {
+    /// Printing density code values, as stored in cineon files
+    #[default]
+    CineonLog,
+    /// Printing density in linear light
+    LinearPd,
+    /// Linear light of linear or video elements
+    Linear,
+    /// ITU-R BT.709 display encoding
+    Rec709,
+    /// sRGB display encoding
+    Srgb,
+    /// ACES2065-1 scene linear, with the AP0 primaries
+    Aces,
+    /// ACEScg scene linear, with the AP1 primaries
+    AcesCg,
+}
+
+/// Encoding of the code values of an element, as told by its designator
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Transfer {
+    /// Logarithmic printing density
+    PrintingDensity,
+    /// Linear light
+    Linear,
+    /// ITU-R BT.709 video encoding
+    Video,
+}
+
+impl Transfer {
+    /// Transfer of an element with `metric` and `designator`, telling
+    /// video samples in the universal metric. Anything else, including
+    /// vendor specific metrics, is taken as printing density.
+    pub fn from_designator(metric: Metric, designator: Designator) -> Self {
+        match (metric, designator) {
+            (
+                Metric::Universal,
+                Designator::VideoRed | Designator::VideoGreen | Designator::VideoBlue,
+            ) => Self::Video,
+            _ => Self::PrintingDensity,
+        }
+    }
+
+    /// Linear light of a code value normalized from 0.0 to 1.0, printing
+    /// densities being converted with `log`.
+    pub(crate) fn to_linear(self, value: f32, log: &LogConversion) -> f32 {
+        match self {
+            Self::PrintingDensity => log.linear(value * 1023.),
+            Self::Linear => value,
+            Self::Video if value < 0.081 => value / 4.5,
+            Self::Video => ((value + 0.099) / 1.099).powf(1. / 0.45),
+        }
+    }
+}
+
+/// Printing density per 10-bit code value
+pub(crate) const DENSITY_PER_CODE: f64 = 0.002;
+/// Gamma of the negative film
+pub(crate) const NEGATIVE_GAMMA: f64 = 0.6;
+
+/// Parameters of the conversion of printing density code values to linear
+/// light, as tweaked when grading scans
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[non_exhaustive]
+pub struct LogConversion {
+    /// 10-bit code value mapped to 0.0
+    pub reference_black: f32,
+    /// 10-bit code value mapped to 1.0
+    pub reference_white: f32,
+    /// Display gamma, contrast growing with it
+    pub display_gamma: f32,
+    /// Width in 10-bit code values of the knee below the reference white
+    /// rolling off highlights, none when 0
+    pub soft_clip: f32,
+}
+
+impl Default for LogConversion {
+    /// Kodak reference points, without soft clip.
+    fn default() -> Self {
+        Self {
+            reference_black: 95.,
+            reference_white: 685.,
+            display_gamma: 1.7,
+            soft_clip: 0.,
+        }
+    }
+}
+
+impl LogConversion {
+    /// Relative exposure of a 10-bit code value, 1.0 at the reference white.
+    fn exposure(&self, code: f32) -> f32 {
+        let slope = (DENSITY_PER_CODE / NEGATIVE_GAMMA) as f32 * self.display_gamma / 1.7;
+        10f32.powf((code - self.reference_white) * slope)
+    }
+
+    /// Linear light of a 10-bit printing density code value, with the
+    /// reference black at 0.0 and the reference white at 1.0.
+    pub(crate) fn linear(&self, code: f32) -> f32 {
+        let black = self.exposure(self.reference_black);
+        let linear = |code: f32| (self.exposure(code) - black) / (1. - black);
+        let breakpoint = self.reference_white - self.soft_clip;
+        if self.soft_clip <= 0. || code <= breakpoint {
+            return linear(code);
+        }
+        // Power curve joining the breakpoint to the reference white
+        let power = self.soft_clip / 100.;
+        let knee_offset = linear(breakpoint);
+        let knee_gain = (1. - knee_offset) / (5. * self.soft_clip).powf(power);
+        knee_offset + (5. * (code - breakpoint)).powf(power) * knee_gain
+    }
+
+    /// 10-bit printing density code value of linear light, the inverse of
+    /// [`LogConversion::linear`], clamped to the 10-bit range.
+    pub(crate) fn log(&self, linear: f32) -> f32 {
+        let black = self.exposure(self.reference_black);
+        let slope = (DENSITY_PER_CODE / NEGATIVE_GAMMA) as f32 * self.display_gamma / 1.7;
+        let log =
+            |linear: f32| self.reference_white + (linear * (1. - black) + black).log10() / slope;
+        let breakpoint = self.reference_white - self.soft_clip;
+        let code = if self.soft_clip <= 0. || linear <= self.linear(breakpoint) {
+            log(linear)
+        } else {
+            let power = self.soft_clip / 100.;
+            let knee_offset = self.linear(breakpoint);
+            let knee_gain = (1. - knee_offset) / (5. * self.soft_clip).powf(power);
+            breakpoint + ((linear - knee_offset) / knee_gain).powf(1. / power) / 5.
+        };
+        // Non-positive exposures give NaN or negative infinity
+        if code.is_nan() {
+            0.
+        } else {
+            code.clamp(0., 1023.)
+        }
+    }
+}
+
+/// sRGB encoding of linear light, clamped to the display range.
+pub(crate) fn linear_to_srgb(linear: f32) -> f32 {
+    let linear = linear.clamp(0., 1.);
+    if linear <= 0.003_130_8 {
+        linear * 12.92
+    } else {
+        1.055 * linear.powf(1. / 2.4) - 0.055
+    }
+}
+
+/// 3x3 matrix, row by row
+pub type Matrix = [[f64; 3]; 3];
+
+/// Product of `a` and `b`.
+pub(crate) fn multiply(a: &Matrix, b: &Matrix) -> Matrix {
+    let mut product = [[0.; 3]; 3];
+    for (row, a_row) in product.iter_mut().zip(a.iter()) {
+        for (column, value) in row.iter_mut().enumerate() {
+            *value = (0..3).map(|k| a_row[k] * b[k][column]).sum();
+        }
+    }
+    product
+}
+
+/// Product of `matrix` and the column vector `vector`.
+pub(crate) fn transform(matrix: &Matrix, vector: [f64; 3]) -> [f64; 3] {
+    let mut result = [0.; 3];
+    for (value, row) in result.iter_mut().zip(matrix.iter()) {
+        *value = (0..3).map(|k| row[k] * vector[k]).sum();
+    }
+    result
+}
+
+/// Inverse of `matrix`, unless it is singular.
+pub(crate) fn invert(matrix: &Matrix) -> Option<Matrix> {
+    let m = matrix;
+    let cofactor =
+        |r0: usize, r1: usize, c0: usize, c1: usize| m[r0][c0] * m[r1][c1] - m[r0][c1] * m[r1][c0];
+    let adjugate = [
+        [
+            cofactor(1, 2, 1, 2),
+            -cofactor(0, 2, 1, 2),
+            cofactor(0, 1, 1, 2),
+        ],
+        [
+            -cofactor(1, 2, 0, 2),
+            cofactor(0, 2, 0, 2),
+            -cofactor(0, 1, 0, 2),
+        ],
+        [
+            cofactor(1, 2, 0, 1),
+            -cofactor(0, 2, 0, 1),
+            cofactor(0, 1, 0, 1),
+        ],
+    ];
+    let determinant = (0..3).map(|k| m[0][k] * adjugate[k][0]).sum::<f64>();
+    if !determinant.is_normal() {
+        return None;
+    }
+    Some(adjugate.map(|row| row.map(|value| value / determinant)))
+}
+
+/// XYZ tristimulus values of the chromaticity `xy`, with a luminance of 1.
+fn xy_to_xyz([x, y]: [f64; 2]) -> [f64; 3] {
+    [x / y, 1., (1. - x - y) / y]
+}
+
+/// Chromaticity of the CIE standard illuminant D65, the white of Rec. 709
+/// and sRGB displays
+pub const D65: [f64; 2] = [0.3127, 0.3290];
+/// Chromaticities of the red, green and blue primaries of Rec. 709 and
+/// sRGB
+pub const REC709_PRIMARIES: [[f64; 2]; 3] = [[0.64, 0.33], [0.30, 0.60], [0.15, 0.06]];
+/// Chromaticity of the white point of ACES
+pub const D60: [f64; 2] = [0.32168, 0.33767];
+/// Chromaticities of the red, green and blue primaries of ACES2065-1
+pub const AP0_PRIMARIES: [[f64; 2]; 3] = [[0.7347, 0.2653], [0.0, 1.0], [0.0001, -0.077]];
+/// Chromaticities of the red, green and blue primaries of ACEScg
+pub const AP1_PRIMARIES: [[f64; 2]; 3] = [[0.713, 0.293], [0.165, 0.830], [0.128, 0.044]];
+
+/// ACES encoding of scene linear reals
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AcesSpace {
+    /// ACES2065-1, with the AP0 primaries, for interchange and archival
+    Aces2065,
+    /// ACEScg, with the AP1 primaries, for rendering and compositing
+    AcesCg,
+}
+
+/// Chromatic adaptation transform, modelling the cone responses white
+/// points are balanced in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Adaptation {
+    /// Bradford transform
+    Bradford,
+    /// CAT02 transform of CIECAM02
+    Cat02,
+}
+
+impl Adaptation {
+    /// Matrix converting XYZ values to cone responses.
+    fn cone_response(self) -> Matrix {
+        match self {
+            Self::Bradford => [
+                [0.8951, 0.2664, -0.1614],
+                [-0.7502, 1.7135, 0.0367],
+                [0.0389, -0.0685, 1.0296],
+            ],
+            Self::Cat02 => [
+                [0.7328, 0.4296, -0.1624],
+                [-0.7036, 1.6975, 0.0061],
+                [0.0030, 0.0136, 0.9834],
+            ],
+        }
+    }
+
+    /// Matrix converting XYZ values seen under the `source` white point to
+    /// the ones matching them under the `target` white point.
+    pub fn matrix(self, source: [f64; 2], target: [f64; 2]) -> Matrix {
+        let cone_response = self.cone_response();
+        let (source, target) = (
+            transform(&cone_response, xy_to_xyz(source)),
+            transform(&cone_response, xy_to_xyz(target)),
+        );
+        let mut scale = [[0.; 3]; 3];
+        for k in 0..3 {
+            scale[k][k] = target[k] / source[k];
+        }
+        // The cone response matrices are not singular
+        let inverse = invert(&cone_response).unwrap_or(cone_response);
+        multiply(&inverse, &multiply(&scale, &cone_response))
+    }
+}
+
+/// White point and primaries of the RGB channels of an image
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Colorimetry {
+    /// Chromaticity of the white point
+    pub white_point: [f64; 2],
+    /// Chromaticities of the red, green and blue primaries
+    pub primaries: [[f64; 2]; 3],
+    rgb_to_xyz: Matrix,
+    xyz_to_rgb: Matrix,
+}
+
+impl Colorimetry {
+    /// Colorimetry of `white_point` and the red, green and blue
+    /// `primaries`, given as CIE 1931 xy chromaticities.
+    ///
+    /// The white point must lie within the unit triangle with a positive
+    /// y. The primaries may be imaginary, as the ones of ACES2065-1, but
+    /// must have a finite, non-zero y and must not be collinear.
+    pub fn new(white_point: [f64; 2], primaries: [[f64; 2]; 3]) -> Result<Self, CineonError> {
+        let white = |[x, y]: [f64; 2]| x >= 0. && y > 0. && x + y <= 1.;
+        let primary = |[x, y]: [f64; 2]| x.is_finite() && y.is_normal();
+        if !white(white_point) || !primaries.iter().all(|xy| primary(*xy)) {
+            return Err(CineonError::InvalidColorimetry);
+        }
+        // Columns are the primaries, scaled so that they add up to white
+        let [r, g, b] = primaries.map(xy_to_xyz);
+        let primaries_matrix = [[r[0], g[0], b[0]], [r[1], g[1], b[1]], [r[2], g[2], b[2]]];
+        let scales = transform(
+            &invert(&primaries_matrix).ok_or(CineonError::InvalidColorimetry)?,
+            xy_to_xyz(white_point),
+        );
+        let rgb_to_xyz = primaries_matrix.map(|row| [0, 1, 2].map(|k| row[k] * scales[k]));
+        let xyz_to_rgb = invert(&rgb_to_xyz).ok_or(CineonError::InvalidColorimetry)?;
+        Ok(Self {
+            white_point,
+            primaries,
+            rgb_to_xyz,
+            xyz_to_rgb,
+        })
+    }
+
+    /// Colorimetry stored in the image information of a header, which
+    /// is invalid when any chromaticity is undefined.
+    pub fn from_image_info(image_info: &ImageInfo) -> Result<Self, CineonError> {
+        let xy = |xy: [Option<f32>; 2]| match xy {
+            [Some(x), Some(y)] => Ok([x as f64, y as f64]),
+            _ => Err(CineonError::InvalidColorimetry),
+        };
+        Self::new(
+            xy(image_info.white_point)?,
+            [
+                xy(image_info.red_primary)?,
+                xy(image_info.green_primary)?,
+                xy(image_info.blue_primary)?,
+            ],
+        )
+    }
+
+    /// Matrix converting linear RGB values to CIE XYZ, the white point
+    /// having a luminance of 1.
+    pub fn rgb_to_xyz(&self) -> Matrix {
+        self.rgb_to_xyz
+    }
+
+    /// Matrix converting CIE XYZ values to linear RGB.
+    pub fn xyz_to_rgb(&self) -> Matrix {
+        self.xyz_to_rgb
+    }
+
+    /// Matrix converting linear RGB values to CIE XYZ, adapted with
+    /// `adaptation` from the white point of the image to `target`, such
+    /// as [`D65`] for display or [`D60`] for ACES.
+    pub fn rgb_to_xyz_adapted(&self, target: [f64; 2], adaptation: Adaptation) -> Matrix {
+        multiply(
+            &adaptation.matrix(self.white_point, target),
+            &self.rgb_to_xyz,
+        )
+    }
+}
+
+/// How colors outside the gamut of the target primaries, which have
+/// negative components, are brought back within it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum GamutMapping {
+    /// Clamps every component on its own, shifting hues
+    #[default]
+    Clip,
+    /// Moves out of gamut colors toward the achromatic color of the same
+    /// luma until they fit, keeping their hue and luma
+    Desaturate,
+    /// Compresses the distance of every color from the achromatic axis
+    /// beyond a threshold, as the ACES reference gamut compression does,
+    /// leaving colors well inside the gamut untouched
+    Compress,
+}
+
+/// Rec. 709 luma weights
+pub(crate) const REC709_LUMA: [f64; 3] = [0.2126, 0.7152, 0.0722];
+/// Distance from the achromatic axis compression starts at
+const COMPRESSION_THRESHOLD: f64 = 0.8;
+/// Distance from the achromatic axis compressed to the gamut boundary
+const COMPRESSION_LIMIT: f64 = 1.2;
+/// Steepness of the compression curve
+const COMPRESSION_POWER: f64 = 1.2;
+
+impl GamutMapping {
+    /// Linear RGB of `rgb` within the gamut, apart from highlights above
+    /// 1.0 which are left to the transfer function.
+    pub(crate) fn map(self, rgb: [f64; 3]) -> [f64; 3] {
+        match self {
+            Self::Clip => rgb.map(|value| value.max(0.)),
+            Self::Desaturate => {
+                let luma: f64 = rgb.iter().zip(REC709_LUMA.iter()).map(|(v, w)| v * w).sum();
+                let min = rgb.iter().copied().fold(f64::INFINITY, f64::min);
+                if min >= 0. || luma <= 0. {
+                    return rgb.map(|value| value.max(0.));
+                }
+                let amount = luma / (luma - min);
+                rgb.map(|value| (luma + amount * (value - luma)).max(0.))
+            }
+            Self::Compress => {
+                let achromatic = rgb.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+                if achromatic <= 0. {
+                    return [0.; 3];
+                }
+                let (threshold, limit, power) =
+                    (COMPRESSION_THRESHOLD, COMPRESSION_LIMIT, COMPRESSION_POWER);
+                // Scale taking the limit to a distance of 1.0
+                let scale = (limit - threshold)
+                    / (((1. - threshold) / (limit - threshold)).powf(-power) - 1.).powf(1. / power);
+                rgb.map(|value| {
+                    let distance = (achromatic - value) / achromatic;
+                    let distance = if distance < threshold {
+                        distance
+                    } else {
+                        let excess = (distance - threshold) / scale;
+                        threshold + scale * excess / (1. + excess.powf(power)).powf(1. / power)
+                    };
+                    (achromatic - distance * achromatic).max(0.)
+                })
+            }
+        }
+    }
+}
+
+/// Filmic tone mapping of linear light, rolling highlights off toward 1.0,
+/// with Krzysztof Narkowicz's fit of the ACES curve.
+pub(crate) fn filmic(linear: f32) -> f32 {
+    let x = linear.max(0.);
+    (x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14)
+}
+
+/// ITU-R BT.709 encoding of linear light, clamped to the display range.
+pub(crate) fn rec709_oetf(linear: f64) -> f64 {
+    let linear = linear.clamp(0., 1.);
+    if linear < 0.018 {
+        4.5 * linear
+    } else {
+        1.099 * linear.powf(0.45) - 0.099
+    }
+}
+
+/// 4x4 Bayer matrix, as thresholds from 0.0 to 1.0
+const BAYER: [[f32; 4]; 4] = [
+    [0. / 16., 8. / 16., 2. / 16., 10. / 16.],
+    [12. / 16., 4. / 16., 14. / 16., 6. / 16.],
+    [3. / 16., 11. / 16., 1. / 16., 9. / 16.],
+    [15. / 16., 7. / 16., 13. / 16., 5. / 16.],
+];
+
+/// Quantizes a value from 0.0 to 1.0 to 8 bits, with ordered dithering
+/// over the pixel at (`x`, `y`).
+#[inline(always)]
+pub(crate) fn dither8(value: f32, x: usize, y: usize) -> u8 {
+    let threshold = BAYER[y % 4][x % 4] + 1. / 32.;
+    (value * 255. + threshold).floor().clamp(0., 255.) as u8
+}
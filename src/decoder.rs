@@ -0,0 +1,1053 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use crate::bits::ByteOrder;
+use crate::color::{self, ColorSpace, LogConversion, Transfer};
+use crate::header::{Header, Orientation};
+use crate::image::{DecodedImage, Layout, Region, Samples, Weights};
+use crate::unpack::DataLayout;
+use crate::CineonError;
+
+/// Transform applied to every sample while unpacking
+#[derive(Debug, Clone, PartialEq)]
+pub enum SampleTransform {
+    /// Multiplies code values by `gain`, then adds `offset`
+    GainOffset {
+        /// Multiplier
+        gain: f32,
+        /// Addend
+        offset: f32,
+    },
+    /// Replaces each code value with the entry it indexes, code values
+    /// beyond the table taking the last entry
+    Lut(Vec<u16>),
+    /// Replaces each code value with the entry it indexes in the table of
+    /// its element, elements beyond the tables taking the last table
+    ChannelLuts(Vec<Vec<u16>>),
+}
+
+impl SampleTransform {
+    /// Reads a 1D LUT of integer entries, such as a scanner shaper LUT.
+    ///
+    /// Discreet `.lut` files, starting with a `LUT: <channels> <length>`
+    /// line and listing the tables one after the other, become per element
+    /// tables, as do CSV or whitespace separated files with a column per
+    /// element. Single column files become a table shared by all elements.
+    /// Blank lines and lines starting with `#` are skipped.
+    pub fn parse_lut(text: &str) -> Result<Self, CineonError> {
+        let mut lines = text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .peekable();
+        let number = |field: &str| field.parse::<u16>().map_err(|_| CineonError::ParserError);
+        let fields = |line: &str| -> Result<Vec<u16>, CineonError> {
+            line.split(|c: char| c == ',' || c.is_whitespace())
+                .filter(|field| !field.is_empty())
+                .map(number)
+                .collect()
+        };
+
+        let mut tables: Vec<Vec<u16>> = Vec::new();
+        if let Some(header) = lines.peek().and_then(|line| line.strip_prefix("LUT:")) {
+            let sizes: Vec<usize> = header
+                .split_whitespace()
+                .map(|field| field.parse().map_err(|_| CineonError::ParserError))
+                .collect::<Result<_, _>>()?;
+            let (channels, length) = match sizes[..] {
+                [channels, length] if channels > 0 && length > 0 => (channels, length),
+                _ => return Err(CineonError::ParserError),
+            };
+            lines.next();
+            let entries = lines.map(fields).collect::<Result<Vec<_>, _>>()?.concat();
+            if entries.len() != channels * length {
+                return Err(CineonError::ParserError);
+            }
+            tables = entries.chunks(length).map(<[u16]>::to_vec).collect();
+        } else {
+            for line in lines {
+                let row = fields(line)?;
+                if tables.is_empty() {
+                    tables = vec![Vec::new(); row.len()];
+                }
+                if row.len() != tables.len() {
+                    return Err(CineonError::ParserError);
+                }
+                for (table, entry) in tables.iter_mut().zip(row) {
+                    table.push(entry);
+                }
+            }
+        }
+        match tables.len() {
+            0 => Err(CineonError::ParserError),
+            1 => Ok(Self::Lut(tables.remove(0))),
+            _ => Ok(Self::ChannelLuts(tables)),
+        }
+    }
+
+    /// Transforms `code` of `element`, the result being clamped to the
+    /// code range up to `max_code`.
+    fn apply(&self, element: usize, code: u32, max_code: u32) -> u32 {
+        let lookup = |lut: &[u16]| match lut.get(code as usize).or_else(|| lut.last()) {
+            Some(value) => (*value as u32).min(max_code),
+            None => code,
+        };
+        match self {
+            Self::GainOffset { gain, offset } => (code as f32 * gain + offset)
+                .round()
+                .clamp(0., max_code as f32) as u32,
+            Self::Lut(lut) => lookup(lut),
+            Self::ChannelLuts(luts) => match luts.get(element).or_else(|| luts.last()) {
+                Some(lut) => lookup(lut),
+                None => code,
+            },
+        }
+    }
+}
+
+/// Range integer code values are normalized to, the reference code values
+/// of each element becoming its bounds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Normalization {
+    /// Reals from 0.0 to 1.0, as 32-bit samples
+    Unit,
+    /// Integers from 0 to 65535, as 16-bit samples
+    FullRange,
+}
+
+/// How the pixels of a block are reduced to a proxy pixel
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProxyFilter {
+    /// Keeps the top left pixel, skipping the other rows and columns
+    Nearest,
+    /// Averages every pixel of the block
+    Box,
+}
+
+/// Flag shared with a decoder to abort it from another thread
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    /// Creates a token that has not been cancelled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Asks every decoder holding the token to stop at the next row.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    /// Whether the token has been cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Decoder of the image data of a cineon image.
+///
+/// The elements of the image become the channels of the decoded pixels, so
+/// they must share the same dimensions and bit depth.
+pub struct Decoder<'a> {
+    header: Header,
+    data: &'a [u8],
+    data_layout: DataLayout,
+    layout: Layout,
+    normalize_orientation: bool,
+    invert_negative: bool,
+    transform: Option<SampleTransform>,
+    normalization: Option<Normalization>,
+    /// Per element reference code values, as offset and scale mapping them
+    /// to the 0.0 to 1.0 range
+    references: Vec<(f32, f32)>,
+    /// Per element tables mapping code values to decoded samples, empty
+    /// when samples are kept as they are
+    luts: Vec<Vec<u16>>,
+    cancel: Option<CancelToken>,
+    log_conversion: LogConversion,
+}
+
+impl<'a> Decoder<'a> {
+    pub(crate) fn new(
+        header: Header,
+        data: &'a [u8],
+        byte_order: ByteOrder,
+    ) -> Result<Self, CineonError> {
+        let layout = Layout::from_header(&header)?;
+        let data_layout = DataLayout::new(&header, byte_order)?;
+        data_layout.check(data)?;
+        Ok(Self {
+            header,
+            data,
+            data_layout,
+            layout,
+            normalize_orientation: false,
+            invert_negative: false,
+            transform: None,
+            normalization: None,
+            references: Vec::new(),
+            luts: Vec::new(),
+            cancel: None,
+            log_conversion: LogConversion::default(),
+        })
+    }
+
+    /// Sets a token checked before every row is unpacked, decoding failing
+    /// with [`CineonError::Cancelled`] once it is cancelled.
+    pub fn cancel_token(mut self, token: Option<CancelToken>) -> Self {
+        self.cancel = token;
+        self
+    }
+
+    /// Sets the parameters printing densities are converted to linear light
+    /// with, for display and float decoding.
+    pub fn log_conversion(mut self, log_conversion: LogConversion) -> Self {
+        self.log_conversion = log_conversion;
+        self
+    }
+
+    /// Fails when decoding has been cancelled.
+    #[inline(always)]
+    fn check_cancelled(&self) -> Result<(), CineonError> {
+        match &self.cancel {
+            Some(token) if token.is_cancelled() => Err(CineonError::Cancelled),
+            _ => Ok(()),
+        }
+    }
+
+    /// Whether samples of negative images, as flagged by the image sense,
+    /// are inverted around the reference code values of their element, so
+    /// that they can be viewed as positives.
+    ///
+    /// Elements without valid reference code values are inverted around
+    /// their full code range.
+    pub fn invert_negative(mut self, invert: bool) -> Self {
+        self.invert_negative = invert;
+        self.update_luts();
+        self
+    }
+
+    /// Sets a transform applied to every sample in the same pass that
+    /// unpacks it, after any inversion, with results clamped to the range
+    /// of the sample type.
+    ///
+    /// Reals are left as they are.
+    pub fn transform(mut self, transform: Option<SampleTransform>) -> Self {
+        self.transform = transform;
+        self.update_luts();
+        self
+    }
+
+    /// Normalizes integer code values after any inversion and transform,
+    /// using the reference code values of each element, or its full code
+    /// range when they are not valid.
+    ///
+    /// Results are clamped to the normalized range. Reals are left as they
+    /// are.
+    pub fn normalize(mut self, normalization: Option<Normalization>) -> Self {
+        self.normalization = normalization;
+        self.references = self
+            .reference_codes()
+            .into_iter()
+            .map(|(min, max)| (min, 1. / (max - min)))
+            .collect();
+        self
+    }
+
+    /// Per element minimum and maximum reference code values, falling back
+    /// to the full code range when they are not valid.
+    fn reference_codes(&self) -> Vec<(f32, f32)> {
+        let count = self.layout.channels as usize;
+        self.header.image_info.channel[..count]
+            .iter()
+            .map(|channel| {
+                let max_code = ((1u64 << channel.bit_depth.min(16)) - 1) as f32;
+                let (min, max) = (channel.min_data, channel.max_data);
+                if min.is_finite() && max.is_finite() && 0. <= min && min < max && max <= max_code {
+                    (min, max)
+                } else {
+                    (0., max_code)
+                }
+            })
+            .collect()
+    }
+
+    /// Code value of `element` normalized from 0.0 to 1.0.
+    #[inline(always)]
+    fn unit(&self, element: usize, sample: u16) -> f32 {
+        let (offset, scale) = self.references[element];
+        ((sample as f32 - offset) * scale).clamp(0., 1.)
+    }
+
+    /// Folds inversion and transform into a table per element.
+    fn update_luts(&mut self) {
+        if self.is_real() {
+            return;
+        }
+        let inversion = self.inversion_sums();
+        if inversion.is_empty() && self.transform.is_none() {
+            self.luts.clear();
+            return;
+        }
+        let bit_depth = self.layout.bit_depth;
+        let max_code = (1u32 << bit_depth) - 1;
+        self.luts = (0..self.layout.channels as usize)
+            .map(|element| {
+                (0..=max_code)
+                    .map(|code| {
+                        let code = match inversion.get(element) {
+                            Some(sum) => sum.saturating_sub(code).min(max_code),
+                            None => code,
+                        };
+                        match &self.transform {
+                            Some(transform) => transform.apply(element, code, max_code) as u16,
+                            None => code as u16,
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+    }
+
+    /// Per element sum of the reference code values samples are inverted
+    /// around, empty when samples are not inverted.
+    fn inversion_sums(&self) -> Vec<u32> {
+        if !self.invert_negative || !self.header.data_format_info.image_sense {
+            return Vec::new();
+        }
+        self.reference_codes()
+            .into_iter()
+            .map(|(min, max)| (min + max).round() as u32)
+            .collect()
+    }
+
+    /// Applies the per-sample transforms to a sample of `element`.
+    #[inline(always)]
+    fn map_sample(&self, element: usize, sample: u16) -> u16 {
+        match self.luts.get(element) {
+            Some(lut) => lut[sample as usize],
+            None => sample,
+        }
+    }
+
+    /// Decodes an integer sample of `element` for a 16-bit buffer.
+    #[inline(always)]
+    fn integer_sample(&self, element: usize, sample: u64) -> u16 {
+        let sample = self.map_sample(element, sample as u16);
+        match self.normalization {
+            Some(Normalization::FullRange) => (self.unit(element, sample) * 65535.).round() as u16,
+            _ => sample,
+        }
+    }
+
+    /// Whether [`decode`](Self::decode) and [`decode_into`](Self::decode_into)
+    /// flip and transpose pixels as needed to return them top to bottom,
+    /// left to right, whatever the orientation of the file.
+    ///
+    /// Rows, regions and tiles always address pixels as they are stored.
+    pub fn normalize_orientation(mut self, normalize: bool) -> Self {
+        self.normalize_orientation = normalize;
+        self
+    }
+
+    /// Orientation the decoded image is converted from.
+    fn orientation(&self) -> Orientation {
+        if self.normalize_orientation {
+            self.header.image_info.image_orientation
+        } else {
+            Orientation::TopToBottomLeftToRight
+        }
+    }
+
+    /// Cineon header.
+    pub fn header(&self) -> &Header {
+        &self.header
+    }
+
+    /// Geometry of the image as decoded by [`decode`](Self::decode).
+    pub fn layout(&self) -> Layout {
+        let mut layout = self.layout;
+        if self.orientation().is_transposed() {
+            layout.width = self.layout.height;
+            layout.height = self.layout.width;
+        }
+        if !self.is_real() {
+            match self.normalization {
+                Some(Normalization::Unit) => layout.bit_depth = 32,
+                Some(Normalization::FullRange) => layout.bit_depth = 16,
+                None => {}
+            }
+        }
+        layout
+    }
+
+    /// Number of samples in a decoded row.
+    fn row_len(&self) -> usize {
+        self.layout.width as usize * self.layout.channels as usize
+    }
+
+    /// Whether samples are reals, which are decoded as they are and cannot
+    /// be written to integer buffers.
+    fn is_real(&self) -> bool {
+        self.layout.bit_depth > 16
+    }
+
+    /// Decodes row `y` into `output`, which must hold at least a row of
+    /// samples.
+    pub fn row_into(&self, y: u32, output: &mut [u16]) -> Result<(), CineonError> {
+        if y >= self.layout.height {
+            return Err(CineonError::OutOfBounds);
+        }
+        if output.len() < self.row_len() {
+            return Err(CineonError::BufferTooSmall);
+        }
+        if self.is_real() || self.normalization == Some(Normalization::Unit) {
+            return Err(CineonError::UnsupportedFormat);
+        }
+        let region = Region::new(0, y, self.layout.width, 1);
+        self.region_into(region, output, |element, sample| {
+            self.integer_sample(element, sample)
+        })
+    }
+
+    /// Decodes the pixels of an already validated region into `output`,
+    /// converting the bits of each sample of an element with `convert`.
+    fn region_into<T, F: Fn(usize, u64) -> T>(
+        &self,
+        region: Region,
+        output: &mut [T],
+        convert: F,
+    ) -> Result<(), CineonError> {
+        let channels = self.layout.channels as usize;
+        let row_len = region.width as usize * channels;
+        let start = region.x as usize;
+        for (index, y) in region.rows().enumerate() {
+            self.check_cancelled()?;
+            let row = &mut output[index * row_len..(index + 1) * row_len];
+            self.data_layout
+                .unpack_row(self.data, y, region.columns(), |element, x, sample| {
+                    row[(x - start) * channels + element] = convert(element, sample);
+                })?;
+        }
+        Ok(())
+    }
+
+    /// Decodes the whole image into `output`, in display order when
+    /// orientation is normalized.
+    fn image_into<T, F: Fn(usize, u64) -> T>(
+        &self,
+        output: &mut [T],
+        convert: F,
+    ) -> Result<(), CineonError> {
+        let Layout { width, height, .. } = self.layout;
+        let orientation = self.orientation();
+        if orientation == Orientation::TopToBottomLeftToRight {
+            return self.region_into(Region::new(0, 0, width, height), output, convert);
+        }
+        let display_width = self.layout().width as usize;
+        let channels = self.layout.channels as usize;
+        self.visit_image(|x, y, element, sample| {
+            output[(y * display_width + x) * channels + element] = convert(element, sample);
+        })
+    }
+
+    /// Unpacks the whole image, handing each sample to `sink` together
+    /// with its display position and element.
+    fn visit_image<F: FnMut(usize, usize, usize, u64)>(
+        &self,
+        mut sink: F,
+    ) -> Result<(), CineonError> {
+        let Layout { width, height, .. } = self.layout;
+        let orientation = self.orientation();
+        for y in 0..height {
+            self.check_cancelled()?;
+            self.data_layout
+                .unpack_row(self.data, y, 0..width, |element, x, sample| {
+                    let (dx, dy) = orientation.display_position(x as u32, y, width, height);
+                    sink(dx as usize, dy as usize, element, sample);
+                })?;
+        }
+        Ok(())
+    }
+
+    /// Decodes the whole image into reals in linear light, converting each
+    /// element with `transfer`, or when there is none with the transfer its
+    /// designator tells.
+    ///
+    /// Code values are taken after any inversion and transform, while
+    /// reals are taken as already linear.
+    pub fn decode_to_float(&self, transfer: Option<Transfer>) -> Result<DecodedImage, CineonError> {
+        let layout = self.layout();
+        let count = self.layout.channels as usize;
+        let transfers: Vec<Transfer> = self.header.image_info.channel[..count]
+            .iter()
+            .map(|channel| {
+                transfer.unwrap_or_else(|| {
+                    Transfer::from_designator(channel.metric, channel.designator)
+                })
+            })
+            .collect();
+        let bit_depth = self.layout.bit_depth;
+        let tables: Vec<Vec<f32>> = if self.is_real() {
+            Vec::new()
+        } else {
+            let max_code = (1u32 << bit_depth) - 1;
+            transfers
+                .iter()
+                .map(|transfer| {
+                    (0..=max_code)
+                        .map(|code| {
+                            transfer.to_linear(code as f32 / max_code as f32, &self.log_conversion)
+                        })
+                        .collect()
+                })
+                .collect()
+        };
+        let mut samples = vec![0.; layout.samples()];
+        self.image_into(&mut samples, |element, sample| match bit_depth {
+            32 => f32::from_bits(sample as u32),
+            64 => f64::from_bits(sample) as f32,
+            _ => tables[element][self.map_sample(element, sample as u16) as usize],
+        })?;
+        let printing_density = transfers
+            .iter()
+            .all(|transfer| *transfer == Transfer::PrintingDensity);
+        Ok(DecodedImage {
+            width: layout.width,
+            height: layout.height,
+            channels: layout.channels,
+            bit_depth: 32,
+            samples: Samples::F32(samples),
+            color_space: if printing_density && !self.is_real() {
+                ColorSpace::LinearPd
+            } else {
+                ColorSpace::Linear
+            },
+        })
+    }
+
+    /// Decodes the whole image into reals, integer code values being
+    /// divided by the largest code value of the bit depth, so that 0.0 to
+    /// 1.0 spans the code range whatever the bit depth. This is the
+    /// canonical input of the color conversions of decoded images.
+    ///
+    /// Code values are taken after any inversion and transform, while
+    /// reals are kept as they are.
+    pub fn decode_normalized(&self) -> Result<DecodedImage, CineonError> {
+        let layout = self.layout();
+        let bit_depth = self.layout.bit_depth;
+        let scale = 1. / ((1u64 << bit_depth.min(16)) - 1) as f32;
+        let mut samples = vec![0.; layout.samples()];
+        self.image_into(&mut samples, |element, sample| match bit_depth {
+            32 => f32::from_bits(sample as u32),
+            64 => f64::from_bits(sample) as f32,
+            _ => self.map_sample(element, sample as u16) as f32 * scale,
+        })?;
+        Ok(DecodedImage {
+            width: layout.width,
+            height: layout.height,
+            channels: layout.channels,
+            bit_depth: 32,
+            samples: Samples::F32(samples),
+            color_space: ColorSpace::CineonLog,
+        })
+    }
+
+    /// Decodes the whole image into tightly packed RGBA pixels of 16-bit
+    /// samples, scaled to the full 16-bit range with opaque alpha, ready
+    /// to be uploaded as a texture.
+    ///
+    /// Single-channel images are shown in gray. Reals are not supported.
+    pub fn decode_rgba16(&self, output: &mut [u16]) -> Result<Layout, CineonError> {
+        let layout = self.layout();
+        let channels = layout.channels as usize;
+        if channels != 1 && channels != 3 {
+            return Err(CineonError::UnsupportedFormat);
+        }
+        if self.is_real() || self.normalization == Some(Normalization::Unit) {
+            return Err(CineonError::UnsupportedFormat);
+        }
+        let width = layout.width as usize;
+        if output.len() < 4 * width * layout.height as usize {
+            return Err(CineonError::BufferTooSmall);
+        }
+        let max_code = (1u32 << layout.bit_depth) - 1;
+        self.visit_image(|x, y, element, sample| {
+            let sample = self.integer_sample(element, sample);
+            let value = match self.normalization {
+                // Already scaled to the full range
+                Some(Normalization::FullRange) => sample,
+                _ => {
+                    let sample = (sample as u32).min(max_code);
+                    ((sample * 0xffff + max_code / 2) / max_code) as u16
+                }
+            };
+            let pixel = 4 * (y * width + x);
+            if channels == 1 {
+                output[pixel..pixel + 3].copy_from_slice(&[value; 3]);
+            } else {
+                output[pixel + element] = value;
+            }
+            output[pixel + 3] = 0xffff;
+        })?;
+        Ok(layout)
+    }
+
+    /// Decodes the whole image for display as 8-bit sRGB with opaque
+    /// alpha, in a single pass, into rows of `stride` bytes of `output`.
+    ///
+    /// Integer code values are taken as printing densities and converted
+    /// to linear light after any inversion and transform, while reals are
+    /// taken as linear light. The result is quantized with ordered
+    /// dithering. Single-channel images are shown in gray.
+    pub fn decode_to_srgb8(&self, output: &mut [u8], stride: usize) -> Result<Layout, CineonError> {
+        let layout = self.layout();
+        let channels = layout.channels as usize;
+        if channels != 1 && channels != 3 {
+            return Err(CineonError::UnsupportedFormat);
+        }
+        let width = layout.width as usize;
+        let height = layout.height as usize;
+        if stride < 4 * width || (height > 0 && output.len() < stride * (height - 1) + 4 * width) {
+            return Err(CineonError::BufferTooSmall);
+        }
+
+        // Display value of every code value, whose range is scaled to 10 bits
+        let bit_depth = self.layout.bit_depth;
+        let display: Vec<f32> = if self.is_real() {
+            Vec::new()
+        } else {
+            let max_code = ((1u32 << bit_depth) - 1) as f32;
+            (0..=max_code as u32)
+                .map(|code| {
+                    color::linear_to_srgb(
+                        self.log_conversion.linear(code as f32 * 1023. / max_code),
+                    )
+                })
+                .collect()
+        };
+        let value = |element: usize, sample: u64| match bit_depth {
+            32 => color::linear_to_srgb(f32::from_bits(sample as u32)),
+            64 => color::linear_to_srgb(f64::from_bits(sample) as f32),
+            _ => display[self.map_sample(element, sample as u16) as usize],
+        };
+        self.visit_image(|x, y, element, sample| {
+            let pixel = y * stride + 4 * x;
+            let quantized = color::dither8(value(element, sample), x, y);
+            if channels == 1 {
+                output[pixel..pixel + 3].copy_from_slice(&[quantized; 3]);
+            } else {
+                output[pixel + element] = quantized;
+            }
+            output[pixel + 3] = 0xff;
+        })?;
+        Ok(layout)
+    }
+
+    /// Decodes `len` samples of `region`, or of the whole image when there
+    /// is none, into the sample type fitting the image.
+    fn decode_samples(&self, region: Option<Region>, len: usize) -> Result<Samples, CineonError> {
+        fn fill<T: Clone, F: Fn(usize, u64) -> T>(
+            decoder: &Decoder,
+            region: Option<Region>,
+            output: &mut [T],
+            convert: F,
+        ) -> Result<(), CineonError> {
+            match region {
+                Some(region) => decoder.region_into(region, output, convert),
+                None => decoder.image_into(output, convert),
+            }
+        }
+        match self.layout.bit_depth {
+            32 => {
+                let mut samples = vec![0.; len];
+                fill(self, region, &mut samples, |_, sample| {
+                    f32::from_bits(sample as u32)
+                })?;
+                Ok(Samples::F32(samples))
+            }
+            64 => {
+                let mut samples = vec![0.; len];
+                fill(self, region, &mut samples, |_, sample| {
+                    f64::from_bits(sample)
+                })?;
+                Ok(Samples::F64(samples))
+            }
+            bit_depth => match self.normalization {
+                Some(Normalization::Unit) => {
+                    let mut samples = vec![0.; len];
+                    fill(self, region, &mut samples, |element, sample| {
+                        self.unit(element, self.map_sample(element, sample as u16))
+                    })?;
+                    Ok(Samples::F32(samples))
+                }
+                _ => {
+                    let mut samples = vec![0; len];
+                    fill(self, region, &mut samples, |element, sample| {
+                        self.integer_sample(element, sample)
+                    })?;
+                    Ok(self.integer_samples(bit_depth, samples))
+                }
+            },
+        }
+    }
+
+    /// Wraps integer samples decoded by [`integer_sample`](Self::integer_sample).
+    fn integer_samples(&self, bit_depth: u8, samples: Vec<u16>) -> Samples {
+        if self.normalization == Some(Normalization::FullRange) {
+            Samples::U16(samples)
+        } else {
+            let signed = self.header.data_format_info.data_sign;
+            Samples::from_u16(bit_depth, signed, samples)
+        }
+    }
+
+    /// Decodes a proxy of the image reduced `factor` times in both
+    /// directions, reducing each block of `factor`x`factor` pixels while
+    /// unpacking, so that skipped rows are never read.
+    ///
+    /// Blocks on the right and bottom edges may be smaller. Pixels are
+    /// addressed as they are stored and only integer samples, not
+    /// normalized to reals, are supported. Signed samples are averaged by
+    /// value.
+    pub fn decode_proxy(
+        &self,
+        factor: u32,
+        filter: ProxyFilter,
+    ) -> Result<DecodedImage, CineonError> {
+        if factor == 0 {
+            return Err(CineonError::OutOfBounds);
+        }
+        if self.is_real() || self.normalization == Some(Normalization::Unit) {
+            return Err(CineonError::UnsupportedFormat);
+        }
+        let Layout {
+            width,
+            height,
+            channels,
+            ..
+        } = self.layout;
+        let proxy_width = width.div_ceil(factor);
+        let proxy_height = height.div_ceil(factor);
+        let channels = channels as usize;
+        let row_len = proxy_width as usize * channels;
+        // Samples normalized to the full range are unsigned
+        let sign_shift = match self.normalization {
+            None if self.header.data_format_info.data_sign => 64 - self.layout.bit_depth as u32,
+            _ => 0,
+        };
+        let mut samples = Vec::with_capacity(row_len * proxy_height as usize);
+        let mut sums = vec![0i64; row_len];
+        let mut counts = vec![0u32; proxy_width as usize];
+        for proxy_y in 0..proxy_height {
+            let top = proxy_y * factor;
+            let rows = match filter {
+                ProxyFilter::Nearest => top..top + 1,
+                ProxyFilter::Box => top..(top + factor).min(height),
+            };
+            sums.iter_mut().for_each(|sum| *sum = 0);
+            counts.iter_mut().for_each(|count| *count = 0);
+            for y in rows {
+                self.check_cancelled()?;
+                self.data_layout
+                    .unpack_row(self.data, y, 0..width, |element, x, sample| {
+                        let factor = factor as usize;
+                        if filter == ProxyFilter::Nearest && x % factor != 0 {
+                            return;
+                        }
+                        let sample = self.integer_sample(element, sample) as i64;
+                        let sample = (sample << sign_shift) >> sign_shift;
+                        sums[x / factor * channels + element] += sample;
+                        if element == 0 {
+                            counts[x / factor] += 1;
+                        }
+                    })?;
+            }
+            samples.extend(sums.iter().enumerate().map(|(index, sum)| {
+                let count = counts[index / channels].max(1) as i64;
+                // Signed averages keep their bits, read back by sign
+                (sum + count / 2).div_euclid(count) as u16
+            }));
+        }
+        Ok(DecodedImage {
+            width: proxy_width,
+            height: proxy_height,
+            channels: channels as u32,
+            bit_depth: self.layout().bit_depth,
+            samples: self.integer_samples(self.layout.bit_depth, samples),
+            color_space: ColorSpace::CineonLog,
+        })
+    }
+
+    /// Decodes a single luma plane of an RGB image, weighing the channels
+    /// of every pixel while unpacking them.
+    ///
+    /// Samples keep the type they would be decoded into, integers being
+    /// rounded and clamped to their range. Signed samples are not
+    /// supported.
+    pub fn decode_luma(&self, weights: Weights) -> Result<DecodedImage, CineonError> {
+        if self.layout.channels != 3 || self.header.data_format_info.data_sign {
+            return Err(CineonError::UnsupportedFormat);
+        }
+        let layout = self.layout();
+        let weights = weights.coefficients();
+        let bit_depth = self.layout.bit_depth;
+        let value = |element: usize, sample: u64| match bit_depth {
+            32 => f32::from_bits(sample as u32) as f64,
+            64 => f64::from_bits(sample),
+            _ if self.normalization == Some(Normalization::Unit) => {
+                self.unit(element, self.map_sample(element, sample as u16)) as f64
+            }
+            _ => self.integer_sample(element, sample) as f64,
+        };
+        let width = layout.width as usize;
+        let mut plane = vec![0f64; width * layout.height as usize];
+        self.visit_image(|x, y, element, sample| {
+            plane[y * width + x] += weights[element] * value(element, sample);
+        })?;
+
+        let samples = match (bit_depth, self.normalization) {
+            (32, _) | (_, Some(Normalization::Unit)) => {
+                Samples::F32(plane.into_iter().map(|v| v as f32).collect())
+            }
+            (64, _) => Samples::F64(plane),
+            (_, normalization) => {
+                let max = if normalization == Some(Normalization::FullRange) {
+                    0xffff
+                } else {
+                    (1u32 << bit_depth) - 1
+                };
+                let samples = plane
+                    .into_iter()
+                    .map(|v| v.round().clamp(0., max as f64) as u16)
+                    .collect();
+                self.integer_samples(bit_depth, samples)
+            }
+        };
+        Ok(DecodedImage {
+            width: layout.width,
+            height: layout.height,
+            channels: 1,
+            bit_depth: layout.bit_depth,
+            samples,
+            color_space: ColorSpace::CineonLog,
+        })
+    }
+
+    /// Decodes row `y`.
+    pub fn row(&self, y: u32) -> Result<Samples, CineonError> {
+        if y >= self.layout.height {
+            return Err(CineonError::OutOfBounds);
+        }
+        let region = Region::new(0, y, self.layout.width, 1);
+        self.decode_samples(Some(region), self.row_len())
+    }
+
+    /// Iterates over the decoded rows, from top to bottom, decoding each
+    /// one only when it is reached.
+    pub fn rows(&self) -> Rows<'_, 'a> {
+        Rows {
+            decoder: self,
+            y: 0,
+        }
+    }
+
+    /// Iterates over independent tiles of `tile_width`x`tile_height`
+    /// pixels, left to right and top to bottom, decoding each one only when
+    /// it is reached.
+    ///
+    /// Tiles on the right and bottom edges are cropped to the image, and
+    /// empty tiles are out of bounds. Since tiles can also be decoded on
+    /// their own through [`decode_region`](Self::decode_region), the
+    /// regions may be shared among threads.
+    pub fn tiles(&self, tile_width: u32, tile_height: u32) -> Result<Tiles<'_, 'a>, CineonError> {
+        if tile_width == 0 || tile_height == 0 {
+            return Err(CineonError::OutOfBounds);
+        }
+        Ok(Tiles {
+            decoder: self,
+            tile_width,
+            tile_height,
+            x: 0,
+            y: 0,
+        })
+    }
+
+    /// Decodes the whole image.
+    ///
+    /// 32 and 64-bit elements are decoded as reals, 1-bit elements as
+    /// bitmaps of zeros and ones.
+    pub fn decode(&self) -> Result<DecodedImage, CineonError> {
+        let layout = self.layout();
+        Ok(DecodedImage {
+            width: layout.width,
+            height: layout.height,
+            channels: layout.channels,
+            bit_depth: layout.bit_depth,
+            samples: self.decode_samples(None, layout.samples())?,
+            color_space: ColorSpace::CineonLog,
+        })
+    }
+
+    /// Decodes the whole image into `output`, without allocating.
+    ///
+    /// Images of reals cannot be decoded into integers.
+    pub fn decode_into(&self, output: &mut [u16]) -> Result<Layout, CineonError> {
+        let layout = self.layout();
+        if output.len() < layout.samples() {
+            return Err(CineonError::BufferTooSmall);
+        }
+        if self.is_real() || self.normalization == Some(Normalization::Unit) {
+            return Err(CineonError::UnsupportedFormat);
+        }
+        self.image_into(output, |element, sample| {
+            self.integer_sample(element, sample)
+        })?;
+        Ok(layout)
+    }
+
+    /// Decodes only the pixels within `region`, skipping the rows above
+    /// and below it and the samples on either side.
+    pub fn decode_region(&self, region: Region) -> Result<DecodedImage, CineonError> {
+        if !region.fits(self.layout.width, self.layout.height) {
+            return Err(CineonError::OutOfBounds);
+        }
+        let channels = self.layout.channels;
+        let len = region.width as usize * region.height as usize * channels as usize;
+        Ok(DecodedImage {
+            width: region.width,
+            height: region.height,
+            channels,
+            bit_depth: self.layout().bit_depth,
+            samples: self.decode_samples(Some(region), len)?,
+            color_space: ColorSpace::CineonLog,
+        })
+    }
+}
+
+/// Block of a tiled image
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tile {
+    /// Area of the image covered by the tile
+    pub region: Region,
+    /// Decoded pixels of the tile
+    pub image: DecodedImage,
+}
+
+/// Iterator over the tiles of a [`Decoder`]
+pub struct Tiles<'d, 'a> {
+    decoder: &'d Decoder<'a>,
+    tile_width: u32,
+    tile_height: u32,
+    x: u32,
+    y: u32,
+}
+
+impl Tiles<'_, '_> {
+    /// Area of the next tile, tiles on the right and bottom edges being
+    /// cropped to the image.
+    fn next_region(&mut self) -> Option<Region> {
+        let Layout { width, height, .. } = self.decoder.layout;
+        if width == 0 || self.y >= height {
+            return None;
+        }
+        let region = Region::new(
+            self.x,
+            self.y,
+            self.tile_width.min(width - self.x),
+            self.tile_height.min(height - self.y),
+        );
+        self.x = self.x.saturating_add(self.tile_width);
+        if self.x >= width {
+            self.x = 0;
+            self.y = self.y.saturating_add(self.tile_height);
+        }
+        Some(region)
+    }
+}
+
+impl Iterator for Tiles<'_, '_> {
+    type Item = Result<Tile, CineonError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let region = self.next_region()?;
+        Some(
+            self.decoder
+                .decode_region(region)
+                .map(|image| Tile { region, image }),
+        )
+    }
+}
+
+/// Iterator over the rows of a [`Decoder`]
+pub struct Rows<'d, 'a> {
+    decoder: &'d Decoder<'a>,
+    y: u32,
+}
+
+impl Iterator for Rows<'_, '_> {
+    type Item = Result<Samples, CineonError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.y >= self.decoder.layout.height {
+            return None;
+        }
+        let row = self.decoder.row(self.y);
+        self.y += 1;
+        Some(row)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let left = self.decoder.layout.height.saturating_sub(self.y) as usize;
+        (left, Some(left))
+    }
+}
+
+impl ExactSizeIterator for Rows<'_, '_> {}
+
+/// Image whose rows are decoded only when first accessed, then cached
+pub struct LazyImage<'a> {
+    decoder: Decoder<'a>,
+    rows: Vec<Option<Samples>>,
+}
+
+impl<'a> LazyImage<'a> {
+    /// Wraps a decoder, whose options apply to every row.
+    pub fn new(decoder: Decoder<'a>) -> Self {
+        let rows = vec![None; decoder.layout.height as usize];
+        Self { decoder, rows }
+    }
+
+    /// Cineon header.
+    pub fn header(&self) -> &Header {
+        self.decoder.header()
+    }
+
+    /// Geometry of the image, as stored.
+    pub fn layout(&self) -> Layout {
+        self.decoder.layout
+    }
+
+    /// Row `y`, decoded on first access.
+    pub fn row(&mut self, y: u32) -> Result<&Samples, CineonError> {
+        let row = self
+            .rows
+            .get_mut(y as usize)
+            .ok_or(CineonError::OutOfBounds)?;
+        match row {
+            Some(samples) => Ok(samples),
+            None => Ok(row.insert(self.decoder.row(y)?)),
+        }
+    }
+
+    /// Whether row `y` has already been decoded.
+    pub fn is_decoded(&self, y: u32) -> bool {
+        matches!(self.rows.get(y as usize), Some(Some(_)))
+    }
+
+    /// Drops every cached row.
+    pub fn clear(&mut self) {
+        self.rows.iter_mut().for_each(|row| *row = None);
+    }
+}
@@ -0,0 +1,65 @@
+//! Interpretation of code values as film densities, for restoration work.
+
+use crate::color::{self, Matrix, DENSITY_PER_CODE};
+use crate::header::ImageChannel;
+
+/// Linear mapping of the code values of an element to printing densities
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DensityScale {
+    /// Printing density of code value 0
+    pub offset: f64,
+    /// Printing density per code value
+    pub scale: f64,
+}
+
+impl DensityScale {
+    /// Mapping of the code values of `channel`, through its reference
+    /// code values and the quantities they represent when the header sets
+    /// them, or else following the Cineon convention of 0.002 density per
+    /// 10-bit code value, scaled to the bit depth of the element.
+    pub fn from_channel(channel: &ImageChannel) -> Self {
+        let data = (channel.min_data as f64, channel.max_data as f64);
+        let quantity = (channel.min_quantity as f64, channel.max_quantity as f64);
+        let span = (data.1 - data.0, quantity.1 - quantity.0);
+        if span.0.is_normal() && span.1.is_normal() {
+            let scale = span.1 / span.0;
+            Self {
+                offset: quantity.0 - data.0 * scale,
+                scale,
+            }
+        } else {
+            let max_code = ((1u64 << channel.bit_depth.clamp(1, 16)) - 1) as f64;
+            Self {
+                offset: 0.,
+                scale: DENSITY_PER_CODE * 1023. / max_code,
+            }
+        }
+    }
+
+    /// Printing density of `code`.
+    pub fn density(&self, code: f64) -> f64 {
+        self.offset + code * self.scale
+    }
+
+    /// Code value of the printing density `density`, not rounded nor
+    /// clamped to the code range.
+    pub fn code(&self, density: f64) -> f64 {
+        (density - self.offset) / self.scale
+    }
+}
+
+/// Status M densities of red, green and blue printing densities, through
+/// `matrix`.
+///
+/// Both sets of densities measure the same dyes through different spectral
+/// responses, so the matrix depends on the film stock and is measured by
+/// reading a calibration target with a Status M densitometer.
+pub fn to_status_m(printing: [f64; 3], matrix: &Matrix) -> [f64; 3] {
+    color::transform(matrix, printing)
+}
+
+/// Printing densities of red, green and blue Status M densities, through
+/// the inverse of `matrix`, unless it is singular.
+pub fn from_status_m(status_m: [f64; 3], matrix: &Matrix) -> Option<[f64; 3]> {
+    color::invert(matrix).map(|inverse| color::transform(&inverse, status_m))
+}
@@ -0,0 +1,90 @@
+//! Comparison of headers field by field.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::export::{header_value, Value};
+use crate::header::Header;
+
+/// Field whose value differs between two headers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldDifference {
+    /// Path of the field, e.g. `image_info.channel[0].bit_depth`
+    pub field: String,
+    /// Value in the header compared, `none` when it lacks the field
+    pub left: String,
+    /// Value in the other header, `none` when it lacks the field
+    pub right: String,
+}
+
+/// Appends the fields of `value` below `path` to `fields`, each formatted
+/// as text.
+pub(crate) fn flatten(path: String, value: &Value, fields: &mut Vec<(String, String)>) {
+    let text = match value {
+        Value::Null => "none".to_string(),
+        Value::Bool(flag) => flag.to_string(),
+        Value::Integer(integer) => integer.to_string(),
+        Value::Real(real) => real.to_string(),
+        Value::Text(text) => format!("{:?}", text),
+        Value::List(values) => {
+            for (index, value) in values.iter().enumerate() {
+                flatten(format!("{}[{}]", path, index), value, fields);
+            }
+            return;
+        }
+        Value::Object(values) => {
+            for (name, value) in values {
+                let path = if path.is_empty() {
+                    name.to_string()
+                } else {
+                    format!("{}.{}", path, name)
+                };
+                flatten(path, value, fields);
+            }
+            return;
+        }
+    };
+    fields.push((path, text));
+}
+
+impl Header {
+    /// Fields whose values differ from the ones of `other`, in header
+    /// order, followed by the fields only `other` has.
+    ///
+    /// Fields are named after the structures of this crate and compared
+    /// as in [`to_json`](Self::to_json), so reals compare by value and
+    /// the user area by size.
+    pub fn diff(&self, other: &Header) -> Vec<FieldDifference> {
+        let mut left = Vec::new();
+        flatten(String::new(), &header_value(self), &mut left);
+        let mut right = Vec::new();
+        flatten(String::new(), &header_value(other), &mut right);
+
+        let right_values: HashMap<&str, &str> = right
+            .iter()
+            .map(|(field, value)| (field.as_str(), value.as_str()))
+            .collect();
+        let left_fields: HashSet<&str> = left.iter().map(|(field, _)| field.as_str()).collect();
+
+        let mut differences = Vec::new();
+        for (field, value) in left.iter() {
+            let other = right_values.get(field.as_str()).copied().unwrap_or("none");
+            if value != other {
+                differences.push(FieldDifference {
+                    field: field.clone(),
+                    left: value.clone(),
+                    right: other.to_string(),
+                });
+            }
+        }
+        for (field, value) in right.iter() {
+            if !left_fields.contains(field.as_str()) {
+                differences.push(FieldDifference {
+                    field: field.clone(),
+                    left: "none".to_string(),
+                    right: value.clone(),
+                });
+            }
+        }
+        differences
+    }
+}
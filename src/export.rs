@@ -0,0 +1,769 @@
+//! Exports of the header fields as text documents, for tools which cannot
+//! read cineon headers.
+
+use std::fmt::Write;
+
+use crate::header::{FilmInfo, Header, Orientation, Timecode};
+
+/// Value of a header field, as laid out in the exported documents
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Value<'a> {
+    /// Missing value
+    Null,
+    /// Flag
+    Bool(bool),
+    /// Integer
+    Integer(i64),
+    /// Real, missing when not finite
+    Real(f32),
+    /// Text
+    Text(&'a str),
+    /// Sequence of values
+    List(Vec<Value<'a>>),
+    /// Fields, by name
+    Object(Vec<(&'static str, Value<'a>)>),
+}
+
+impl<'a> Value<'a> {
+    /// Real which may be undefined.
+    fn real(value: Option<f32>) -> Self {
+        value.map_or(Self::Null, Self::Real)
+    }
+
+    /// Pair of reals each of which may be undefined, such as a
+    /// chromaticity, missing when both are.
+    fn pair(value: [Option<f32>; 2]) -> Self {
+        match value {
+            [None, None] => Self::Null,
+            [x, y] => Self::List(vec![Self::real(x), Self::real(y)]),
+        }
+    }
+}
+
+/// Fields of `header`, grouped by section with their names in the
+/// structures of this crate.
+pub(crate) fn header_value(header: &Header) -> Value<'_> {
+    use Value::*;
+
+    let file_info = &header.file_info;
+    let image_info = &header.image_info;
+    let data_format_info = &header.data_format_info;
+    let origination_info = &header.origination_info;
+
+    let channels = image_info
+        .elements()
+        .map(|channel| {
+            Object(vec![
+                ("metric", Integer(u8::from(channel.metric).into())),
+                ("designator", Integer(u8::from(channel.designator).into())),
+                ("bit_depth", Integer(channel.bit_depth.into())),
+                ("pixels_per_line", Integer(channel.pixels_per_line.into())),
+                (
+                    "lines_per_element",
+                    Integer(channel.lines_per_element.into()),
+                ),
+                ("min_data", Real(channel.min_data)),
+                ("min_quantity", Real(channel.min_quantity)),
+                ("max_data", Real(channel.max_data)),
+                ("max_quantity", Real(channel.max_quantity)),
+            ])
+        })
+        .collect();
+    let padding = |padding: Option<u32>| padding.map_or(Null, |padding| Integer(padding.into()));
+
+    let film_info = header.film_info.as_ref().map_or(Null, |film_info| {
+        Object(vec![
+            (
+                "film_manufacturing_id_code",
+                Integer(film_info.film_manufacturing_id_code.into()),
+            ),
+            ("film_type", Integer(film_info.film_type.into())),
+            ("perfs_offset", Integer(film_info.perfs_offset.into())),
+            ("prefix", Integer(film_info.prefix.into())),
+            ("count", Integer(film_info.count.into())),
+            ("format", Text(&film_info.format)),
+            ("frame_position", Integer(film_info.frame_position.into())),
+            ("frame_rate", Value::real(film_info.frame_rate)),
+            ("frame_id", Text(&film_info.frame_id)),
+            ("slate_info", Text(&film_info.slate_info)),
+        ])
+    });
+
+    Object(vec![
+        (
+            "file_info",
+            Object(vec![
+                ("magic_number", Integer(file_info.magic_number.into())),
+                ("image_offset", Integer(file_info.image_offset.into())),
+                ("generic_size", Integer(file_info.generic_size.into())),
+                ("industry_size", Integer(file_info.industry_size.into())),
+                ("user_size", Integer(file_info.user_size.into())),
+                ("file_size", Integer(file_info.file_size.into())),
+                ("version", Text(&file_info.version)),
+                ("filename", Text(&file_info.filename)),
+                ("creation_date", Text(&file_info.creation_date)),
+                ("creation_time", Text(&file_info.creation_time)),
+            ]),
+        ),
+        (
+            "image_info",
+            Object(vec![
+                (
+                    "image_orientation",
+                    Integer(u8::from(image_info.image_orientation).into()),
+                ),
+                (
+                    "number_of_elements",
+                    Integer(image_info.number_of_elements.into()),
+                ),
+                ("channel", List(channels)),
+                ("white_point", Value::pair(image_info.white_point)),
+                ("red_primary", Value::pair(image_info.red_primary)),
+                ("green_primary", Value::pair(image_info.green_primary)),
+                ("blue_primary", Value::pair(image_info.blue_primary)),
+                ("label_text", Text(&image_info.label_text)),
+            ]),
+        ),
+        (
+            "data_format_info",
+            Object(vec![
+                (
+                    "interleave",
+                    Integer(u8::from(data_format_info.interleave).into()),
+                ),
+                (
+                    "packing",
+                    Integer(u8::from(data_format_info.packing).into()),
+                ),
+                ("data_sign", Bool(data_format_info.data_sign)),
+                ("image_sense", Bool(data_format_info.image_sense)),
+                ("line_padding", padding(data_format_info.line_padding)),
+                ("channel_padding", padding(data_format_info.channel_padding)),
+            ]),
+        ),
+        (
+            "origination_info",
+            Object(vec![
+                ("x_offset", Integer(origination_info.x_offset.into())),
+                ("y_offset", Integer(origination_info.y_offset.into())),
+                (
+                    "source_image_file_name",
+                    Text(&origination_info.source_image_file_name),
+                ),
+                ("source_date", Text(&origination_info.source_date)),
+                ("source_time", Text(&origination_info.source_time)),
+                ("input_device", Text(&origination_info.input_device)),
+                (
+                    "input_device_model_number",
+                    Text(&origination_info.input_device_model_number),
+                ),
+                (
+                    "input_device_serial_number",
+                    Text(&origination_info.input_device_serial_number),
+                ),
+                (
+                    "x_device_pitch",
+                    Value::real(origination_info.x_device_pitch),
+                ),
+                (
+                    "y_device_pitch",
+                    Value::real(origination_info.y_device_pitch),
+                ),
+                ("gamma", Value::real(origination_info.gamma)),
+            ]),
+        ),
+        ("film_info", film_info),
+        (
+            "user_info_size",
+            header
+                .user_info
+                .as_ref()
+                .map_or(Null, |user_info| Integer(user_info.len() as i64)),
+        ),
+    ])
+}
+
+/// Film information of `header` edit lists are filled from, none when the
+/// industry section is missing or left blank by its writer.
+fn edge_code_info(header: &Header) -> Option<&FilmInfo> {
+    header
+        .film_info
+        .as_ref()
+        .filter(|film_info| !film_info.is_blank())
+}
+
+/// Appends `text` to `json` as a string literal.
+fn json_string(json: &mut String, text: &str) {
+    json.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if c.is_control() => {
+                let _ = write!(json, "\\u{:04x}", c as u32);
+            }
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+}
+
+/// Appends `value` to `json`, indented by `depth` levels when `pretty`.
+fn json_value(json: &mut String, value: &Value, pretty: bool, depth: usize) {
+    let newline = |json: &mut String, depth: usize| {
+        if pretty {
+            json.push('\n');
+            json.extend(std::iter::repeat_n("  ", depth));
+        }
+    };
+    match value {
+        Value::Null => json.push_str("null"),
+        Value::Bool(flag) => json.push_str(if *flag { "true" } else { "false" }),
+        Value::Integer(integer) => {
+            let _ = write!(json, "{}", integer);
+        }
+        Value::Real(real) if real.is_finite() => {
+            let _ = write!(json, "{}", real);
+        }
+        Value::Real(_) => json.push_str("null"),
+        Value::Text(text) => json_string(json, text),
+        Value::List(values) => {
+            json.push('[');
+            for (index, value) in values.iter().enumerate() {
+                if index > 0 {
+                    json.push(',');
+                }
+                newline(json, depth + 1);
+                json_value(json, value, pretty, depth + 1);
+            }
+            if !values.is_empty() {
+                newline(json, depth);
+            }
+            json.push(']');
+        }
+        Value::Object(fields) => {
+            json.push('{');
+            for (index, (name, value)) in fields.iter().enumerate() {
+                if index > 0 {
+                    json.push(',');
+                }
+                newline(json, depth + 1);
+                json_string(json, name);
+                json.push_str(if pretty { ": " } else { ":" });
+                json_value(json, value, pretty, depth + 1);
+            }
+            if !fields.is_empty() {
+                newline(json, depth);
+            }
+            json.push('}');
+        }
+    }
+}
+
+impl Header {
+    /// Header as a compact JSON document.
+    ///
+    /// Sections and fields are named after the structures of this crate,
+    /// enumerations are stored as their codes in the file, and reals which
+    /// are not finite, as well as missing sections, are `null`. The user
+    /// area is only described by its size.
+    pub fn to_json(&self) -> String {
+        let mut json = String::new();
+        json_value(&mut json, &header_value(self), false, 0);
+        json
+    }
+
+    /// Header as a JSON document indented for reading, with the same
+    /// layout as [`to_json`](Self::to_json).
+    pub fn to_json_pretty(&self) -> String {
+        let mut json = String::new();
+        json_value(&mut json, &header_value(self), true, 0);
+        json.push('\n');
+        json
+    }
+}
+
+/// Element name of a field in XML documents, after the naming of the
+/// Kodak 4.5 specification.
+fn xml_name(name: &'static str) -> &'static str {
+    match name {
+        "file_info" => "FileInformation",
+        "magic_number" => "MagicNumber",
+        "image_offset" => "ImageDataOffset",
+        "generic_size" => "GenericHeaderLength",
+        "industry_size" => "IndustryHeaderLength",
+        "user_size" => "UserDataLength",
+        "file_size" => "TotalFileSize",
+        "version" => "HeaderVersion",
+        "filename" => "FileName",
+        "creation_date" => "CreationDate",
+        "creation_time" => "CreationTime",
+        "image_info" => "ImageInformation",
+        "image_orientation" => "ImageOrientation",
+        "number_of_elements" => "NumberOfChannels",
+        "channel" => "Channel",
+        "metric" => "DesignatorMetric",
+        "designator" => "DesignatorChannel",
+        "bit_depth" => "BitsPerPixel",
+        "pixels_per_line" => "PixelsPerLine",
+        "lines_per_element" => "LinesPerImage",
+        "min_data" => "MinimumDataValue",
+        "min_quantity" => "MinimumQuantity",
+        "max_data" => "MaximumDataValue",
+        "max_quantity" => "MaximumQuantity",
+        "white_point" => "WhitePoint",
+        "red_primary" => "RedPrimary",
+        "green_primary" => "GreenPrimary",
+        "blue_primary" => "BluePrimary",
+        "label_text" => "Label",
+        "data_format_info" => "DataFormatInformation",
+        "interleave" => "Interleave",
+        "packing" => "Packing",
+        "data_sign" => "DataSigned",
+        "image_sense" => "ImageSense",
+        "line_padding" => "EndOfLinePadding",
+        "channel_padding" => "EndOfChannelPadding",
+        "origination_info" => "ImageOriginationInformation",
+        "x_offset" => "XOffset",
+        "y_offset" => "YOffset",
+        "source_image_file_name" => "SourceImageFileName",
+        "source_date" => "SourceDate",
+        "source_time" => "SourceTime",
+        "input_device" => "InputDevice",
+        "input_device_model_number" => "InputDeviceModelNumber",
+        "input_device_serial_number" => "InputDeviceSerialNumber",
+        "x_device_pitch" => "XInputDevicePitch",
+        "y_device_pitch" => "YInputDevicePitch",
+        "gamma" => "ImageGamma",
+        "film_info" => "MotionPictureFilmInformation",
+        "film_manufacturing_id_code" => "FilmManufacturerIdCode",
+        "film_type" => "FilmType",
+        "perfs_offset" => "OffsetInPerfs",
+        "prefix" => "Prefix",
+        "count" => "Count",
+        "format" => "Format",
+        "frame_position" => "FramePosition",
+        "frame_rate" => "FrameRate",
+        "frame_id" => "FrameIdentification",
+        "slate_info" => "SlateInformation",
+        "user_info_size" => "UserDataSize",
+        name => name,
+    }
+}
+
+/// Appends `text` to `xml` with markup characters escaped.
+fn xml_text(xml: &mut String, text: &str) {
+    for c in text.chars() {
+        match c {
+            '&' => xml.push_str("&amp;"),
+            '<' => xml.push_str("&lt;"),
+            '>' => xml.push_str("&gt;"),
+            '"' => xml.push_str("&quot;"),
+            '\'' => xml.push_str("&apos;"),
+            // Not allowed in XML 1.0 documents, even escaped
+            c if c.is_control() && !matches!(c, '\t' | '\n' | '\r') => {}
+            c => xml.push(c),
+        }
+    }
+}
+
+/// Appends `value` to `xml` as the element `name`, indented by `depth`
+/// levels. Missing values are left out, and sequences of objects are
+/// repeated elements while other sequences are space separated.
+fn xml_element(xml: &mut String, name: &str, value: &Value, depth: usize) {
+    let indent = "  ".repeat(depth);
+    match value {
+        Value::Null => {}
+        Value::List(values) if matches!(values.first(), Some(Value::Object(_))) => {
+            for value in values {
+                xml_element(xml, name, value, depth);
+            }
+        }
+        Value::Object(fields) => {
+            let _ = writeln!(xml, "{}<{}>", indent, name);
+            for (field, value) in fields {
+                xml_element(xml, xml_name(field), value, depth + 1);
+            }
+            let _ = writeln!(xml, "{}</{}>", indent, name);
+        }
+        value => {
+            let _ = write!(xml, "{}<{}>", indent, name);
+            xml_scalar(xml, value);
+            let _ = writeln!(xml, "</{}>", name);
+        }
+    }
+}
+
+/// Appends the text content of a value which is neither an object nor a
+/// sequence of objects.
+fn xml_scalar(xml: &mut String, value: &Value) {
+    match value {
+        Value::Null | Value::Object(_) => {}
+        Value::Bool(flag) => xml.push_str(if *flag { "true" } else { "false" }),
+        Value::Integer(integer) => {
+            let _ = write!(xml, "{}", integer);
+        }
+        Value::Real(real) => {
+            let _ = write!(xml, "{}", real);
+        }
+        Value::Text(text) => xml_text(xml, text),
+        Value::List(values) => {
+            for (index, value) in values.iter().enumerate() {
+                if index > 0 {
+                    xml.push(' ');
+                }
+                xml_scalar(xml, value);
+            }
+        }
+    }
+}
+
+impl Header {
+    /// Header as an XML document, whose elements are named after the
+    /// fields of the Kodak 4.5 specification.
+    ///
+    /// Enumerations are stored as their codes in the file, reals which are
+    /// not finite as `inf` or `NaN`, and missing sections are left out.
+    pub fn to_xml(&self) -> String {
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        xml.push_str("<CineonHeader version=\"4.5\">\n");
+        if let Value::Object(sections) = header_value(self) {
+            for (name, value) in &sections {
+                xml_element(&mut xml, xml_name(name), value, 1);
+            }
+        }
+        xml.push_str("</CineonHeader>\n");
+        xml
+    }
+}
+
+/// ALE field of `text`, whose tabs and line breaks would split the row.
+fn ale_field(text: &str) -> String {
+    text.trim()
+        .chars()
+        .map(|c| if c.is_control() { ' ' } else { c })
+        .collect()
+}
+
+/// Avid Log Exchange document with a row for each of `headers`, so that
+/// editorial can conform against the scan metadata.
+///
+/// Rows hold the clip name, from the file name without its extension, the
+/// start and end timecodes, the keycode and the source file name. Film
+/// information is left empty for headers without it. The frame rate of the
+/// heading is the one of the first header with film information, 24 when
+/// there is none.
+pub fn ale<'a>(headers: impl IntoIterator<Item = &'a Header>) -> String {
+    let headers: Vec<&Header> = headers.into_iter().collect();
+    let frame_rate = headers
+        .iter()
+        .find_map(|header| edge_code_info(header)?.frame_rate)
+        .unwrap_or(24.);
+
+    let mut ale = String::from("Heading\nFIELD_DELIM\tTABS\nVIDEO_FORMAT\tFILM\n");
+    let _ = writeln!(ale, "FPS\t{}", frame_rate);
+    ale.push_str("\nColumn\nName\tTracks\tStart\tEnd\tFPS\tKN Start\tSource File\n\nData\n");
+    for header in headers {
+        let filename = header.file_info.filename.trim();
+        let name = filename.rsplit_once('.').map_or(filename, |(stem, _)| stem);
+        let (start, end, fps, keycode) = match edge_code_info(header) {
+            Some(film_info) => {
+                let timecode = |frame| {
+                    film_info
+                        .frame_rate
+                        .and_then(|frame_rate| Timecode::from_frames(frame, frame_rate))
+                        .map_or(String::new(), |timecode| timecode.to_string())
+                };
+                (
+                    timecode(film_info.frame_position),
+                    timecode(film_info.frame_position.saturating_add(1)),
+                    film_info
+                        .frame_rate
+                        .map_or(String::new(), |frame_rate| frame_rate.to_string()),
+                    film_info.keycode(),
+                )
+            }
+            None => Default::default(),
+        };
+        let _ = writeln!(
+            ale,
+            "{}\tV\t{}\t{}\t{}\t{}\t{}",
+            ale_field(name),
+            start,
+            end,
+            fps,
+            keycode,
+            ale_field(&header.origination_info.source_image_file_name)
+        );
+    }
+    ale
+}
+
+/// Column of the CSV export of headers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CsvColumn {
+    /// File name
+    FileName,
+    /// Pixels per line of the first element
+    Width,
+    /// Lines of the first element
+    Height,
+    /// Bits per sample of the first element
+    BitDepth,
+    /// Number of elements
+    Elements,
+    /// Keycode, empty without film information
+    Keycode,
+    /// Frame rate, empty without film information
+    FrameRate,
+    /// Creation date
+    CreationDate,
+    /// Creation time
+    CreationTime,
+    /// Source date
+    SourceDate,
+    /// Source time
+    SourceTime,
+    /// Source image file name
+    SourceFileName,
+    /// Input device
+    InputDevice,
+    /// Label
+    Label,
+}
+
+impl CsvColumn {
+    /// File name, dimensions, bit depth, keycode and creation date and
+    /// time, as used in scanning logs.
+    pub const DEFAULT: &'static [CsvColumn] = &[
+        Self::FileName,
+        Self::Width,
+        Self::Height,
+        Self::BitDepth,
+        Self::Keycode,
+        Self::CreationDate,
+        Self::CreationTime,
+    ];
+
+    /// Title of the column.
+    pub fn title(self) -> &'static str {
+        match self {
+            Self::FileName => "file_name",
+            Self::Width => "width",
+            Self::Height => "height",
+            Self::BitDepth => "bit_depth",
+            Self::Elements => "elements",
+            Self::Keycode => "keycode",
+            Self::FrameRate => "frame_rate",
+            Self::CreationDate => "creation_date",
+            Self::CreationTime => "creation_time",
+            Self::SourceDate => "source_date",
+            Self::SourceTime => "source_time",
+            Self::SourceFileName => "source_file_name",
+            Self::InputDevice => "input_device",
+            Self::Label => "label",
+        }
+    }
+
+    /// Value of the column for `header`.
+    fn value(self, header: &Header) -> String {
+        let channel = &header.image_info.channel[0];
+        let film_info = edge_code_info(header);
+        match self {
+            Self::FileName => header.file_info.filename.clone(),
+            Self::Width => channel.pixels_per_line.to_string(),
+            Self::Height => channel.lines_per_element.to_string(),
+            Self::BitDepth => channel.bit_depth.to_string(),
+            Self::Elements => header.image_info.number_of_elements.to_string(),
+            Self::Keycode => film_info.map_or(String::new(), |film_info| film_info.keycode()),
+            Self::FrameRate => film_info
+                .and_then(|film_info| film_info.frame_rate)
+                .map_or(String::new(), |frame_rate| frame_rate.to_string()),
+            Self::CreationDate => header.file_info.creation_date.clone(),
+            Self::CreationTime => header.file_info.creation_time.clone(),
+            Self::SourceDate => header.origination_info.source_date.clone(),
+            Self::SourceTime => header.origination_info.source_time.clone(),
+            Self::SourceFileName => header.origination_info.source_image_file_name.clone(),
+            Self::InputDevice => header.origination_info.input_device.clone(),
+            Self::Label => header.image_info.label_text.clone(),
+        }
+    }
+}
+
+/// Appends `text` to `csv` as a field, quoted when it holds separators,
+/// quotes or line breaks.
+fn csv_field(csv: &mut String, text: &str) {
+    if text.contains([',', '"', '\n', '\r']) {
+        csv.push('"');
+        csv.push_str(&text.replace('"', "\"\""));
+        csv.push('"');
+    } else {
+        csv.push_str(text);
+    }
+}
+
+/// Appends a row of `fields` to `csv`.
+fn csv_row(csv: &mut String, fields: impl Iterator<Item = String>) {
+    for (index, field) in fields.enumerate() {
+        if index > 0 {
+            csv.push(',');
+        }
+        csv_field(csv, &field);
+    }
+    csv.push('\n');
+}
+
+/// CSV document with a row of `columns` for each of `headers`, after a row
+/// of column titles.
+pub fn csv<'a>(headers: impl IntoIterator<Item = &'a Header>, columns: &[CsvColumn]) -> String {
+    let mut csv = String::new();
+    csv_row(
+        &mut csv,
+        columns.iter().map(|column| column.title().to_string()),
+    );
+    for header in headers {
+        csv_row(&mut csv, columns.iter().map(|column| column.value(header)));
+    }
+    csv
+}
+
+/// Appends the XMP property `name` with `value` as its text, unless empty.
+fn xmp_property(xmp: &mut String, name: &str, value: &str) {
+    let value = value.trim();
+    if !value.is_empty() {
+        let _ = write!(xmp, "   <{}>", name);
+        xml_text(xmp, value);
+        let _ = writeln!(xmp, "</{}>", name);
+    }
+}
+
+/// XMP-DM time format of timecodes at `frame_rate` frames per second.
+fn xmp_time_format(frame_rate: f32) -> Option<&'static str> {
+    let formats = [
+        (23.976, "23976Timecode"),
+        (24., "24Timecode"),
+        (25., "25Timecode"),
+        (29.97, "2997DropTimecode"),
+        (30., "30Timecode"),
+        (50., "50Timecode"),
+        (59.94, "5994DropTimecode"),
+        (60., "60Timecode"),
+    ];
+    formats
+        .iter()
+        .find(|(rate, _)| (rate - frame_rate).abs() < 0.001)
+        .map(|(_, format)| *format)
+}
+
+impl Header {
+    /// XMP sidecar describing the header with Dublin Core, XMP basic, TIFF
+    /// and XMP Dynamic Media properties, for asset managers which index
+    /// sidecars but cannot read cineon headers.
+    ///
+    /// Empty text fields and unknown values are left out.
+    pub fn to_xmp(&self) -> String {
+        let image_info = &self.image_info;
+        let origination_info = &self.origination_info;
+        let channel = &image_info.channel[0];
+
+        let mut xmp = String::from(concat!(
+            "<?xpacket begin=\"\u{feff}\" id=\"W5M0MpCehiHzreSzNTczkc9d\"?>\n",
+            "<x:xmpmeta xmlns:x=\"adobe:ns:meta/\">\n",
+            " <rdf:RDF xmlns:rdf=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\">\n",
+            "  <rdf:Description rdf:about=\"\"\n",
+            "    xmlns:dc=\"http://purl.org/dc/elements/1.1/\"\n",
+            "    xmlns:xmp=\"http://ns.adobe.com/xap/1.0/\"\n",
+            "    xmlns:tiff=\"http://ns.adobe.com/tiff/1.0/\"\n",
+            "    xmlns:xmpDM=\"http://ns.adobe.com/xmp/1.0/DynamicMedia/\"\n",
+            "    xmlns:stDim=\"http://ns.adobe.com/xap/1.0/sType/Dimensions#\">\n",
+            "   <dc:format>image/cineon</dc:format>\n",
+        ));
+        if !image_info.label_text.trim().is_empty() {
+            xmp.push_str("   <dc:title><rdf:Alt><rdf:li xml:lang=\"x-default\">");
+            xml_text(&mut xmp, image_info.label_text.trim());
+            xmp.push_str("</rdf:li></rdf:Alt></dc:title>\n");
+        }
+        xmp_property(
+            &mut xmp,
+            "dc:source",
+            &origination_info.source_image_file_name,
+        );
+        if let Some(creation) = self.file_info.creation() {
+            let date = creation.date_string().replace(':', "-");
+            let date_time = format!("{}T{}", date, creation.time_string());
+            xmp_property(&mut xmp, "xmp:CreateDate", &date_time);
+        }
+        let creator_tool = format!(
+            "{} {}",
+            origination_info.input_device.trim(),
+            origination_info.input_device_model_number.trim()
+        );
+        xmp_property(&mut xmp, "xmp:CreatorTool", &creator_tool);
+
+        xmp_property(
+            &mut xmp,
+            "tiff:ImageWidth",
+            &channel.pixels_per_line.to_string(),
+        );
+        xmp_property(
+            &mut xmp,
+            "tiff:ImageLength",
+            &channel.lines_per_element.to_string(),
+        );
+        xmp.push_str("   <tiff:BitsPerSample><rdf:Seq>");
+        for channel in image_info.elements() {
+            let _ = write!(xmp, "<rdf:li>{}</rdf:li>", channel.bit_depth);
+        }
+        xmp.push_str("</rdf:Seq></tiff:BitsPerSample>\n");
+        let orientation = match image_info.image_orientation {
+            Orientation::TopToBottomLeftToRight => "1",
+            Orientation::TopToBottomRightToLeft => "2",
+            Orientation::BottomToTopRightToLeft => "3",
+            Orientation::BottomToTopLeftToRight => "4",
+            _ => "",
+        };
+        xmp_property(&mut xmp, "tiff:Orientation", orientation);
+
+        let _ = writeln!(
+            xmp,
+            concat!(
+                "   <xmpDM:videoFrameSize rdf:parseType=\"Resource\">",
+                "<stDim:w>{}</stDim:w><stDim:h>{}</stDim:h><stDim:unit>pixel</stDim:unit>",
+                "</xmpDM:videoFrameSize>"
+            ),
+            channel.pixels_per_line, channel.lines_per_element
+        );
+        if let Some(ratio) = origination_info.pixel_aspect_ratio() {
+            let ratio = format!("{}/1000", (ratio * 1000.).round() as u32);
+            xmp_property(&mut xmp, "xmpDM:videoPixelAspectRatio", &ratio);
+        }
+        if let Some(film_info) = edge_code_info(self) {
+            if let Some(frame_rate) = film_info.frame_rate.filter(|rate| *rate > 0.) {
+                let frame_rate = frame_rate.to_string();
+                xmp_property(&mut xmp, "xmpDM:videoFrameRate", &frame_rate);
+            }
+            let time_format = film_info.frame_rate.and_then(xmp_time_format);
+            if let (Some(time_format), Some(timecode)) = (time_format, film_info.timecode()) {
+                let _ = writeln!(
+                    xmp,
+                    concat!(
+                        "   <xmpDM:startTimecode rdf:parseType=\"Resource\">",
+                        "<xmpDM:timeFormat>{}</xmpDM:timeFormat>",
+                        "<xmpDM:timeValue>{}</xmpDM:timeValue>",
+                        "</xmpDM:startTimecode>"
+                    ),
+                    time_format, timecode
+                );
+            }
+            xmp_property(&mut xmp, "xmpDM:shotName", &film_info.frame_id);
+            xmp_property(&mut xmp, "xmpDM:logComment", &film_info.slate_info);
+        }
+
+        xmp.push_str("  </rdf:Description>\n </rdf:RDF>\n</x:xmpmeta>\n<?xpacket end=\"w\"?>\n");
+        xmp
+    }
+}
@@ -0,0 +1,59 @@
+//! IEEE 754 half precision output, the storage format of GPU and EXR
+//! pipelines.
+
+use crate::image::{DecodedImage, Samples};
+use crate::CineonError;
+
+/// Bits of the half precision real nearest to `value`, ties going to
+/// even, values beyond the half range becoming infinities.
+pub(crate) fn f32_to_f16_bits(value: f32) -> u16 {
+    let bits = value.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exponent = ((bits >> 23) & 0xff) as i32;
+    let mantissa = bits & 0x7f_ffff;
+
+    if exponent == 0xff {
+        // Infinities, and NaNs keeping a quiet payload bit
+        let nan = if mantissa != 0 { 0x200 } else { 0 };
+        return sign | 0x7c00 | nan;
+    }
+    let exponent = exponent - 127 + 15;
+    if exponent >= 0x1f {
+        return sign | 0x7c00;
+    }
+    if exponent <= 0 {
+        // Subnormal halves, or zero when too small
+        if exponent < -10 {
+            return sign;
+        }
+        let mantissa = mantissa | 0x80_0000;
+        let shift = (14 - exponent) as u32;
+        let half = mantissa >> shift;
+        let remainder = mantissa & ((1 << shift) - 1);
+        let midpoint = 1 << (shift - 1);
+        let round = remainder > midpoint || (remainder == midpoint && half & 1 == 1);
+        return sign | (half + round as u32) as u16;
+    }
+    let half = ((exponent as u32) << 10) | (mantissa >> 13);
+    let remainder = mantissa & 0x1fff;
+    let round = remainder > 0x1000 || (remainder == 0x1000 && half & 1 == 1);
+    // A carry out of the mantissa correctly bumps the exponent
+    sign | (half + round as u32) as u16
+}
+
+impl DecodedImage {
+    /// Samples of an image of reals, such as one decoded or converted to
+    /// float, as the bits of half precision reals, halving the memory of
+    /// 32-bit reals.
+    ///
+    /// The bits can be turned into values with `half::f16::from_bits`.
+    pub fn to_f16_bits(&self) -> Result<Vec<u16>, CineonError> {
+        match &self.samples {
+            Samples::F32(samples) => Ok(samples.iter().map(|v| f32_to_f16_bits(*v)).collect()),
+            Samples::F64(samples) => {
+                Ok(samples.iter().map(|v| f32_to_f16_bits(*v as f32)).collect())
+            }
+            _ => Err(CineonError::UnsupportedFormat),
+        }
+    }
+}
@@ -1,16 +1,117 @@
-/// Cineon format version V4.5
+//! Cineon format version V4.5
+
+use std::fmt;
+
+use crate::color::ColorSpace;
+use crate::image::{DecodedImage, Samples};
 
 /// Maximum number of image elements
 pub const MAX_ELEMENTS: usize = 8;
 
-/// Maximum number of components per image element
+// Maximum number of components per image element
 //const MAX_COMPONENTS: usize = 8;
 
 /// Magic Cookie value
 pub const MAGIC_COOKIE: u32 = 0x802A5FD7;
 
+/// Generic section header length (in bytes)
+pub const GENERIC_SIZE: u32 = 1024;
+
+/// Industry specific section header length (in bytes)
+pub const INDUSTRY_SIZE: u32 = 1024;
+
+/// Calendar date and time of day, as stored in the date and time strings
+/// of the header
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct DateTime {
+    /// Year
+    pub year: u16,
+    /// Month, from 1 to 12
+    pub month: u8,
+    /// Day of the month, from 1 to 31
+    pub day: u8,
+    /// Hour, from 0 to 23
+    pub hour: u8,
+    /// Minute, from 0 to 59
+    pub minute: u8,
+    /// Second, from 0 to 59
+    pub second: u8,
+}
+
+/// Groups of consecutive digits of `text`, at most `max` of them.
+fn digit_groups(text: &str, max: usize) -> Vec<&str> {
+    text.split(|c: char| !c.is_ascii_digit())
+        .filter(|group| !group.is_empty())
+        .take(max)
+        .collect()
+}
+
+impl DateTime {
+    /// Date and time parsed from a date string, `yyyy:mm:dd` in the
+    /// specification, and a time string, `hh:mm:ss` possibly followed by a
+    /// time zone, which is ignored.
+    ///
+    /// Any non-digit separator is accepted, as are unseparated `yyyymmdd`
+    /// dates and two digit years, taken from 1970 to 2069. A missing time
+    /// is taken as midnight. Returns `None` when the date is missing or out
+    /// of range.
+    pub fn parse(date: &str, time: &str) -> Option<Self> {
+        let groups = digit_groups(date, 3);
+        let (year, month, day) = match groups[..] {
+            [year, month, day] => (year, month, day),
+            [date] if date.len() == 8 => (&date[..4], &date[4..6], &date[6..]),
+            _ => return None,
+        };
+        let mut year: u16 = year.parse().ok()?;
+        if year < 100 {
+            year += if year < 70 { 2000 } else { 1900 };
+        }
+        let month: u8 = month.parse().ok()?;
+        let day: u8 = day.parse().ok()?;
+
+        let groups = digit_groups(time, 3);
+        let (hour, minute, second) = match groups[..] {
+            [] => ("0", "0", "0"),
+            [hour, minute] => (hour, minute, "0"),
+            [hour, minute, second] => (hour, minute, second),
+            [time] if time.len() == 6 => (&time[..2], &time[2..4], &time[4..]),
+            _ => return None,
+        };
+        let date_time = Self {
+            year,
+            month,
+            day,
+            hour: hour.parse().ok()?,
+            minute: minute.parse().ok()?,
+            second: second.parse().ok()?,
+        };
+        let valid = (1..=12).contains(&date_time.month)
+            && (1..=31).contains(&date_time.day)
+            && date_time.hour < 24
+            && date_time.minute < 60
+            && date_time.second < 60;
+        valid.then_some(date_time)
+    }
+
+    /// Date string in the `yyyy:mm:dd` format of the specification.
+    pub fn date_string(&self) -> String {
+        format!("{:04}:{:02}:{:02}", self.year, self.month, self.day)
+    }
+
+    /// Time string in the `hh:mm:ss` format of the specification.
+    pub fn time_string(&self) -> String {
+        format!("{:02}:{:02}:{:02}", self.hour, self.minute, self.second)
+    }
+}
+
+impl fmt::Display for DateTime {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}", self.date_string(), self.time_string())
+    }
+}
+
 /// File Information
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone, PartialEq)]
 pub struct FileInformation {
     /// Indicates start of Cineon image file and it is used to
     /// determine byte order.
@@ -37,8 +138,146 @@ pub struct FileInformation {
                                reserved: [u8; 36],*/
 }
 
+/// Version of the header format, parsed from its version string
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Version {
+    /// Version 4.5, the layout this crate reads and writes
+    V4_5,
+    /// Another version, as major and minor numbers
+    Other(u8, u8),
+    /// Version string not understood
+    Unknown(String),
+}
+
+impl From<&str> for Version {
+    fn from(x: &str) -> Self {
+        let numbers = x.trim().trim_start_matches(['V', 'v']);
+        let parsed = numbers
+            .split_once('.')
+            .and_then(|(major, minor)| Some((major.parse().ok()?, minor.parse().ok()?)));
+        match parsed {
+            Some((4, 5)) => Self::V4_5,
+            Some((major, minor)) => Self::Other(major, minor),
+            None => Self::Unknown(x.to_string()),
+        }
+    }
+}
+
+impl Version {
+    /// Whether the version is older than 4.5, `None` when unknown.
+    pub fn is_legacy(&self) -> Option<bool> {
+        match self {
+            Self::V4_5 => Some(false),
+            Self::Other(major, minor) => Some((*major, *minor) < (4, 5)),
+            Self::Unknown(_) => None,
+        }
+    }
+}
+
+impl FileInformation {
+    /// Version of the header format.
+    ///
+    /// Headers of every version are read with the 4.5 layout, as no other
+    /// layout is documented.
+    pub fn header_version(&self) -> Version {
+        Version::from(self.version.as_str())
+    }
+
+    /// Creation date and time, `None` when missing or not understood.
+    pub fn creation(&self) -> Option<DateTime> {
+        DateTime::parse(&self.creation_date, &self.creation_time)
+    }
+
+    /// Sets the creation date and time strings to `date_time`.
+    pub fn set_creation(&mut self, date_time: DateTime) {
+        self.creation_date = date_time.date_string();
+        self.creation_time = date_time.time_string();
+    }
+}
+
+/// String field of the header, whose bytes can be read back from the file
+/// when its decoded text is not enough, e.g. for fields in a legacy
+/// encoding
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum StringField {
+    /// Version number of header format
+    Version,
+    /// Filename
+    Filename,
+    /// Create date
+    CreationDate,
+    /// Create time
+    CreationTime,
+    /// Label text
+    Label,
+    /// Source image filename
+    SourceImageFileName,
+    /// Source date
+    SourceDate,
+    /// Source time
+    SourceTime,
+    /// Input device name
+    InputDevice,
+    /// Input device model number
+    InputDeviceModelNumber,
+    /// Input device serial number
+    InputDeviceSerialNumber,
+    /// Format string of the film information
+    Format,
+    /// Frame identification of the film information
+    FrameId,
+    /// Slate information of the film information
+    SlateInfo,
+}
+
+impl StringField {
+    /// Offset of the field from the start of the file, in bytes.
+    pub fn offset(self) -> usize {
+        match self {
+            Self::Version => 24,
+            Self::Filename => 32,
+            Self::CreationDate => 132,
+            Self::CreationTime => 144,
+            Self::Label => 452,
+            Self::SourceImageFileName => 720,
+            Self::SourceDate => 820,
+            Self::SourceTime => 832,
+            Self::InputDevice => 844,
+            Self::InputDeviceModelNumber => 908,
+            Self::InputDeviceSerialNumber => 940,
+            Self::Format => 1036,
+            Self::FrameId => 1076,
+            Self::SlateInfo => 1108,
+        }
+    }
+
+    /// Size of the field, in bytes.
+    pub fn size(self) -> usize {
+        match self {
+            Self::Version => 8,
+            Self::Filename | Self::SourceImageFileName => 100,
+            Self::CreationDate | Self::CreationTime | Self::SourceDate | Self::SourceTime => 12,
+            Self::Label | Self::SlateInfo => 200,
+            Self::InputDevice => 64,
+            Self::InputDeviceModelNumber
+            | Self::InputDeviceSerialNumber
+            | Self::Format
+            | Self::FrameId => 32,
+        }
+    }
+
+    /// Bytes of the field in the cineon file `input`, including any
+    /// padding, or `None` when the file is too short to hold it.
+    pub fn raw(self, input: &[u8]) -> Option<&[u8]> {
+        input.get(self.offset()..self.offset() + self.size())
+    }
+}
+
 /// Component interleaving method
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum Interleave {
     /// Pixel interleave (rgbrgbrgb...)
     Pixel,
@@ -62,8 +301,21 @@ impl From<u8> for Interleave {
     }
 }
 
+impl From<Interleave> for u8 {
+    #[inline(always)]
+    fn from(x: Interleave) -> Self {
+        match x {
+            Interleave::Pixel => 0,
+            Interleave::Line => 1,
+            Interleave::Channel => 2,
+            Interleave::Undefined => 0xff,
+        }
+    }
+}
+
 /// Component data packing method
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum Packing {
     /// Use all bits (tight packing)
     Packed,
@@ -102,8 +354,25 @@ impl From<u8> for Packing {
     }
 }
 
+impl From<Packing> for u8 {
+    #[inline(always)]
+    fn from(x: Packing) -> Self {
+        match x {
+            Packing::Packed => 0,
+            Packing::ByteLeft => 1,
+            Packing::ByteRight => 2,
+            Packing::WordLeft => 3,
+            Packing::WordRight => 4,
+            Packing::LongWordLeft => 5,
+            Packing::LongWordRight => 6,
+            Packing::PackAsManyAsPossible => 7,
+            Packing::Undefined => 0xff,
+        }
+    }
+}
+
 /// Data Format Information
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct DataFormatInfo {
     /// Data interleave
     pub interleave: Interleave,
@@ -134,11 +403,99 @@ impl Default for DataFormatInfo {
     }
 }
 
+/// Metric of the code values of a channel, first byte of its designator
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Metric {
+    /// Universal metric
+    #[default]
+    Universal,
+    /// Vendor specific metric
+    Vendor(u8),
+}
+
+impl From<u8> for Metric {
+    #[inline(always)]
+    fn from(x: u8) -> Self {
+        match x {
+            0 => Self::Universal,
+            _ => Self::Vendor(x),
+        }
+    }
+}
+
+impl From<Metric> for u8 {
+    #[inline(always)]
+    fn from(x: Metric) -> Self {
+        match x {
+            Metric::Universal => 0,
+            Metric::Vendor(x) => x,
+        }
+    }
+}
+
+/// Content of a channel, second byte of its designator
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Designator {
+    /// Black and white
+    #[default]
+    Luminance,
+    /// Red, printing density
+    Red,
+    /// Green, printing density
+    Green,
+    /// Blue, printing density
+    Blue,
+    /// Red, video
+    VideoRed,
+    /// Green, video
+    VideoGreen,
+    /// Blue, video
+    VideoBlue,
+    /// Content not defined by the universal metric
+    Other(u8),
+}
+
+impl From<u8> for Designator {
+    #[inline(always)]
+    fn from(x: u8) -> Self {
+        match x {
+            0 => Self::Luminance,
+            1 => Self::Red,
+            2 => Self::Green,
+            3 => Self::Blue,
+            4 => Self::VideoRed,
+            5 => Self::VideoGreen,
+            6 => Self::VideoBlue,
+            _ => Self::Other(x),
+        }
+    }
+}
+
+impl From<Designator> for u8 {
+    #[inline(always)]
+    fn from(x: Designator) -> Self {
+        match x {
+            Designator::Luminance => 0,
+            Designator::Red => 1,
+            Designator::Green => 2,
+            Designator::Blue => 3,
+            Designator::VideoRed => 4,
+            Designator::VideoGreen => 5,
+            Designator::VideoBlue => 6,
+            Designator::Other(x) => x,
+        }
+    }
+}
+
 /// Image Channel
-#[derive(Default, Debug, Clone, Copy)]
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
 pub struct ImageChannel {
-    /// Channel descriptor
-    pub designator: [u8; 2],
+    /// Metric of the channel
+    pub metric: Metric,
+    /// Content of the channel
+    pub designator: Designator,
     /// Bits per pixel
     pub bit_depth: u8,
     /*/// Reserved
@@ -159,7 +516,8 @@ pub struct ImageChannel {
 }
 
 /// Image Orientation Code
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum Orientation {
     /// Oriented top to bottom, left to right
     TopToBottomLeftToRight,
@@ -198,8 +556,54 @@ impl From<u8> for Orientation {
     }
 }
 
+impl Orientation {
+    /// Whether stored lines are displayed as columns.
+    pub fn is_transposed(&self) -> bool {
+        matches!(
+            self,
+            Self::LeftToRightTopToBottom
+                | Self::RightToLeftTopToBottom
+                | Self::LeftToRightBottomToTop
+                | Self::RightToLeftBottomToTop
+        )
+    }
+
+    /// Display position of the pixel stored at column `x` of line `y`, in
+    /// an element of `width` pixels per line and `height` lines.
+    pub(crate) fn display_position(&self, x: u32, y: u32, width: u32, height: u32) -> (u32, u32) {
+        let (flip_x, flip_y) = (width - 1 - x, height - 1 - y);
+        match self {
+            Self::TopToBottomLeftToRight | Self::Undefined => (x, y),
+            Self::TopToBottomRightToLeft => (flip_x, y),
+            Self::BottomToTopLeftToRight => (x, flip_y),
+            Self::BottomToTopRightToLeft => (flip_x, flip_y),
+            Self::LeftToRightTopToBottom => (y, x),
+            Self::RightToLeftTopToBottom => (flip_y, x),
+            Self::LeftToRightBottomToTop => (y, flip_x),
+            Self::RightToLeftBottomToTop => (flip_y, flip_x),
+        }
+    }
+}
+
+impl From<Orientation> for u8 {
+    #[inline(always)]
+    fn from(x: Orientation) -> Self {
+        match x {
+            Orientation::TopToBottomLeftToRight => 0,
+            Orientation::TopToBottomRightToLeft => 1,
+            Orientation::BottomToTopLeftToRight => 2,
+            Orientation::BottomToTopRightToLeft => 3,
+            Orientation::LeftToRightTopToBottom => 4,
+            Orientation::RightToLeftTopToBottom => 5,
+            Orientation::LeftToRightBottomToTop => 6,
+            Orientation::RightToLeftBottomToTop => 7,
+            Orientation::Undefined => 0xff,
+        }
+    }
+}
+
 /// Image Information
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct ImageInfo {
     /// Image orientation
     pub image_orientation: Orientation,
@@ -209,14 +613,14 @@ pub struct ImageInfo {
     //pub reserved: [u8; 2],
     /// Image element data structures
     pub channel: [ImageChannel; MAX_ELEMENTS],
-    /// White point (x,y, pair)
-    pub white_point: [f32; 2],
-    /// Red primary chromaticity (x, y pair)
-    pub red_primary: [f32; 2],
-    /// Green primary chromaticity (x, y pair)
-    pub green_primary: [f32; 2],
-    /// Blue primary chromaticity (x, y pair)
-    pub blue_primary: [f32; 2],
+    /// White point (x,y, pair), each `None` when undefined
+    pub white_point: [Option<f32>; 2],
+    /// Red primary chromaticity (x, y pair), each `None` when undefined
+    pub red_primary: [Option<f32>; 2],
+    /// Green primary chromaticity (x, y pair), each `None` when undefined
+    pub green_primary: [Option<f32>; 2],
+    /// Blue primary chromaticity (x, y pair), each `None` when undefined
+    pub blue_primary: [Option<f32>; 2],
 
     /// Label text
     pub label_text: String, // 200 bytes
@@ -224,23 +628,32 @@ pub struct ImageInfo {
                             //pub reserved: [u8; 28],
 }
 
+impl ImageInfo {
+    /// Channels of the elements in use, the first `number_of_elements` of
+    /// `channel`, at most 8.
+    pub fn elements(&self) -> impl ExactSizeIterator<Item = &ImageChannel> + Clone {
+        let count = (self.number_of_elements as usize).min(MAX_ELEMENTS);
+        self.channel[..count].iter()
+    }
+}
+
 impl Default for ImageInfo {
     fn default() -> Self {
         Self {
             image_orientation: Orientation::Undefined,
             number_of_elements: 1,
             channel: [ImageChannel::default(); MAX_ELEMENTS],
-            white_point: [0.; 2],
-            red_primary: [0.; 2],
-            green_primary: [0.; 2],
-            blue_primary: [0.; 2],
+            white_point: [None; 2],
+            red_primary: [None; 2],
+            green_primary: [None; 2],
+            blue_primary: [None; 2],
             label_text: String::new(),
         }
     }
 }
 
 /// Origination Information
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone, PartialEq)]
 pub struct OriginationInfo {
     /// X offset
     pub x_offset: i32,
@@ -260,18 +673,250 @@ pub struct OriginationInfo {
     /// Input device serial number
     pub input_device_serial_number: String, // 32 bytes
 
-    /// X device pitch (samples/mm)
-    pub x_device_pitch: f32,
-    /// Y device pitch (samples/mm)
-    pub y_device_pitch: f32,
-    /// Gamma
-    pub gamma: f32,
+    /// X device pitch (samples/mm), `None` when undefined
+    pub x_device_pitch: Option<f32>,
+    /// Y device pitch (samples/mm), `None` when undefined
+    pub y_device_pitch: Option<f32>,
+    /// Gamma, `None` when undefined
+    pub gamma: Option<f32>,
     /*/// Reserved
     reserved: [u8; 40],*/
 }
 
+impl OriginationInfo {
+    /// Source date and time, `None` when missing or not understood.
+    pub fn source(&self) -> Option<DateTime> {
+        DateTime::parse(&self.source_date, &self.source_time)
+    }
+
+    /// Sets the source date and time strings to `date_time`.
+    pub fn set_source(&mut self, date_time: DateTime) {
+        self.source_date = date_time.date_string();
+        self.source_time = date_time.time_string();
+    }
+
+    /// Pixel aspect ratio, width over height of a pixel, from the device
+    /// pitches. Returns `None` when either pitch is undefined or not
+    /// positive.
+    pub fn pixel_aspect_ratio(&self) -> Option<f32> {
+        let x_device_pitch = self.x_device_pitch.filter(|pitch| *pitch > 0.)?;
+        let y_device_pitch = self.y_device_pitch.filter(|pitch| *pitch > 0.)?;
+        // Pitches are in samples per mm, so pixel sizes are their inverses
+        Some(y_device_pitch / x_device_pitch)
+    }
+}
+
+/// Film manufacturer, from the manufacturing ID code of the KEYKODE edge
+/// code (SMPTE ST 254) the film information is read from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Manufacturer {
+    /// Eastman Kodak, code 1
+    Kodak,
+    /// Manufacturer whose code is not known
+    Other(u8),
+}
+
+impl From<u8> for Manufacturer {
+    #[inline(always)]
+    fn from(x: u8) -> Self {
+        match x {
+            1 => Self::Kodak,
+            _ => Self::Other(x),
+        }
+    }
+}
+
+impl From<Manufacturer> for u8 {
+    #[inline(always)]
+    fn from(x: Manufacturer) -> Self {
+        match x {
+            Manufacturer::Kodak => 1,
+            Manufacturer::Other(x) => x,
+        }
+    }
+}
+
+impl Manufacturer {
+    /// Human-readable name of the manufacturer, `None` when unknown.
+    pub fn name(self) -> Option<&'static str> {
+        match self {
+            Self::Kodak => Some("Kodak"),
+            Self::Other(_) => None,
+        }
+    }
+}
+
+/// Known film stocks, by manufacturer and film type code.
+///
+/// The film type code of the edge code is made of the last two digits of
+/// the Kodak stock number, shared by the variants of a stock on different
+/// gauges and bases, such as the 35mm (52xx) and 16mm (72xx) camera
+/// negatives or the 22xx and 52xx intermediates.
+const FILM_STOCKS: &[(Manufacturer, u8, &str)] = &[
+    (Manufacturer::Kodak, 3, "Kodak Vision3 50D 5203/7203"),
+    (Manufacturer::Kodak, 7, "Kodak Vision3 250D 5207/7207"),
+    (Manufacturer::Kodak, 13, "Kodak Vision3 200T 5213/7213"),
+    (Manufacturer::Kodak, 19, "Kodak Vision3 500T 5219/7219"),
+    (Manufacturer::Kodak, 22, "Kodak Eastman Double-X 5222/7222"),
+    (
+        Manufacturer::Kodak,
+        42,
+        "Kodak Vision Color Intermediate 2242/5242",
+    ),
+    (
+        Manufacturer::Kodak,
+        54,
+        "Kodak Vision3 Color Digital Intermediate 5254/2254",
+    ),
+    (Manufacturer::Kodak, 83, "Kodak Vision Color Print 2383"),
+];
+
+/// Human-readable name of the film stock with the given manufacturer and
+/// film type code, `None` when not in the lookup table.
+pub fn film_stock(manufacturer: Manufacturer, film_type: u8) -> Option<&'static str> {
+    FILM_STOCKS
+        .iter()
+        .find(|(m, t, _)| *m == manufacturer && *t == film_type)
+        .map(|(_, _, name)| *name)
+}
+
+/// Film format, parsed from the format string of the film information
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum FilmFormat {
+    /// 35mm Academy aperture
+    Academy,
+    /// 35mm full aperture, also known as silent aperture
+    Full,
+    /// Super 35
+    Super35,
+    /// 8-perf horizontal 35mm
+    VistaVision,
+    /// Standard 16mm
+    Standard16,
+    /// Super 16
+    Super16,
+    /// 5-perf 65mm
+    Format65,
+    /// Format not recognized, with the original string
+    Other(String),
+}
+
+impl From<&str> for FilmFormat {
+    fn from(x: &str) -> Self {
+        let normalized: String = x
+            .chars()
+            .filter(|c| c.is_ascii_alphanumeric())
+            .map(|c| c.to_ascii_lowercase())
+            .collect();
+        match normalized.as_str() {
+            "academy" | "academyaperture" => Self::Academy,
+            "full" | "fullaperture" | "silent" | "silentaperture" => Self::Full,
+            "super35" | "s35" => Self::Super35,
+            "vistavision" | "vv" | "8perf" => Self::VistaVision,
+            "16mm" | "standard16" | "std16" => Self::Standard16,
+            "super16" | "s16" => Self::Super16,
+            "65mm" | "5perf65" | "format65" => Self::Format65,
+            _ => Self::Other(x.to_string()),
+        }
+    }
+}
+
+impl FilmFormat {
+    /// Camera aperture as width and height in millimeters, `None` for
+    /// formats not recognized.
+    pub fn aperture(&self) -> Option<(f32, f32)> {
+        match self {
+            Self::Academy => Some((21.95, 16.00)),
+            Self::Full => Some((24.89, 18.67)),
+            Self::Super35 => Some((24.89, 18.66)),
+            Self::VistaVision => Some((37.72, 24.92)),
+            Self::Standard16 => Some((10.26, 7.49)),
+            Self::Super16 => Some((12.52, 7.41)),
+            Self::Format65 => Some((52.48, 23.01)),
+            Self::Other(_) => None,
+        }
+    }
+
+    /// Aspect ratio of the camera aperture, `None` for formats not
+    /// recognized.
+    pub fn aspect_ratio(&self) -> Option<f32> {
+        self.aperture().map(|(width, height)| width / height)
+    }
+}
+
+/// SMPTE timecode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Timecode {
+    /// Hours, from 0 to 23
+    pub hours: u8,
+    /// Minutes, from 0 to 59
+    pub minutes: u8,
+    /// Seconds, from 0 to 59
+    pub seconds: u8,
+    /// Frames within the second
+    pub frames: u8,
+    /// Whether frame numbers are dropped to follow the NTSC rates
+    pub drop_frame: bool,
+}
+
+impl Timecode {
+    /// Timecode of the frame at `frame_position` from midnight, at
+    /// `frame_rate` frames per second.
+    ///
+    /// Rates of 29.97 and 59.94 count in drop-frame, skipping the first 2
+    /// or 4 frame numbers of each minute not multiple of ten. Returns `None`
+    /// when the rate is not positive or rounds above 255 frames per second,
+    /// whose frame numbers do not fit `frames`.
+    pub fn from_frames(frame_position: u32, frame_rate: f32) -> Option<Self> {
+        if !(frame_rate.is_finite() && frame_rate >= 0.5) {
+            return None;
+        }
+        let nominal = frame_rate.round() as u64;
+        if nominal > u64::from(u8::MAX) {
+            return None;
+        }
+        let drop_frame = matches!(nominal, 30 | 60) && (frame_rate - nominal as f32).abs() > 0.001;
+        let mut frame = frame_position as u64;
+        if drop_frame {
+            let dropped = nominal / 15;
+            let per_ten_minutes = 600 * nominal - 9 * dropped;
+            let per_minute = 60 * nominal - dropped;
+            let tens = frame / per_ten_minutes;
+            let rest = frame % per_ten_minutes;
+            frame += 9 * dropped * tens;
+            if rest > dropped {
+                frame += dropped * ((rest - dropped) / per_minute);
+            }
+        }
+        let seconds = frame / nominal;
+        Some(Self {
+            hours: (seconds / 3600 % 24) as u8,
+            minutes: (seconds / 60 % 60) as u8,
+            seconds: (seconds % 60) as u8,
+            frames: (frame % nominal) as u8,
+            drop_frame,
+        })
+    }
+}
+
+impl fmt::Display for Timecode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:02}:{:02}:{:02}{}{:02}",
+            self.hours,
+            self.minutes,
+            self.seconds,
+            if self.drop_frame { ';' } else { ':' },
+            self.frames
+        )
+    }
+}
+
 /// Motion Picture and Television Industry Specific Information
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone, PartialEq)]
 pub struct FilmInfo {
     /// Film edge code manufacturing ID code
     pub film_manufacturing_id_code: u8,
@@ -292,8 +937,8 @@ pub struct FilmInfo {
     /// Frame position in sequence
     pub frame_position: u32,
 
-    /// Frame rate of original (frame / sec)
-    pub frame_rate: f32,
+    /// Frame rate of original (frame / sec), `None` when undefined
+    pub frame_rate: Option<f32>,
 
     /// Frame identification, e.g. keyframe
     pub frame_id: String, // 32 bytes
@@ -303,8 +948,66 @@ pub struct FilmInfo {
                             reserved1: [u8; 740],*/
 }
 
+impl FilmInfo {
+    /// Whether no field is set, as in industry sections left zeroed or
+    /// undefined by their writer.
+    pub fn is_blank(&self) -> bool {
+        let unset_u8 = |value: u8| value == 0 || value == u8::MAX;
+        let unset_u32 = |value: u32| value == 0 || value == u32::MAX;
+        unset_u8(self.film_manufacturing_id_code)
+            && unset_u8(self.film_type)
+            && unset_u8(self.perfs_offset)
+            && unset_u32(self.prefix)
+            && unset_u32(self.count)
+            && unset_u32(self.frame_position)
+            && self.frame_rate.is_none_or(|frame_rate| frame_rate == 0.)
+            && self.format.is_empty()
+            && self.frame_id.is_empty()
+            && self.slate_info.is_empty()
+    }
+
+    /// Film manufacturer, from the manufacturing ID code.
+    pub fn manufacturer(&self) -> Manufacturer {
+        self.film_manufacturing_id_code.into()
+    }
+
+    /// Human-readable name of the film stock, `None` when the manufacturer
+    /// and film type codes are not in the lookup table.
+    pub fn stock_name(&self) -> Option<&'static str> {
+        film_stock(self.manufacturer(), self.film_type)
+    }
+
+    /// Film edge code in its human-readable form, e.g.
+    /// `01 19 12 3456 7890+12`: manufacturing ID code, film type, prefix
+    /// split into its two and four digit groups, count, and offset in
+    /// perfs.
+    pub fn keycode(&self) -> String {
+        format!(
+            "{:02} {:02} {:02} {:04} {:04}+{:02}",
+            self.film_manufacturing_id_code,
+            self.film_type,
+            self.prefix / 10_000,
+            self.prefix % 10_000,
+            self.count,
+            self.perfs_offset
+        )
+    }
+
+    /// Timecode of the frame, from its position in the sequence and the
+    /// frame rate of the original. Returns `None` when the frame rate is
+    /// undefined or not positive.
+    pub fn timecode(&self) -> Option<Timecode> {
+        Timecode::from_frames(self.frame_position, self.frame_rate?)
+    }
+
+    /// Film format, parsed from the format string.
+    pub fn film_format(&self) -> FilmFormat {
+        FilmFormat::from(self.format.as_str())
+    }
+}
+
 /// Generic File and Image Header Information
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone, PartialEq)]
 pub struct Header {
     /// File Information
     pub file_info: FileInformation,
@@ -330,3 +1033,209 @@ pub struct Header {
     /// for display.
     pub user_info: Option<Vec<u8>>,
 }
+
+/// Pixels per line of the postage stamp
+pub const POSTAGE_STAMP_WIDTH: u32 = 96;
+
+/// Lines of the postage stamp
+pub const POSTAGE_STAMP_HEIGHT: u32 = 64;
+
+impl Header {
+    /// Postage stamp stored at the beginning of the user area, as an 8-bit
+    /// RGB thumbnail oriented for display.
+    ///
+    /// Returns `None` when the user area is too small to hold it.
+    pub fn postage_stamp(&self) -> Option<DecodedImage> {
+        postage_stamp(self.user_info.as_ref()?)
+    }
+}
+
+/// Bytes of the postage stamp
+pub(crate) const POSTAGE_STAMP_SIZE: usize =
+    (POSTAGE_STAMP_WIDTH * POSTAGE_STAMP_HEIGHT * 3) as usize;
+
+/// Postage stamp at the beginning of the user area `user_info`, `None`
+/// when it is too small to hold it.
+pub(crate) fn postage_stamp(user_info: &[u8]) -> Option<DecodedImage> {
+    let pixels = user_info.get(..POSTAGE_STAMP_SIZE)?;
+    Some(DecodedImage {
+        width: POSTAGE_STAMP_WIDTH,
+        height: POSTAGE_STAMP_HEIGHT,
+        channels: 3,
+        bit_depth: 8,
+        samples: Samples::U8(pixels.to_vec()),
+        color_space: ColorSpace::CineonLog,
+    })
+}
+
+/// Writes a field of the header summary, aligning its value.
+fn field(f: &mut fmt::Formatter, name: &str, value: impl fmt::Display) -> fmt::Result {
+    writeln!(f, "  {:<24}{}", name, value)
+}
+
+/// Text of a value of the header summary which may be undefined.
+fn defined(value: Option<impl fmt::Display>) -> String {
+    value.map_or("undefined".to_string(), |value| value.to_string())
+}
+
+impl fmt::Display for Header {
+    /// Formats every field of the header, one per line, grouped by
+    /// section.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let file_info = &self.file_info;
+        writeln!(f, "File Information")?;
+        field(
+            f,
+            "Magic number",
+            format_args!("{:#010x}", file_info.magic_number),
+        )?;
+        field(f, "Image offset", file_info.image_offset)?;
+        field(f, "Generic size", file_info.generic_size)?;
+        field(f, "Industry size", file_info.industry_size)?;
+        field(f, "User size", file_info.user_size)?;
+        field(f, "File size", file_info.file_size)?;
+        field(f, "Version", &file_info.version)?;
+        field(f, "Filename", &file_info.filename)?;
+        field(f, "Creation date", &file_info.creation_date)?;
+        field(f, "Creation time", &file_info.creation_time)?;
+
+        let image_info = &self.image_info;
+        writeln!(f, "Image Information")?;
+        field(
+            f,
+            "Orientation",
+            format_args!("{:?}", image_info.image_orientation),
+        )?;
+        field(f, "Number of elements", image_info.number_of_elements)?;
+        for (element, channel) in image_info.elements().enumerate() {
+            writeln!(f, "  Element {}", element)?;
+            field(
+                f,
+                "  Designator",
+                format_args!("{:?} {:?}", channel.metric, channel.designator),
+            )?;
+            field(f, "  Bit depth", channel.bit_depth)?;
+            field(
+                f,
+                "  Size",
+                format_args!("{}x{}", channel.pixels_per_line, channel.lines_per_element),
+            )?;
+            field(
+                f,
+                "  Data range",
+                format_args!("{} to {}", channel.min_data, channel.max_data),
+            )?;
+            field(
+                f,
+                "  Quantity range",
+                format_args!("{} to {}", channel.min_quantity, channel.max_quantity),
+            )?;
+        }
+        for (name, xy) in [
+            ("White point", image_info.white_point),
+            ("Red primary", image_info.red_primary),
+            ("Green primary", image_info.green_primary),
+            ("Blue primary", image_info.blue_primary),
+        ] {
+            let [x, y] = xy;
+            let xy = match xy {
+                [None, None] => None,
+                _ => Some(format!("{}, {}", defined(x), defined(y))),
+            };
+            field(f, name, defined(xy))?;
+        }
+        field(f, "Label", &image_info.label_text)?;
+
+        let data_format_info = &self.data_format_info;
+        writeln!(f, "Data Format Information")?;
+        field(
+            f,
+            "Interleave",
+            format_args!("{:?}", data_format_info.interleave),
+        )?;
+        field(f, "Packing", format_args!("{:?}", data_format_info.packing))?;
+        field(
+            f,
+            "Data sign",
+            if data_format_info.data_sign {
+                "signed"
+            } else {
+                "unsigned"
+            },
+        )?;
+        field(
+            f,
+            "Image sense",
+            if data_format_info.image_sense {
+                "negative"
+            } else {
+                "positive"
+            },
+        )?;
+        field(
+            f,
+            "Line padding",
+            data_format_info.line_padding.unwrap_or(0),
+        )?;
+        field(
+            f,
+            "Channel padding",
+            data_format_info.channel_padding.unwrap_or(0),
+        )?;
+
+        let origination_info = &self.origination_info;
+        writeln!(f, "Origination Information")?;
+        field(
+            f,
+            "Offset",
+            format_args!(
+                "{}, {}",
+                origination_info.x_offset, origination_info.y_offset
+            ),
+        )?;
+        field(f, "Source image", &origination_info.source_image_file_name)?;
+        field(f, "Source date", &origination_info.source_date)?;
+        field(f, "Source time", &origination_info.source_time)?;
+        field(f, "Input device", &origination_info.input_device)?;
+        field(
+            f,
+            "Device model",
+            &origination_info.input_device_model_number,
+        )?;
+        field(
+            f,
+            "Device serial",
+            &origination_info.input_device_serial_number,
+        )?;
+        field(
+            f,
+            "Device pitch",
+            format_args!(
+                "{}, {}",
+                defined(origination_info.x_device_pitch),
+                defined(origination_info.y_device_pitch)
+            ),
+        )?;
+        field(f, "Gamma", defined(origination_info.gamma))?;
+
+        if let Some(film_info) = &self.film_info {
+            writeln!(f, "Film Information")?;
+            field(f, "Keycode", film_info.keycode())?;
+            field(f, "Film stock", film_info.stock_name().unwrap_or("unknown"))?;
+            field(f, "Format", &film_info.format)?;
+            field(f, "Frame position", film_info.frame_position)?;
+            field(f, "Frame rate", defined(film_info.frame_rate))?;
+            if let Some(timecode) = film_info.timecode() {
+                field(f, "Timecode", timecode)?;
+            }
+            field(f, "Frame ID", &film_info.frame_id)?;
+            field(f, "Slate", &film_info.slate_info)?;
+        }
+
+        if let Some(user_info) = &self.user_info {
+            writeln!(f, "User Data")?;
+            field(f, "Size", format_args!("{} bytes", user_info.len()))?;
+        }
+        Ok(())
+    }
+}
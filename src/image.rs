@@ -0,0 +1,721 @@
+use std::ops::Range;
+
+use crate::color::{
+    self, AcesSpace, Adaptation, ColorSpace, Colorimetry, GamutMapping, LogConversion,
+    AP0_PRIMARIES, AP1_PRIMARIES, D60, D65, DENSITY_PER_CODE, NEGATIVE_GAMMA, REC709_PRIMARIES,
+};
+use crate::header::{Header, MAX_ELEMENTS};
+use crate::CineonError;
+
+/// Decoded samples, stored in the natural type for their bit depth
+#[derive(Debug, Clone, PartialEq)]
+pub enum Samples {
+    /// Samples up to 8 bits
+    U8(Vec<u8>),
+    /// Samples from 9 to 16 bits (10, 12 and 16-bit elements)
+    U16(Vec<u16>),
+    /// Floating point samples (32-bit elements)
+    F32(Vec<f32>),
+    /// Signed samples up to 8 bits
+    I8(Vec<i8>),
+    /// Signed samples from 9 to 16 bits
+    I16(Vec<i16>),
+    /// Double precision floating point samples (64-bit elements)
+    F64(Vec<f64>),
+}
+
+impl Samples {
+    /// Stores integer samples of `bit_depth` bits in the smallest fitting type,
+    /// reading them as two's complement values when `signed`.
+    pub(crate) fn from_u16(bit_depth: u8, signed: bool, samples: Vec<u16>) -> Self {
+        match (bit_depth <= 8, signed) {
+            (true, false) => Self::U8(samples.into_iter().map(|sample| sample as u8).collect()),
+            (false, false) => Self::U16(samples),
+            (true, true) => {
+                let shift = 8 - bit_depth as u32;
+                Self::I8(
+                    samples
+                        .into_iter()
+                        .map(|sample| ((sample as u8) << shift) as i8 >> shift)
+                        .collect(),
+                )
+            }
+            (false, true) => {
+                let shift = 16 - bit_depth.min(16) as u32;
+                Self::I16(
+                    samples
+                        .into_iter()
+                        .map(|sample| (sample << shift) as i16 >> shift)
+                        .collect(),
+                )
+            }
+        }
+    }
+
+    /// Stores the bits of samples of `bit_depth` bits, 32 and 64-bit
+    /// samples being reals.
+    pub(crate) fn from_bits(bit_depth: u8, signed: bool, samples: Vec<u64>) -> Self {
+        match bit_depth {
+            32 => Self::F32(
+                samples
+                    .into_iter()
+                    .map(|sample| f32::from_bits(sample as u32))
+                    .collect(),
+            ),
+            64 => Self::F64(samples.into_iter().map(f64::from_bits).collect()),
+            _ => Self::from_u16(
+                bit_depth,
+                signed,
+                samples.into_iter().map(|sample| sample as u16).collect(),
+            ),
+        }
+    }
+
+    /// Number of samples.
+    pub fn len(&self) -> usize {
+        match self {
+            Self::U8(samples) => samples.len(),
+            Self::U16(samples) => samples.len(),
+            Self::F32(samples) => samples.len(),
+            Self::I8(samples) => samples.len(),
+            Self::I16(samples) => samples.len(),
+            Self::F64(samples) => samples.len(),
+        }
+    }
+
+    /// Whether there are no samples.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Value of the sample at `index`.
+    #[inline(always)]
+    pub(crate) fn get(&self, index: usize) -> f64 {
+        match self {
+            Self::U8(samples) => samples[index] as f64,
+            Self::U16(samples) => samples[index] as f64,
+            Self::F32(samples) => samples[index] as f64,
+            Self::I8(samples) => samples[index] as f64,
+            Self::I16(samples) => samples[index] as f64,
+            Self::F64(samples) => samples[index],
+        }
+    }
+}
+
+/// Geometry of decoded image data
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Layout {
+    /// Pixels per line
+    pub width: u32,
+    /// Number of lines
+    pub height: u32,
+    /// Samples per pixel
+    pub channels: u32,
+    /// Bits per sample in the file
+    pub bit_depth: u8,
+}
+
+impl Layout {
+    /// Layout of the image described by a header, whose elements must
+    /// share the same dimensions and bit depth.
+    pub(crate) fn from_header(header: &Header) -> Result<Self, CineonError> {
+        let elements = Self::elements(header)?;
+        let first = elements[0];
+        if elements.iter().any(|element| *element != first) {
+            return Err(CineonError::UnsupportedFormat);
+        }
+        Ok(Self {
+            channels: elements.len() as u32,
+            ..first
+        })
+    }
+
+    /// Layouts of every element described by a header, each as a single
+    /// channel with its own geometry.
+    pub(crate) fn elements(header: &Header) -> Result<Vec<Self>, CineonError> {
+        let count = header.image_info.number_of_elements as usize;
+        if count == 0 || count > MAX_ELEMENTS {
+            return Err(CineonError::UnsupportedFormat);
+        }
+        Ok(header.image_info.channel[..count]
+            .iter()
+            .map(|channel| Self {
+                width: channel.pixels_per_line,
+                height: channel.lines_per_element,
+                channels: 1,
+                bit_depth: channel.bit_depth,
+            })
+            .collect())
+    }
+
+    /// Total number of samples.
+    pub fn samples(&self) -> usize {
+        self.width as usize * self.height as usize * self.channels as usize
+    }
+}
+
+/// Rectangular area of an image
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Region {
+    /// Leftmost column
+    pub x: u32,
+    /// Topmost row
+    pub y: u32,
+    /// Number of columns
+    pub width: u32,
+    /// Number of rows
+    pub height: u32,
+}
+
+impl Region {
+    /// Creates the region of `width`x`height` pixels whose top left corner
+    /// is at (`x`, `y`).
+    pub fn new(x: u32, y: u32, width: u32, height: u32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// Whether the region lies within an image of `width`x`height` pixels.
+    pub fn fits(&self, width: u32, height: u32) -> bool {
+        self.x as u64 + self.width as u64 <= width as u64
+            && self.y as u64 + self.height as u64 <= height as u64
+    }
+
+    /// Columns covered by the region.
+    pub(crate) fn columns(&self) -> Range<u32> {
+        self.x..self.x + self.width
+    }
+
+    /// Rows covered by the region.
+    pub(crate) fn rows(&self) -> Range<u32> {
+        self.y..self.y + self.height
+    }
+}
+
+/// Dithering applied when reducing the bit depth of samples
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dither {
+    /// Rounds every sample to the nearest level
+    None,
+    /// Adds a 4x4 Bayer pattern, cheap and stable across frames
+    Ordered,
+    /// Spreads the rounding error of every sample over its neighbours,
+    /// following Floyd-Steinberg
+    ErrorDiffusion,
+}
+
+/// Weights of the red, green and blue channels in a monochrome conversion
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Weights {
+    /// Luma coefficients of ITU-R BT.709
+    Rec709,
+    /// Average of the three channels
+    Equal,
+    /// Custom red, green and blue weights, applied as they are
+    Custom([f32; 3]),
+}
+
+impl Weights {
+    pub(crate) fn coefficients(&self) -> [f64; 3] {
+        match self {
+            Self::Rec709 => [0.2126, 0.7152, 0.0722],
+            Self::Equal => [1. / 3.; 3],
+            Self::Custom([r, g, b]) => [*r as f64, *g as f64, *b as f64],
+        }
+    }
+}
+
+/// Weighted sums of the channels of every RGB pixel, converted back with
+/// `convert`.
+fn weigh<T: Copy, F: Fn(f64) -> T>(
+    samples: &[T],
+    weights: [f64; 3],
+    value: impl Fn(T) -> f64,
+    convert: F,
+) -> Vec<T> {
+    samples
+        .chunks_exact(3)
+        .map(|pixel| {
+            convert(
+                pixel
+                    .iter()
+                    .zip(weights.iter())
+                    .map(|(sample, weight)| value(*sample) * weight)
+                    .sum(),
+            )
+        })
+        .collect()
+}
+
+/// How a matte is combined with the colors of RGBA images
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Alpha {
+    /// Colors are kept independent of the matte
+    Straight,
+    /// Colors are multiplied by the matte, after their encoding
+    Premultiplied,
+}
+
+/// Balance change of the red, green and blue channels of an image
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Adjustment {
+    /// Printer points, 0.025 log exposure each, twelve making about a stop
+    PrinterPoints([f32; 3]),
+    /// Stops of exposure, each doubling the light
+    Stops([f32; 3]),
+    /// Multiplies samples by `gain`, then adds `offset`, within the domain
+    /// of the image, such as white balance gains neutralizing the cast of
+    /// faded stock. Offsets are 10-bit code values for printing densities.
+    GainOffset {
+        /// Per channel multiplier
+        gain: [f32; 3],
+        /// Per channel addend
+        offset: [f32; 3],
+    },
+}
+
+impl Adjustment {
+    /// Maps a 10-bit printing density code value, or linear light when
+    /// `linear`, of `channel`.
+    fn apply(&self, channel: usize, value: f32, linear: bool) -> f32 {
+        // Change of log exposure
+        let exposure = match self {
+            Self::PrinterPoints(points) => points[channel] * PRINTER_POINT,
+            Self::Stops(stops) => stops[channel] * std::f32::consts::LOG10_2,
+            Self::GainOffset { gain, offset } => return value * gain[channel] + offset[channel],
+        };
+        if linear {
+            value * 10f32.powf(exposure)
+        } else {
+            value + exposure * (NEGATIVE_GAMMA / DENSITY_PER_CODE) as f32
+        }
+    }
+}
+
+/// Log exposure of a printer point
+const PRINTER_POINT: f32 = 0.025;
+
+/// Decoded Image
+#[derive(Debug, Clone, PartialEq)]
+pub struct DecodedImage {
+    /// Pixels per line
+    pub width: u32,
+    /// Number of lines
+    pub height: u32,
+    /// Samples per pixel
+    pub channels: u32,
+    /// Bits per sample in the file
+    pub bit_depth: u8,
+    /// Samples, row by row, with the channels of a pixel next to each other
+    pub samples: Samples,
+    /// Encoding of the samples
+    pub color_space: ColorSpace,
+}
+
+impl DecodedImage {
+    /// Geometry of the image.
+    pub fn layout(&self) -> Layout {
+        Layout {
+            width: self.width,
+            height: self.height,
+            channels: self.channels,
+            bit_depth: self.bit_depth,
+        }
+    }
+
+    /// Converts the image to 8-bit samples, scaling the code range of
+    /// integer samples and clamping reals from 0.0 to 1.0, with `dither`
+    /// hiding the banding of the lost precision.
+    ///
+    /// 8-bit images are returned as they are.
+    pub fn to_8bit(&self, dither: Dither) -> Result<DecodedImage, CineonError> {
+        let scale = match self.samples {
+            Samples::U8(_) if self.bit_depth == 8 => return Ok(self.clone()),
+            Samples::U8(_) | Samples::U16(_) => 255. / ((1u32 << self.bit_depth) - 1) as f32,
+            Samples::F32(_) | Samples::F64(_) => 255.,
+            _ => return Err(CineonError::UnsupportedFormat),
+        };
+        let width = self.width as usize;
+        let channels = self.channels as usize;
+        let value = |index: usize| self.samples.get(index) as f32 * scale;
+        let samples = match dither {
+            Dither::None => (0..self.samples.len())
+                .map(|index| value(index).round().clamp(0., 255.) as u8)
+                .collect(),
+            Dither::Ordered => (0..self.samples.len())
+                .map(|index| {
+                    let pixel = index / channels;
+                    color::dither8(value(index) / 255., pixel % width, pixel / width)
+                })
+                .collect(),
+            Dither::ErrorDiffusion => {
+                let row_len = width * channels;
+                let mut errors = vec![0f32; 2 * (row_len + 2 * channels)];
+                let mut samples = Vec::with_capacity(self.samples.len());
+                for y in 0..self.height as usize {
+                    // Errors of the current and next rows, with a pixel of
+                    // margin on both sides
+                    let (current, next) = errors.split_at_mut(row_len + 2 * channels);
+                    if y > 0 {
+                        current.copy_from_slice(next);
+                        next.iter_mut().for_each(|error| *error = 0.);
+                    }
+                    for index in 0..row_len {
+                        let at = index + channels;
+                        let wanted = value(y * row_len + index) + current[at];
+                        let quantized = wanted.round().clamp(0., 255.);
+                        let error = wanted - quantized;
+                        current[at + channels] += error * 7. / 16.;
+                        next[at - channels] += error * 3. / 16.;
+                        next[at] += error * 5. / 16.;
+                        next[at + channels] += error / 16.;
+                        samples.push(quantized as u8);
+                    }
+                }
+                samples
+            }
+        };
+        Ok(DecodedImage {
+            bit_depth: 8,
+            samples: Samples::U8(samples),
+            ..*self
+        })
+    }
+
+    /// Rebalances the image by `adjustment`, as colorists do before any
+    /// conversion.
+    ///
+    /// Printing density code values are offset, following the Cineon
+    /// convention of 0.002 density per 10-bit code value and a negative
+    /// gamma of 0.6, and clamped to the code range. Linear images are
+    /// scaled instead. Single-channel images take the red change, and
+    /// mattes are left as they are.
+    pub fn adjust(&self, adjustment: Adjustment) -> Result<DecodedImage, CineonError> {
+        let channels = self.channels as usize;
+        let max = ((1u32 << self.bit_depth.min(16)) - 1) as f32;
+        // Codes of the RGB channels are scaled to 10 bits and back
+        let code = |index: usize, code: f32| match index % channels {
+            channel if channel < 3 => {
+                let code = adjustment.apply(channel, code * 1023. / max, false) * max / 1023.;
+                code.round().clamp(0., max)
+            }
+            _ => code,
+        };
+        let mut image = self.clone();
+        match (&mut image.samples, self.color_space) {
+            (Samples::U8(samples), ColorSpace::CineonLog) => {
+                for (index, sample) in samples.iter_mut().enumerate() {
+                    *sample = code(index, *sample as f32) as u8;
+                }
+            }
+            (Samples::U16(samples), ColorSpace::CineonLog) => {
+                for (index, sample) in samples.iter_mut().enumerate() {
+                    *sample = code(index, *sample as f32) as u16;
+                }
+            }
+            (Samples::F32(samples), ColorSpace::LinearPd | ColorSpace::Linear) => {
+                for (index, sample) in samples.iter_mut().enumerate() {
+                    if index % channels < 3 {
+                        *sample = adjustment.apply(index % channels, *sample, true);
+                    }
+                }
+            }
+            _ => return Err(CineonError::UnsupportedFormat),
+        }
+        Ok(image)
+    }
+
+    /// Converts an image in linear light, such as a render, into printing
+    /// density code values of `bit_depth` bits, up to 16, with the inverse
+    /// of `log`, so that it can be written back as a cineon scan.
+    pub fn to_log(&self, log: &LogConversion, bit_depth: u8) -> Result<DecodedImage, CineonError> {
+        if !matches!(self.color_space, ColorSpace::LinearPd | ColorSpace::Linear)
+            || !(1..=16).contains(&bit_depth)
+        {
+            return Err(CineonError::UnsupportedFormat);
+        }
+        let scale = ((1u32 << bit_depth) - 1) as f32 / 1023.;
+        let codes = (0..self.samples.len())
+            .map(|index| (log.log(self.samples.get(index) as f32) * scale).round());
+        let samples = if bit_depth <= 8 {
+            Samples::U8(codes.map(|code| code as u8).collect())
+        } else {
+            Samples::U16(codes.map(|code| code as u16).collect())
+        };
+        Ok(DecodedImage {
+            bit_depth,
+            samples,
+            color_space: ColorSpace::CineonLog,
+            ..*self
+        })
+    }
+
+    /// Converts an RGB image, with the chromaticities of `colorimetry`,
+    /// into display-ready Rec. 709 samples of 8 or 16 bits.
+    ///
+    /// Printing density code values are converted to linear light with
+    /// `log`, reals being taken as code values normalized from 0.0 to 1.0,
+    /// as decoded by [`Decoder::decode_normalized`](crate::Decoder::decode_normalized),
+    /// while linear images are taken as they are. Colors
+    /// are then moved to the Rec. 709 primaries, adapting the white point
+    /// to D65 with the Bradford transform, brought within the gamut with
+    /// `gamut`, and encoded with the Rec. 709 transfer function.
+    pub fn to_rec709(
+        &self,
+        colorimetry: &Colorimetry,
+        log: &LogConversion,
+        gamut: GamutMapping,
+        bit_depth: u8,
+    ) -> Result<DecodedImage, CineonError> {
+        let target = Colorimetry::new(D65, REC709_PRIMARIES)?;
+        self.to_display(
+            colorimetry,
+            log,
+            &target,
+            (bit_depth, Alpha::Straight),
+            |pixel| gamut.map(pixel).map(color::rec709_oetf),
+        )
+        .map(|image| DecodedImage {
+            color_space: ColorSpace::Rec709,
+            ..image
+        })
+    }
+
+    /// Converts an RGB image, with the chromaticities of `colorimetry`,
+    /// into sRGB samples of 8 or 16 bits for web and preview use, as
+    /// [`DecodedImage::to_rec709`] does but with the sRGB transfer
+    /// function.
+    ///
+    /// The fourth channel of RGBA images is taken as a matte, kept linear
+    /// and combined with the colors as told by `alpha`.
+    pub fn to_srgb(
+        &self,
+        colorimetry: &Colorimetry,
+        log: &LogConversion,
+        gamut: GamutMapping,
+        bit_depth: u8,
+        alpha: Alpha,
+    ) -> Result<DecodedImage, CineonError> {
+        let target = Colorimetry::new(D65, REC709_PRIMARIES)?;
+        self.to_display(colorimetry, log, &target, (bit_depth, alpha), |pixel| {
+            gamut
+                .map(pixel)
+                .map(|value| color::linear_to_srgb(value as f32) as f64)
+        })
+        .map(|image| DecodedImage {
+            color_space: ColorSpace::Srgb,
+            ..image
+        })
+    }
+
+    /// Converts an RGB image, with the chromaticities of `colorimetry`,
+    /// into ACES scene linear reals in the primaries of `space`, adapting
+    /// the white point to D60 with the Bradford transform, so that scans
+    /// can enter an ACES pipeline.
+    ///
+    /// Printing density code values are converted to linear light with
+    /// `log`, reals being taken as normalized code values, while linear
+    /// images are taken as they are. The
+    /// fourth channel of RGBA images is kept as a straight matte.
+    pub fn to_aces(
+        &self,
+        colorimetry: &Colorimetry,
+        log: &LogConversion,
+        space: AcesSpace,
+    ) -> Result<DecodedImage, CineonError> {
+        let (primaries, color_space) = match space {
+            AcesSpace::Aces2065 => (AP0_PRIMARIES, ColorSpace::Aces),
+            AcesSpace::AcesCg => (AP1_PRIMARIES, ColorSpace::AcesCg),
+        };
+        let target = Colorimetry::new(D60, primaries)?;
+        let mut samples = Vec::with_capacity(self.samples.len());
+        self.convert_pixels(colorimetry, log, &target, |rgb, matte| {
+            samples.extend(rgb.iter().chain(matte.iter()).map(|value| *value as f32));
+        })?;
+        Ok(DecodedImage {
+            bit_depth: 32,
+            samples: Samples::F32(samples),
+            color_space,
+            ..*self
+        })
+    }
+
+    /// Renders a reasonable 8-bit sRGB preview in one call, for thumbnails
+    /// and asset browsers: printing densities are converted to linear
+    /// light with the default [`LogConversion`], highlights are rolled off
+    /// with a filmic curve, and the result is quantized with ordered
+    /// dithering.
+    ///
+    /// Primaries are not converted. Single-channel images stay gray and
+    /// the matte of RGBA images is dropped.
+    pub fn preview(&self) -> Result<DecodedImage, CineonError> {
+        let channels = self.channels as usize;
+        let kept = match channels {
+            1 | 3 => channels,
+            4 => 3,
+            _ => return Err(CineonError::UnsupportedFormat),
+        };
+        let log = LogConversion::default();
+        let width = self.width as usize;
+        let pixels = self.samples.len() / channels;
+        let mut samples = Vec::with_capacity(pixels * kept);
+        for pixel in 0..pixels {
+            for channel in 0..kept {
+                let linear = self.linear_sample(pixel * channels + channel, &log)? as f32;
+                let display = color::linear_to_srgb(color::filmic(linear));
+                samples.push(color::dither8(display, pixel % width, pixel / width));
+            }
+        }
+        Ok(DecodedImage {
+            channels: kept as u32,
+            bit_depth: 8,
+            samples: Samples::U8(samples),
+            color_space: ColorSpace::Srgb,
+            ..*self
+        })
+    }
+
+    /// Linear light of the sample at `index`, printing densities being
+    /// converted with `log`.
+    fn linear_sample(&self, index: usize, log: &LogConversion) -> Result<f64, CineonError> {
+        // Reals are normalized code values or linear light already
+        let max_code = match self.samples {
+            Samples::I8(_) | Samples::I16(_) => return Err(CineonError::UnsupportedFormat),
+            Samples::F32(_) | Samples::F64(_) => 1.,
+            _ => ((1u64 << self.bit_depth.min(16)) - 1) as f64,
+        };
+        let value = self.samples.get(index) / max_code;
+        match self.color_space {
+            ColorSpace::CineonLog => Ok(log.linear((value * 1023.) as f32) as f64),
+            ColorSpace::LinearPd | ColorSpace::Linear => Ok(value),
+            _ => Err(CineonError::UnsupportedFormat),
+        }
+    }
+
+    /// Matte of the sample at `index`, from 0.0 to 1.0.
+    fn alpha_sample(&self, index: usize) -> f64 {
+        let value = self.samples.get(index);
+        match self.samples {
+            Samples::F32(_) | Samples::F64(_) => value.clamp(0., 1.),
+            _ => (value / ((1u64 << self.bit_depth.min(16)) - 1) as f64).clamp(0., 1.),
+        }
+    }
+
+    /// Hands the linear light of every pixel of an RGB or RGBA image with
+    /// the chromaticities of `source`, moved to the ones of `target`, to
+    /// `sink`, together with its matte for RGBA images.
+    fn convert_pixels<F: FnMut([f64; 3], Option<f64>)>(
+        &self,
+        source: &Colorimetry,
+        log: &LogConversion,
+        target: &Colorimetry,
+        mut sink: F,
+    ) -> Result<(), CineonError> {
+        let channels = self.channels as usize;
+        if channels != 3 && channels != 4 {
+            return Err(CineonError::UnsupportedFormat);
+        }
+        let matrix = color::multiply(
+            &target.xyz_to_rgb(),
+            &source.rgb_to_xyz_adapted(target.white_point, Adaptation::Bradford),
+        );
+        for start in (0..self.samples.len()).step_by(channels) {
+            let rgb = [
+                self.linear_sample(start, log)?,
+                self.linear_sample(start + 1, log)?,
+                self.linear_sample(start + 2, log)?,
+            ];
+            let matte = (channels == 4).then(|| self.alpha_sample(start + 3));
+            sink(color::transform(&matrix, rgb), matte);
+        }
+        Ok(())
+    }
+
+    /// Converts an RGB or RGBA image with the chromaticities of `source`
+    /// to the ones of `target`, encoding the linear light of each pixel
+    /// with `encode` into samples of the bit depth of `output`, 8 or 16,
+    /// and combining it with any matte as told by its alpha.
+    fn to_display<F: Fn([f64; 3]) -> [f64; 3]>(
+        &self,
+        source: &Colorimetry,
+        log: &LogConversion,
+        target: &Colorimetry,
+        output: (u8, Alpha),
+        encode: F,
+    ) -> Result<DecodedImage, CineonError> {
+        let (bit_depth, alpha) = output;
+        if bit_depth != 8 && bit_depth != 16 {
+            return Err(CineonError::UnsupportedFormat);
+        }
+        let max = ((1u32 << bit_depth) - 1) as f64;
+        let mut values = Vec::with_capacity(self.samples.len());
+        self.convert_pixels(source, log, target, |rgb, matte| {
+            let mut encoded = encode(rgb).to_vec();
+            if let Some(matte) = matte {
+                if alpha == Alpha::Premultiplied {
+                    encoded.iter_mut().for_each(|value| *value *= matte);
+                }
+                encoded.push(matte);
+            }
+            values.extend(
+                encoded
+                    .iter()
+                    .map(|value| (value * max).round().clamp(0., max)),
+            );
+        })?;
+        let samples = if bit_depth == 8 {
+            Samples::U8(values.into_iter().map(|value| value as u8).collect())
+        } else {
+            Samples::U16(values.into_iter().map(|value| value as u16).collect())
+        };
+        Ok(DecodedImage {
+            bit_depth,
+            samples,
+            ..*self
+        })
+    }
+
+    /// Converts an RGB image into a single-channel one, such as a matte or
+    /// a soundtrack scan, by weighing its channels.
+    ///
+    /// Integer samples are rounded and clamped to the range of the bit
+    /// depth. Single-channel images are returned as they are.
+    pub fn to_monochrome(&self, weights: Weights) -> Result<DecodedImage, CineonError> {
+        match self.channels {
+            1 => return Ok(self.clone()),
+            3 => {}
+            _ => return Err(CineonError::UnsupportedFormat),
+        }
+        let weights = weights.coefficients();
+        let bits = self.bit_depth.min(16) as u32;
+        let max = ((1u32 << bits) - 1) as f64;
+        let min = -((1u32 << bits) as f64) / 2.;
+        let integer = |value: f64, min: f64, max: f64| value.round().clamp(min, max);
+        let samples = match &self.samples {
+            Samples::U8(samples) => Samples::U8(weigh(samples, weights, f64::from, |v| {
+                integer(v, 0., max) as u8
+            })),
+            Samples::U16(samples) => Samples::U16(weigh(samples, weights, f64::from, |v| {
+                integer(v, 0., max) as u16
+            })),
+            Samples::I8(samples) => Samples::I8(weigh(samples, weights, f64::from, |v| {
+                integer(v, min, -min - 1.) as i8
+            })),
+            Samples::I16(samples) => Samples::I16(weigh(samples, weights, f64::from, |v| {
+                integer(v, min, -min - 1.) as i16
+            })),
+            Samples::F32(samples) => Samples::F32(weigh(samples, weights, f64::from, |v| v as f32)),
+            Samples::F64(samples) => Samples::F64(weigh(samples, weights, |v| v, |v| v)),
+        };
+        Ok(DecodedImage {
+            channels: 1,
+            samples,
+            ..*self
+        })
+    }
+}
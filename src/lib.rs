@@ -1,17 +1,74 @@
+use std::convert::TryFrom;
+
+pub mod analysis;
+pub mod burnin;
+pub mod density;
+pub mod ocio;
+pub mod patch;
+pub mod presets;
+#[cfg(feature = "testing")]
+pub mod synthetic;
+pub mod user_data;
+pub mod watermark;
+
+mod bits;
+mod cdl;
+mod color;
+mod decoder;
+mod diff;
+mod export;
+#[cfg(feature = "half")]
+mod half;
 mod header;
+mod image;
+mod lut;
+mod offsets;
+mod options;
 mod parser;
+mod sequence;
+mod unpack;
+mod validate;
+mod writer;
+
+pub use bits::{BitReader, BitWriter, ByteOrder};
+pub use cdl::Cdl;
+pub use color::{
+    AcesSpace, Adaptation, ColorSpace, Colorimetry, GamutMapping, LogConversion, Matrix, Transfer,
+    AP0_PRIMARIES, AP1_PRIMARIES, D60, D65, REC709_PRIMARIES,
+};
+pub use decoder::{
+    CancelToken, Decoder, LazyImage, Normalization, ProxyFilter, Rows, SampleTransform, Tile, Tiles,
+};
+pub use diff::FieldDifference;
+pub use export::{ale, csv, CsvColumn};
+pub use header::{
+    film_stock, DateTime, Designator, FilmFormat, Manufacturer, Metric, Packing, StringField,
+    Timecode, Version,
+};
+pub use image::{Adjustment, Alpha, DecodedImage, Dither, Layout, Region, Samples, Weights};
+pub use lut::Lut3d;
+pub use offsets::FieldLocation;
+pub use options::{Limits, ParseOptions, Warning};
+pub use sequence::FrameName;
+pub use validate::{repair, validate, Finding, Severity, ValidationReport};
 
 use header::{
-    DataFormatInfo, FileInformation, FilmInfo, ImageChannel, ImageInfo, OriginationInfo,
-    MAX_ELEMENTS,
+    DataFormatInfo, FileInformation, FilmInfo, ImageChannel, ImageInfo, Interleave, Orientation,
+    OriginationInfo, MAX_ELEMENTS,
 };
 
-use header::{Header, MAGIC_COOKIE};
+use header::{Header, GENERIC_SIZE, INDUSTRY_SIZE, MAGIC_COOKIE};
 use parser::ReadBytes;
-use parser::{check_magick, read_bytes, read_string, BigEndian, Endian, LittleEndian};
+use parser::{
+    check_magick, defined, defined_pair, read_bytes, read_string, BigEndian, Endian, LittleEndian,
+};
+use unpack::DataLayout;
+use user_data::{UserData, UserDataRegistry};
+use writer::{pack_pixels, HeaderWriter};
 
 /// Cineon Error
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum CineonError {
     /// The file is not a Cineon image
     NotCineonImage,
@@ -21,14 +78,112 @@ pub enum CineonError {
     StringConversion,
     /// An error occurred while writing a new Cineon image
     OutputError,
+    /// The image data uses a bit depth or layout that cannot be decoded
+    UnsupportedFormat,
+    /// The output buffer cannot hold the decoded image
+    BufferTooSmall,
+    /// The requested rows or pixels lie outside the image
+    OutOfBounds,
+    /// Decoding was aborted through a cancel token
+    Cancelled,
+    /// The white point or primaries are not valid chromaticities
+    InvalidColorimetry,
+    /// The image claims more resources than allowed by the parse options
+    LimitExceeded,
+    /// The header holds a value outside the specification, rejected by
+    /// strict parsing
+    OutOfSpec(Warning),
+    /// The header describes image data larger than the input, or too
+    /// large to be addressed
+    InvalidSize,
 }
 
 /// Image Data
+#[non_exhaustive]
 pub struct ImageData {
     /// Cineon Header
     pub header: Header,
-    /// Pixels
-    pub pixels: Vec<u8>,
+    /// Image elements, in the order declared by the header, each decoded
+    /// as a single-channel image
+    pub elements: Vec<DecodedImage>,
+}
+
+impl ImageData {
+    /// Cineon header.
+    pub fn header(&self) -> &Header {
+        &self.header
+    }
+
+    /// Image elements, in the order declared by the header.
+    pub fn elements(&self) -> &[DecodedImage] {
+        &self.elements
+    }
+}
+
+/// Image data borrowing its pixels from the input buffer
+#[non_exhaustive]
+pub struct ImageDataRef<'a> {
+    /// Cineon Header
+    pub header: Header,
+    /// Packed image data, as stored in the file
+    pub data: &'a [u8],
+    byte_order: ByteOrder,
+}
+
+impl<'a> ImageDataRef<'a> {
+    /// Cineon header.
+    pub fn header(&self) -> &Header {
+        &self.header
+    }
+
+    /// Packed image data, as stored in the file.
+    pub fn data(&self) -> &'a [u8] {
+        self.data
+    }
+
+    /// Prepares the decoding of the image data, holding every element as a
+    /// channel.
+    pub fn decoder(&self) -> Result<Decoder<'a>, CineonError> {
+        Decoder::new(self.header.clone(), self.data, self.byte_order)
+    }
+
+    /// Geometry of every element, which may differ from one element to
+    /// another.
+    pub fn element_layouts(&self) -> Result<Vec<Layout>, CineonError> {
+        Layout::elements(&self.header)
+    }
+
+    /// Decodes `element` alone, with its own geometry.
+    pub fn decode_element(&self, element: usize) -> Result<DecodedImage, CineonError> {
+        let layout = *self
+            .element_layouts()?
+            .get(element)
+            .ok_or(CineonError::OutOfBounds)?;
+        let data_layout = DataLayout::new(&self.header, self.byte_order)?;
+        data_layout.check(self.data)?;
+        let mut samples = Vec::with_capacity(layout.samples());
+        for y in 0..layout.height {
+            data_layout
+                .unpack_element_row(self.data, element, y, |_, sample| samples.push(sample))?;
+        }
+        let signed = self.header.data_format_info.data_sign;
+        Ok(DecodedImage {
+            width: layout.width,
+            height: layout.height,
+            channels: 1,
+            bit_depth: layout.bit_depth,
+            samples: Samples::from_bits(layout.bit_depth, signed, samples),
+            color_space: ColorSpace::CineonLog,
+        })
+    }
+
+    /// Decodes every element, copying the image data.
+    pub fn to_image_data(&self) -> Result<ImageData, CineonError> {
+        Ok(ImageData {
+            header: self.header.clone(),
+            elements: Cineon::parse_elements(self.data, &self.header, self.byte_order)?,
+        })
+    }
 }
 
 /// Cineon Parser
@@ -43,59 +198,274 @@ impl Cineon {
 
     /// Parses the header of a cineon image.
     pub fn parse_header(&self, input: &[u8]) -> Result<Header, CineonError> {
-        self.parse_header_inner(input).map(|(_, v)| v)
+        self.parse_header_with_options(input, &ParseOptions::default())
+    }
+
+    /// Parses the header of a cineon image, following `options`.
+    pub fn parse_header_with_options(
+        &self,
+        input: &[u8],
+        options: &ParseOptions,
+    ) -> Result<Header, CineonError> {
+        self.parse_header_inner(input, options).map(|(_, v)| v)
+    }
+
+    /// Parses the header of a cineon image, following `options`, along with
+    /// the values it holds outside the specification.
+    pub fn parse_header_with_warnings(
+        &self,
+        input: &[u8],
+        options: &ParseOptions,
+    ) -> Result<(Header, Vec<Warning>), CineonError> {
+        let header = self.parse_header_with_options(input, options)?;
+        let warnings = options::warnings(&header);
+        Ok((header, warnings))
+    }
+
+    /// Parses the header of a cineon image, along with the payload of its
+    /// user area as parsed by the first parser of `registry` sniffing it.
+    ///
+    /// The payload is `None` when there is no user area or no parser
+    /// understands it.
+    pub fn parse_header_with_user_data(
+        &self,
+        input: &[u8],
+        registry: &UserDataRegistry,
+    ) -> Result<(Header, Option<UserData>), CineonError> {
+        let header = self.parse_header(input)?;
+        let user_data = match &header.user_info {
+            Some(user_info) => registry.parse(user_info).transpose()?,
+            None => None,
+        };
+        Ok((header, user_data))
     }
 
     /// Parses image data.
     pub fn parse_image(&self, input: &[u8]) -> Result<ImageData, CineonError> {
-        let (i, header) = self.parse_header_inner(input)?;
-        let image_height = header.image_info.channel[0].lines_per_element;
-
-        let bytes_per_row = if header.image_info.number_of_elements == 1 {
-            Self::bytes_per_row(
-                1, // Monochrome image
-                header.image_info.channel[0].bit_depth as u32,
-                header.image_info.channel[0].pixels_per_line,
-                true,
-            )
-        } else {
-            Self::bytes_per_row(
-                3, // Three planes image
-                header.image_info.channel[0].bit_depth as u32,
-                header.image_info.channel[0].pixels_per_line,
-                true,
-            )
-        };
+        self.parse_image_with_options(input, &ParseOptions::default())
+    }
 
-        let total_bytes = image_height * bytes_per_row;
-        let (_, pixels) = read_bytes(total_bytes)(i)?;
+    /// Parses image data, following `options`.
+    pub fn parse_image_with_options(
+        &self,
+        input: &[u8],
+        options: &ParseOptions,
+    ) -> Result<ImageData, CineonError> {
+        let (data, header) = self.parse_header_inner(input, options)?;
+        let byte_order = Self::byte_order(input);
+        let elements = Self::parse_elements(data, &header, byte_order)?;
 
-        Ok(ImageData {
+        Ok(ImageData { header, elements })
+    }
+
+    /// Parses the header of a cineon image, borrowing its image data
+    /// without decoding or copying it.
+    pub fn parse_image_ref<'a>(&self, input: &'a [u8]) -> Result<ImageDataRef<'a>, CineonError> {
+        let (data, header) = self.parse_header_inner(input, &ParseOptions::default())?;
+        Ok(ImageDataRef {
             header,
-            pixels: pixels.to_owned(),
+            data,
+            byte_order: Self::byte_order(input),
         })
     }
 
-    fn bytes_per_row(samples_per_pixel: u32, bit_depth: u32, width: u32, pad: bool) -> u32 {
-        match bit_depth {
-            1 | 8 | 32 => 4 * ((samples_per_pixel * width * bit_depth + 31) / 32),
-            10 => {
-                if !pad {
-                    4 * ((samples_per_pixel * width * bit_depth + 31) / 32)
-                } else {
-                    4 * (((32 * ((samples_per_pixel * width + 2) / 3)) + 31) / 32)
-                }
-            }
-            12 => {
-                if !pad {
-                    4 * ((samples_per_pixel * width * bit_depth + 31) / 32)
-                } else {
-                    2 * (((16 * samples_per_pixel * width) + 15) / 16)
+    /// Prepares the decoding of image data, holding every element as a
+    /// channel.
+    ///
+    /// All elements must share the same dimensions and bit depth.
+    pub fn decoder<'a>(&self, input: &'a [u8]) -> Result<Decoder<'a>, CineonError> {
+        self.decoder_with_options(input, &ParseOptions::default())
+    }
+
+    /// Prepares the decoding of image data, following `options`.
+    pub fn decoder_with_options<'a>(
+        &self,
+        input: &'a [u8],
+        options: &ParseOptions,
+    ) -> Result<Decoder<'a>, CineonError> {
+        let (i, header) = self.parse_header_inner(input, options)?;
+        Decoder::new(header, i, Self::byte_order(input))
+    }
+
+    /// Wraps image data so that rows are decoded only when accessed.
+    ///
+    /// All elements must share the same dimensions and bit depth.
+    pub fn lazy<'a>(&self, input: &'a [u8]) -> Result<LazyImage<'a>, CineonError> {
+        Ok(LazyImage::new(self.decoder(input)?))
+    }
+
+    /// Decodes image data into a single image holding every element as
+    /// a channel.
+    ///
+    /// All elements must share the same dimensions and bit depth.
+    pub fn decode(&self, input: &[u8]) -> Result<DecodedImage, CineonError> {
+        self.decoder(input)?.decode()
+    }
+
+    /// Decodes image data into reals from 0.0 to 1.0 over the code range,
+    /// as [`Decoder::decode_normalized`] does.
+    pub fn decode_normalized(&self, input: &[u8]) -> Result<DecodedImage, CineonError> {
+        self.decoder(input)?.decode_normalized()
+    }
+
+    /// Rewrites a cineon image with canonical section sizes, cleaned strings
+    /// and a correct file size, keeping its image data untouched.
+    pub fn normalize(&self, input: &[u8]) -> Result<Vec<u8>, CineonError> {
+        let (pixels, header) = self.parse_header_inner(input, &ParseOptions::default())?;
+        Self::write(header, pixels)
+    }
+
+    /// Encodes an image of unsigned integer samples, up to 16 bits, as a
+    /// cineon file with one element per channel, so that single-channel
+    /// images become monochrome files.
+    ///
+    /// Samples must be printing density code values, images converted to
+    /// other color spaces being rejected.
+    ///
+    /// Metadata is taken from `header`, while the image structure is
+    /// rewritten to describe pixel interleaved samples packed into
+    /// left-justified 32-bit words. Elements take the descriptions of the
+    /// header ones when their number matches, otherwise they are designated
+    /// as black and white or red, green and blue.
+    pub fn encode(&self, header: &Header, image: &DecodedImage) -> Result<Vec<u8>, CineonError> {
+        self.encode_with_packing(header, image, Packing::LongWordLeft)
+    }
+
+    /// Encodes an image as [`Cineon::encode`] does, packing its samples
+    /// following `packing`.
+    pub fn encode_with_packing(
+        &self,
+        header: &Header,
+        image: &DecodedImage,
+        packing: Packing,
+    ) -> Result<Vec<u8>, CineonError> {
+        let count = image.channels as usize;
+        if count == 0 || count > MAX_ELEMENTS || image.color_space != ColorSpace::CineonLog {
+            return Err(CineonError::UnsupportedFormat);
+        }
+        let pixels = pack_pixels(image, &packing)?;
+
+        let mut header = header.clone();
+        let image_info = &mut header.image_info;
+        let template = image_info.channel;
+        let same_elements = image_info.number_of_elements as usize == count;
+        let max_code = ((1u32 << image.bit_depth) - 1) as f32;
+        for (element, channel) in image_info.channel.iter_mut().enumerate() {
+            *channel = if element >= count {
+                ImageChannel::default()
+            } else if same_elements {
+                template[element]
+            } else {
+                ImageChannel {
+                    metric: Metric::Universal,
+                    designator: Designator::from(if count == 3 { element as u8 + 1 } else { 0 }),
+                    min_data: 0.,
+                    max_data: max_code,
+                    ..template[0]
                 }
+            };
+            if element < count {
+                channel.bit_depth = image.bit_depth;
+                channel.pixels_per_line = image.width;
+                channel.lines_per_element = image.height;
             }
-            16 => 2 * ((samples_per_pixel * width * bit_depth + 8) / 16),
-            64 => 8 * ((samples_per_pixel * width * bit_depth + 63) / 64),
-            _ => 4 * ((samples_per_pixel * width * bit_depth + 31) / 32),
+        }
+        image_info.number_of_elements = count as u8;
+        image_info.image_orientation = Orientation::TopToBottomLeftToRight;
+        header.data_format_info = DataFormatInfo {
+            interleave: Interleave::Pixel,
+            packing,
+            data_sign: false,
+            line_padding: Some(0),
+            channel_padding: Some(0),
+            ..header.data_format_info
+        };
+        Self::write(header, &pixels)
+    }
+
+    /// Writes `header`, with canonical section sizes and a correct file
+    /// size, followed by `pixels`.
+    fn write(mut header: Header, pixels: &[u8]) -> Result<Vec<u8>, CineonError> {
+        let user_size = header
+            .user_info
+            .as_ref()
+            .map_or(0, |user_info| user_info.len());
+        let image_offset = (GENERIC_SIZE + INDUSTRY_SIZE) as usize + user_size;
+        let file_size =
+            u32::try_from(image_offset + pixels.len()).map_err(|_| CineonError::OutputError)?;
+
+        let file_info = &mut header.file_info;
+        file_info.image_offset = image_offset as u32;
+        file_info.generic_size = GENERIC_SIZE;
+        file_info.industry_size = INDUSTRY_SIZE;
+        file_info.user_size = user_size as u32;
+        file_info.file_size = file_size;
+        file_info.version = "V4.5".to_owned();
+
+        let mut writer = HeaderWriter::new();
+        writer.write_header(&header);
+        let mut output = writer.into_bytes();
+        output.extend_from_slice(pixels);
+        Ok(output)
+    }
+
+    /// Decodes image data into `output`, without allocating, and returns
+    /// the layout of the decoded samples.
+    ///
+    /// Samples are stored row by row, with the channels of a pixel next to
+    /// each other, like in [`DecodedImage`]. All elements must share the
+    /// same dimensions and bit depth.
+    pub fn decode_into(&self, input: &[u8], output: &mut [u16]) -> Result<Layout, CineonError> {
+        self.decoder(input)?.decode_into(output)
+    }
+
+    fn parse_elements(
+        input: &[u8],
+        header: &Header,
+        byte_order: ByteOrder,
+    ) -> Result<Vec<DecodedImage>, CineonError> {
+        let data_layout = DataLayout::new(header, byte_order)?;
+        data_layout.check(input)?;
+        let mut elements: Vec<Vec<u64>> = header
+            .image_info
+            .elements()
+            .map(|channel| {
+                Vec::with_capacity(
+                    channel.pixels_per_line as usize * channel.lines_per_element as usize,
+                )
+            })
+            .collect();
+        for y in 0..data_layout.height() {
+            data_layout.unpack_row(input, y, 0..u32::MAX, |element, _, sample| {
+                elements[element].push(sample)
+            })?;
+        }
+
+        Ok(header
+            .image_info
+            .elements()
+            .zip(elements)
+            .map(|(channel, samples)| DecodedImage {
+                width: channel.pixels_per_line,
+                height: channel.lines_per_element,
+                channels: 1,
+                bit_depth: channel.bit_depth,
+                samples: Samples::from_bits(
+                    channel.bit_depth,
+                    header.data_format_info.data_sign,
+                    samples,
+                ),
+                color_space: ColorSpace::CineonLog,
+            })
+            .collect())
+    }
+
+    #[inline(always)]
+    fn byte_order(input: &[u8]) -> ByteOrder {
+        if Self::is_big_endian(input) {
+            ByteOrder::Big
+        } else {
+            ByteOrder::Little
         }
     }
 
@@ -104,31 +474,51 @@ impl Cineon {
         magick[0] == 0x80 && magick[1] == 0x2a && magick[2] == 0x5f && magick[3] == 0xd7
     }
 
-    fn parse_header_inner<'a>(&self, input: &'a [u8]) -> Result<(&'a [u8], Header), CineonError> {
+    fn parse_header_inner<'a>(
+        &self,
+        input: &'a [u8],
+        options: &ParseOptions,
+    ) -> Result<(&'a [u8], Header), CineonError> {
         let (i, magick_number) = check_magick(input, MAGIC_COOKIE)?;
         let parser: Endian = if Self::is_big_endian(magick_number) {
-            Endian::new(BigEndian::default())
+            Endian::new(BigEndian)
         } else {
-            Endian::new(LittleEndian::default())
+            Endian::new(LittleEndian)
         };
         let (i, file_info) = Self::parse_file_info(i, &parser)?;
+        options.limits.check_user_size(file_info.user_size)?;
         let (i, image_info) = Self::parse_image_info(i, &parser)?;
+        options.limits.check_image(&image_info)?;
         let (i, data_format_info) = Self::parse_data_format_info(i, &parser)?;
         let (i, origination_info) = Self::parse_origination_info(i, &parser)?;
-        let (i, film_info) = if file_info.image_offset > 2048 && file_info.user_size != 0 {
+        // Sections follow the generic one when their size is not zero and
+        // they end before the image data
+        let fits =
+            |end: u64| file_info.image_offset == u32::MAX || file_info.image_offset as u64 >= end;
+        let industry_end = GENERIC_SIZE as u64 + INDUSTRY_SIZE as u64;
+        let (i, film_info, industry_end) = if file_info.industry_size != 0 && fits(industry_end) {
             let (i, film_info) = Self::parse_film_info(i, &parser)?;
-            (i, Some(film_info))
+            (i, Some(film_info), industry_end)
         } else {
-            // 1024 is the film info size
-            let (i, _) = read_bytes(1024usize)(i)?;
-            (i, None)
+            (i, None, GENERIC_SIZE as u64)
         };
-        let (i, user_info) = if file_info.image_offset > 2048 && file_info.user_size != 0 {
-            let (i, user_info) = read_bytes(file_info.user_size)(i)?;
+        let user_size = file_info.user_size;
+        let (i, user_info) = if user_size != 0 && fits(industry_end + user_size as u64) {
+            let (i, user_info) = read_bytes(user_size)(i)?;
             (i, Some(user_info.to_owned()))
         } else {
             (i, None)
         };
+        // Image data starts at the image offset, past any padding following
+        // the header, unless the offset is undefined or points inside it
+        let header_size = input.len() - i.len();
+        let i = match file_info.image_offset {
+            u32::MAX => i,
+            offset if (offset as usize) < header_size => i,
+            offset => input
+                .get(offset as usize..)
+                .ok_or(CineonError::ParserError)?,
+        };
         let header = Header {
             file_info,
             image_info,
@@ -137,6 +527,11 @@ impl Cineon {
             film_info,
             user_info,
         };
+        if options.strict {
+            if let Some(warning) = crate::options::warnings(&header).into_iter().next() {
+                return Err(CineonError::OutOfSpec(warning));
+            }
+        }
 
         Ok((i, header))
     }
@@ -183,7 +578,7 @@ impl Cineon {
         let mut channel: [ImageChannel; MAX_ELEMENTS] = [ImageChannel::default(); MAX_ELEMENTS];
         let mut state = i;
         for item in channel.iter_mut() {
-            let (i, (designator_0, designator_1)) = parser.run(ReadBytes::read_u8_pair)(state)?;
+            let (i, (metric, designator)) = parser.run(ReadBytes::read_u8_pair)(state)?;
             let (i, bit_depth) = parser.run(ReadBytes::read_u8)(i)?;
             let (i, _) = read_bytes(1u8)(i)?;
             let (i, pixels_per_line) = parser.run(ReadBytes::read_u32)(i)?;
@@ -193,7 +588,8 @@ impl Cineon {
             let (i, max_data) = parser.run(ReadBytes::read_f32)(i)?;
             let (i, max_quantity) = parser.run(ReadBytes::read_f32)(i)?;
             *item = ImageChannel {
-                designator: [designator_0, designator_1],
+                metric: metric.into(),
+                designator: designator.into(),
                 bit_depth,
                 pixels_per_line,
                 lines_per_element,
@@ -204,10 +600,10 @@ impl Cineon {
             };
             state = i;
         }
-        let (i, (white_point_x, white_point_y)) = parser.run(ReadBytes::read_f32_pair)(state)?;
-        let (i, (red_primary_x, red_primary_y)) = parser.run(ReadBytes::read_f32_pair)(i)?;
-        let (i, (green_primary_x, green_primary_y)) = parser.run(ReadBytes::read_f32_pair)(i)?;
-        let (i, (blue_primary_x, blue_primary_y)) = parser.run(ReadBytes::read_f32_pair)(i)?;
+        let (i, white_point) = parser.run(ReadBytes::read_f32_pair)(state)?;
+        let (i, red_primary) = parser.run(ReadBytes::read_f32_pair)(i)?;
+        let (i, green_primary) = parser.run(ReadBytes::read_f32_pair)(i)?;
+        let (i, blue_primary) = parser.run(ReadBytes::read_f32_pair)(i)?;
         let (i, label_text) = read_string(200u8)(i)?;
         let (i, _) = read_bytes(28u8)(i)?;
         Ok((
@@ -216,10 +612,10 @@ impl Cineon {
                 image_orientation: image_orientation.into(),
                 number_of_elements,
                 channel,
-                white_point: [white_point_x, white_point_y],
-                red_primary: [red_primary_x, red_primary_y],
-                green_primary: [green_primary_x, green_primary_y],
-                blue_primary: [blue_primary_x, blue_primary_y],
+                white_point: defined_pair(white_point),
+                red_primary: defined_pair(red_primary),
+                green_primary: defined_pair(green_primary),
+                blue_primary: defined_pair(blue_primary),
                 label_text,
             },
         ))
@@ -276,9 +672,9 @@ impl Cineon {
                 input_device,
                 input_device_model_number,
                 input_device_serial_number,
-                x_device_pitch,
-                y_device_pitch,
-                gamma,
+                x_device_pitch: defined(x_device_pitch),
+                y_device_pitch: defined(y_device_pitch),
+                gamma: defined(gamma),
             },
         ))
     }
@@ -309,7 +705,7 @@ impl Cineon {
                 count,
                 format,
                 frame_position,
-                frame_rate,
+                frame_rate: defined(frame_rate),
                 frame_id,
                 slate_info,
             },
@@ -321,20 +717,2132 @@ impl Cineon {
 mod tests {
     use super::*;
 
-    const DATA: &'static [u8] = include_bytes!("../assets/data.cin");
+    const DATA: &[u8] = include_bytes!("../assets/data.cin");
 
     #[test]
     fn is_cineon() {
-        assert_eq!(Cineon::default().is_cineon(DATA), true);
+        assert!(Cineon.is_cineon(DATA));
     }
 
     #[test]
     fn read_header() {
-        assert!(Cineon::default().parse_header(DATA).is_ok());
+        assert!(Cineon.parse_header(DATA).is_ok());
     }
 
     #[test]
     fn read_image() {
-        assert!(Cineon::default().parse_image(DATA).is_ok());
+        let image = Cineon.parse_image(DATA).unwrap();
+        assert_eq!(image.elements.len(), 3);
+        for element in image.elements.iter() {
+            assert_eq!((element.width, element.height), (800, 600));
+            match &element.samples {
+                Samples::U8(samples) => {
+                    assert_eq!(samples.len(), 800 * 600);
+                    assert_eq!(samples[0], 0x18);
+                }
+                _ => panic!("8-bit samples expected"),
+            }
+        }
+    }
+
+    #[test]
+    fn decode() {
+        let image = Cineon.decode(DATA).unwrap();
+        assert_eq!((image.width, image.height, image.channels), (800, 600, 3));
+        assert_eq!(image.samples, Samples::U8(DATA[2048..].to_vec()));
+    }
+
+    #[test]
+    fn read_image_ref() {
+        let image = Cineon.parse_image_ref(DATA).unwrap();
+        assert_eq!(image.data().as_ptr(), DATA[2048..].as_ptr());
+        assert_eq!(
+            image.decoder().unwrap().decode().unwrap(),
+            Cineon.decode(DATA).unwrap()
+        );
+        assert_eq!(
+            image.to_image_data().unwrap().elements,
+            Cineon.parse_image(DATA).unwrap().elements
+        );
+    }
+
+    #[test]
+    fn read_elements_with_own_geometry() {
+        // Channel interleaved elements of 4x2 and 2x1 pixels
+        let mut image = DATA[..2048].to_vec();
+        image[193] = 2;
+        for (channel, (width, height)) in [(4u32, 2u32), (2, 1)].iter().enumerate() {
+            let start = 196 + 28 * channel;
+            image[start + 4..start + 8].copy_from_slice(&width.to_be_bytes());
+            image[start + 8..start + 12].copy_from_slice(&height.to_be_bytes());
+        }
+        image[680] = 2;
+        image.extend_from_slice(&[1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 0, 0]);
+
+        let elements = Cineon.parse_image(&image).unwrap().elements;
+        assert_eq!(
+            elements[0].samples,
+            Samples::U8(vec![1, 2, 3, 4, 5, 6, 7, 8])
+        );
+        assert_eq!((elements[1].width, elements[1].height), (2, 1));
+        assert_eq!(elements[1].samples, Samples::U8(vec![9, 10]));
+
+        let image_ref = Cineon.parse_image_ref(&image).unwrap();
+        let layouts = image_ref.element_layouts().unwrap();
+        assert_eq!((layouts[1].width, layouts[1].height), (2, 1));
+        assert_eq!(image_ref.decode_element(1).unwrap(), elements[1]);
+        assert!(image_ref.decode_element(2).is_err());
+        assert!(Cineon.decoder(&image).is_err());
+    }
+
+    #[test]
+    fn read_image_with_line_padding() {
+        // Pad every 2400 bytes row of the sample image with 8 bytes
+        let mut padded = DATA[..2048].to_vec();
+        padded[684..688].copy_from_slice(&8u32.to_be_bytes());
+        for row in DATA[2048..].chunks(2400) {
+            padded.extend_from_slice(row);
+            padded.extend_from_slice(&[0xff; 8]);
+        }
+
+        let expected = Cineon.parse_image(DATA).unwrap();
+        let image = Cineon.parse_image(&padded).unwrap();
+        for (element, expected) in image.elements.iter().zip(expected.elements.iter()) {
+            assert_eq!(element.samples, expected.samples);
+        }
+    }
+
+    #[test]
+    fn read_image_after_padding() {
+        // Vendor padding between the header and the image data
+        let mut padded = DATA[..2048].to_vec();
+        padded[4..8].copy_from_slice(&2560u32.to_be_bytes());
+        padded.extend_from_slice(&[0xff; 512]);
+        padded.extend_from_slice(&DATA[2048..]);
+
+        assert_eq!(
+            Cineon.decode(&padded).unwrap(),
+            Cineon.decode(DATA).unwrap()
+        );
+        assert_eq!(
+            Cineon.normalize(&padded).unwrap(),
+            Cineon.normalize(DATA).unwrap()
+        );
+    }
+
+    #[test]
+    fn postage_stamp() {
+        assert!(Cineon.parse_header(DATA).unwrap().postage_stamp().is_none());
+
+        let stamp: Vec<u8> = (0..96 * 64 * 3).map(|v| v as u8).collect();
+        let mut image = DATA[..2048].to_vec();
+        image[4..8].copy_from_slice(&(2048 + stamp.len() as u32).to_be_bytes());
+        image[16..20].copy_from_slice(&(stamp.len() as u32).to_be_bytes());
+        image.extend_from_slice(&stamp);
+        image.extend_from_slice(&DATA[2048..]);
+
+        let thumbnail = Cineon
+            .parse_header(&image)
+            .unwrap()
+            .postage_stamp()
+            .unwrap();
+        assert_eq!((thumbnail.width, thumbnail.height), (96, 64));
+        assert_eq!(thumbnail.samples, Samples::U8(stamp));
+    }
+
+    #[test]
+    fn patch_header() {
+        let mut header = Cineon.parse_header(DATA).unwrap();
+        header.image_info.label_text = "fixed".to_owned();
+        let patches = patch::from_edit(DATA, &header).unwrap();
+        assert!(patches
+            .iter()
+            .all(|patch| (452..652).contains(&patch.offset)));
+
+        let mut file = DATA.to_vec();
+        patch::apply(&mut file, &patches, patch::Scope::Header).unwrap();
+        assert_eq!(Cineon.parse_header(&file).unwrap(), header);
+        assert_eq!(file[2048..], DATA[2048..]);
+
+        let pixels = [patch::BytePatch {
+            offset: 2048,
+            bytes: vec![0],
+        }];
+        assert!(patch::apply(&mut file, &pixels, patch::Scope::Header).is_err());
+        patch::apply(&mut file, &pixels, patch::Scope::Anywhere).unwrap();
+        assert_eq!(file[2048], 0);
+
+        // Text after the NUL ending a string does not outlive the edit
+        let mut dirty = DATA.to_vec();
+        dirty[452..459].copy_from_slice(b"abc\0XYZ");
+        let mut header = Cineon.parse_header(&dirty).unwrap();
+        header.image_info.label_text = "abcd".to_owned();
+        let patches = patch::from_edit(&dirty, &header).unwrap();
+        assert_eq!(patches.len(), 1);
+        assert_eq!((patches[0].offset, patches[0].bytes.len()), (452, 200));
+        patch::apply(&mut dirty, &patches, patch::Scope::Header).unwrap();
+        assert_eq!(dirty[452..460], *b"abcd\0\0\0\0");
+
+        // An undefined image offset does not extend the header to the pixels
+        dirty[4..8].copy_from_slice(&[0xff; 4]);
+        assert!(Cineon.parse_header(&dirty).is_ok());
+        assert!(patch::apply(&mut dirty, &pixels, patch::Scope::Header).is_err());
+    }
+
+    #[test]
+    fn reject_absurd_sizes() {
+        let mut image = DATA.to_vec();
+        for channel in 0..3 {
+            let start = 196 + 28 * channel;
+            image[start + 4..start + 12].copy_from_slice(&[0xff; 8]);
+        }
+        assert!(matches!(
+            Cineon.decode(&image),
+            Err(CineonError::InvalidSize)
+        ));
+        assert!(matches!(
+            Cineon.parse_image(&image),
+            Err(CineonError::InvalidSize)
+        ));
+
+        // Truncated image data
+        let truncated = &DATA[..DATA.len() - 1];
+        assert!(matches!(
+            Cineon.decode(truncated),
+            Err(CineonError::InvalidSize)
+        ));
+    }
+
+    #[test]
+    fn parse_limits() {
+        let mut options = ParseOptions::default();
+        options.limits.max_width = Some(800);
+        options.limits.max_decoded_bytes = Some(800 * 600 * 3);
+        assert!(Cineon.decoder_with_options(DATA, &options).is_ok());
+
+        options.limits.max_decoded_bytes = Some(800 * 600 * 3 - 1);
+        let error = Cineon.parse_image_with_options(DATA, &options);
+        assert!(matches!(error, Err(CineonError::LimitExceeded)));
+
+        let mut options = ParseOptions::default();
+        options.limits.max_height = Some(599);
+        let error = Cineon.parse_header_with_options(DATA, &options);
+        assert!(matches!(error, Err(CineonError::LimitExceeded)));
+    }
+
+    #[test]
+    fn strict_parsing() {
+        let mut options = ParseOptions::default();
+        let (_, warnings) = Cineon.parse_header_with_warnings(DATA, &options).unwrap();
+        assert!(warnings.is_empty());
+
+        let mut image = DATA.to_vec();
+        image[192] = 9;
+        image[4..8].copy_from_slice(&1024u32.to_be_bytes());
+        let (_, warnings) = Cineon.parse_header_with_warnings(&image, &options).unwrap();
+        assert_eq!(
+            warnings,
+            [Warning::UndefinedOrientation, Warning::ImageOffset(1024)]
+        );
+
+        options.strict = true;
+        assert!(matches!(
+            Cineon.parse_header_with_options(&image, &options),
+            Err(CineonError::OutOfSpec(Warning::UndefinedOrientation))
+        ));
+    }
+
+    #[test]
+    fn log_conversion() {
+        let mut writer = BitWriter::new(ByteOrder::Big);
+        for (n, code) in [95, 685, 95, 1023, 95, 95].iter().enumerate() {
+            writer.write_bits(*code, 10).unwrap();
+            if n == 2 {
+                writer.align_to_word();
+            }
+        }
+        writer.align_to_word();
+        let image = tiny_image(10, &writer.into_bytes());
+        let decoder = Cineon.decoder(&image).unwrap();
+        let default = decoder.decode_to_float(None).unwrap();
+        let (black, white) = match &default.samples {
+            Samples::F32(samples) => (samples[0], samples[1]),
+            _ => unreachable!(),
+        };
+        assert!(black.abs() < 1e-6);
+        assert!((white - 1.).abs() < 1e-6);
+
+        let log = LogConversion {
+            soft_clip: 40.,
+            ..Default::default()
+        };
+        let decoder = Cineon.decoder(&image).unwrap().log_conversion(log);
+        let clipped = decoder.decode_to_float(None).unwrap();
+        match (&default.samples, &clipped.samples) {
+            (Samples::F32(default), Samples::F32(clipped)) => {
+                // Untouched below the knee, still reaching 1.0 at the
+                // reference white, and rolled off beyond it
+                assert_eq!(default[0], clipped[0]);
+                assert!((clipped[1] - 1.).abs() < 1e-6);
+                assert!(clipped[3] < default[3]);
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn linear_to_log() {
+        let log = LogConversion {
+            soft_clip: 40.,
+            ..Default::default()
+        };
+        let codes: Vec<u16> = vec![0, 95, 300, 650, 685, 900];
+        let image = DecodedImage {
+            width: 2,
+            height: 1,
+            channels: 3,
+            bit_depth: 10,
+            samples: Samples::U16(codes.clone()),
+            color_space: ColorSpace::CineonLog,
+        };
+        assert!(image.to_log(&log, 10).is_err());
+
+        let linear: Vec<f32> = codes
+            .iter()
+            .map(|code| log.linear(*code as f32).max(0.))
+            .collect();
+        let image = DecodedImage {
+            samples: Samples::F32(linear),
+            bit_depth: 32,
+            color_space: ColorSpace::LinearPd,
+            ..image
+        };
+        let encoded = image.to_log(&log, 10).unwrap();
+        assert_eq!(encoded.color_space, ColorSpace::CineonLog);
+        // Code values below the reference black are lost to clamping
+        assert_eq!(
+            encoded.samples,
+            Samples::U16(vec![95, 95, 300, 650, 685, 900])
+        );
+    }
+
+    #[test]
+    fn colorimetry() {
+        // Rec. 709 primaries with a D65 white point
+        let header = Cineon.parse_header(DATA).unwrap();
+        let colorimetry = Colorimetry::from_image_info(&header.image_info).unwrap();
+        let expected = [
+            [0.4124, 0.3576, 0.1805],
+            [0.2126, 0.7152, 0.0722],
+            [0.0193, 0.1192, 0.9505],
+        ];
+        for (row, expected) in colorimetry.rgb_to_xyz().iter().zip(expected.iter()) {
+            for (value, expected) in row.iter().zip(expected.iter()) {
+                assert!((value - expected).abs() < 1e-3);
+            }
+        }
+        let rgb = [0.2, 0.5, 0.8];
+        let xyz = color::transform(&colorimetry.rgb_to_xyz(), rgb);
+        let back = color::transform(&colorimetry.xyz_to_rgb(), xyz);
+        for (value, expected) in back.iter().zip(rgb.iter()) {
+            assert!((value - expected).abs() < 1e-9);
+        }
+
+        let collinear = [[0.2, 0.2], [0.3, 0.3], [0.4, 0.4]];
+        assert!(matches!(
+            Colorimetry::new([0.3127, 0.329], collinear),
+            Err(CineonError::InvalidColorimetry)
+        ));
+        assert!(Colorimetry::new([0.; 2], REC709_PRIMARIES).is_err());
+        assert!(Colorimetry::new(D60, AP0_PRIMARIES).is_ok());
+    }
+
+    #[test]
+    fn chromatic_adaptation() {
+        let header = Cineon.parse_header(DATA).unwrap();
+        let colorimetry = Colorimetry::from_image_info(&header.image_info).unwrap();
+        for adaptation in [Adaptation::Bradford, Adaptation::Cat02] {
+            // White maps to the target white, with the same luminance
+            let matrix = colorimetry.rgb_to_xyz_adapted(D60, adaptation);
+            let [x, y, z] = color::transform(&matrix, [1., 1., 1.]);
+            assert!((y - 1.).abs() < 1e-9);
+            assert!((x / (x + y + z) - D60[0]).abs() < 1e-9);
+            assert!((y / (x + y + z) - D60[1]).abs() < 1e-9);
+
+            // Adapting to the same white point changes nothing
+            let matrix = colorimetry.rgb_to_xyz_adapted(D65, adaptation);
+            for (row, expected) in matrix.iter().zip(colorimetry.rgb_to_xyz().iter()) {
+                for (value, expected) in row.iter().zip(expected.iter()) {
+                    assert!((value - expected).abs() < 1e-6);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn convert_to_rec709() {
+        let header = Cineon.parse_header(DATA).unwrap();
+        let colorimetry = Colorimetry::from_image_info(&header.image_info).unwrap();
+        let log = LogConversion::default();
+        // Reference black, reference white and a bright red
+        let image = DecodedImage {
+            width: 3,
+            height: 1,
+            channels: 3,
+            bit_depth: 10,
+            samples: Samples::U16(vec![95, 95, 95, 685, 685, 685, 685, 95, 95]),
+            color_space: ColorSpace::CineonLog,
+        };
+        let rec709 = image
+            .to_rec709(&colorimetry, &log, GamutMapping::Clip, 8)
+            .unwrap();
+        assert_eq!(rec709.color_space, ColorSpace::Rec709);
+        assert_eq!(
+            rec709.samples,
+            Samples::U8(vec![0, 0, 0, 255, 255, 255, 255, 0, 0])
+        );
+        assert!(rec709
+            .to_rec709(&colorimetry, &log, GamutMapping::Clip, 8)
+            .is_err());
+        assert!(image
+            .to_rec709(&colorimetry, &log, GamutMapping::Clip, 12)
+            .is_err());
+    }
+
+    #[test]
+    fn convert_to_srgb() {
+        let header = Cineon.parse_header(DATA).unwrap();
+        let colorimetry = Colorimetry::from_image_info(&header.image_info).unwrap();
+        let log = LogConversion::default();
+        // Reference white with a half matte
+        let image = DecodedImage {
+            width: 1,
+            height: 1,
+            channels: 4,
+            bit_depth: 10,
+            samples: Samples::U16(vec![685, 685, 685, 511]),
+            color_space: ColorSpace::CineonLog,
+        };
+        let straight = image
+            .to_srgb(&colorimetry, &log, GamutMapping::Clip, 8, Alpha::Straight)
+            .unwrap();
+        assert_eq!(straight.color_space, ColorSpace::Srgb);
+        assert_eq!(straight.samples, Samples::U8(vec![255, 255, 255, 127]));
+        let premultiplied = image
+            .to_srgb(
+                &colorimetry,
+                &log,
+                GamutMapping::Clip,
+                16,
+                Alpha::Premultiplied,
+            )
+            .unwrap();
+        assert_eq!(premultiplied.samples, Samples::U16(vec![32735; 4]));
+    }
+
+    #[test]
+    fn convert_to_aces() {
+        let header = Cineon.parse_header(DATA).unwrap();
+        let colorimetry = Colorimetry::from_image_info(&header.image_info).unwrap();
+        let log = LogConversion::default();
+        let image = DecodedImage {
+            width: 2,
+            height: 1,
+            channels: 3,
+            bit_depth: 10,
+            samples: Samples::U16(vec![685, 685, 685, 685, 95, 95]),
+            color_space: ColorSpace::CineonLog,
+        };
+        for (space, color_space) in [
+            (AcesSpace::Aces2065, ColorSpace::Aces),
+            (AcesSpace::AcesCg, ColorSpace::AcesCg),
+        ] {
+            let aces = image.to_aces(&colorimetry, &log, space).unwrap();
+            assert_eq!(aces.color_space, color_space);
+            let samples = match &aces.samples {
+                Samples::F32(samples) => samples,
+                _ => unreachable!(),
+            };
+            // The reference white stays neutral, and red falls within the
+            // wider gamut
+            for value in &samples[..3] {
+                assert!((value - 1.).abs() < 1e-4);
+            }
+            assert!(samples[3] > samples[4] && samples[4] > -0.1);
+        }
+    }
+
+    #[test]
+    fn printing_density() {
+        let header = Cineon.parse_header(DATA).unwrap();
+        // Codes 0 to 255 stand for densities 0 to 2.048
+        let scale = density::DensityScale::from_channel(&header.image_info.channel[0]);
+        assert!((scale.density(255.) - 2.048).abs() < 1e-6);
+        assert!((scale.code(1.024) - 127.5).abs() < 1e-4);
+
+        let mut channel = header.image_info.channel[0];
+        channel.bit_depth = 10;
+        channel.max_data = 0.;
+        let scale = density::DensityScale::from_channel(&channel);
+        assert!((scale.density(1000.) - 2.).abs() < 1e-9);
+
+        let matrix = [[0.9, 0.1, 0.], [0.05, 0.9, 0.05], [0., 0.1, 0.9]];
+        let printing = [0.8, 1.2, 1.5];
+        let status_m = density::to_status_m(printing, &matrix);
+        let back = density::from_status_m(status_m, &matrix).unwrap();
+        for (value, expected) in back.iter().zip(printing.iter()) {
+            assert!((value - expected).abs() < 1e-9);
+        }
+        assert!(density::from_status_m(status_m, &[[0.; 3]; 3]).is_none());
+    }
+
+    #[test]
+    fn channel_luts() {
+        let lut =
+            SampleTransform::parse_lut("# shaper\nLUT: 3 2\n10\n20\n30 40\n\n50\n60\n").unwrap();
+        assert_eq!(
+            lut,
+            SampleTransform::ChannelLuts(vec![vec![10, 20], vec![30, 40], vec![50, 60]])
+        );
+        let csv = SampleTransform::parse_lut("10,30,50\n20,40,60\n").unwrap();
+        assert_eq!(csv, lut);
+        assert_eq!(
+            SampleTransform::parse_lut("7\n8\n").unwrap(),
+            SampleTransform::Lut(vec![7, 8])
+        );
+        assert!(SampleTransform::parse_lut("LUT: 3 2\n1\n2\n").is_err());
+        assert!(SampleTransform::parse_lut("1,2\n3\n").is_err());
+
+        let image = tiny_image(8, &[0, 1, 0, 1, 0, 1, 0, 0]);
+        let decoder = Cineon.decoder(&image).unwrap().transform(Some(lut));
+        assert_eq!(
+            decoder.decode().unwrap().samples,
+            Samples::U8(vec![10, 40, 50, 20, 30, 60])
+        );
+    }
+
+    #[test]
+    fn cube_lut() {
+        // Inverts red, swaps green and blue
+        let mut cube = "TITLE \"test\"\n# comment\nLUT_3D_SIZE 2\n".to_owned();
+        for b in 0..2 {
+            for g in 0..2 {
+                for r in 0..2 {
+                    cube += &format!("{} {} {}\n", 1 - r, b, g);
+                }
+            }
+        }
+        let lut = Lut3d::parse_cube(&cube).unwrap();
+        assert_eq!(lut.lookup([0.25, 0.5, 1.]), [0.75, 1., 0.5]);
+        assert_eq!(lut.lookup([-1., 2., 0.]), [1., 0., 1.]);
+
+        let image = DecodedImage {
+            width: 1,
+            height: 1,
+            channels: 4,
+            bit_depth: 32,
+            samples: Samples::F32(vec![0.25, 0.5, 1., 0.3]),
+            color_space: ColorSpace::Linear,
+        };
+        assert_eq!(
+            lut.apply(&image).unwrap().samples,
+            Samples::F32(vec![0.75, 1., 0.5, 0.3])
+        );
+
+        assert!(Lut3d::parse_cube("LUT_3D_SIZE 2\n0 0 0\n").is_err());
+        assert!(matches!(
+            Lut3d::parse_cube("LUT_1D_SIZE 2\n0 0 0\n1 1 1\n"),
+            Err(CineonError::UnsupportedFormat)
+        ));
+
+        // Resolve header, with the input range of its Cineon log LUTs
+        let entries = cube.split("LUT_3D_SIZE 2\n").nth(1).unwrap();
+        let resolve = format!(
+            "#Created by: DaVinci Resolve\nLUT_3D_SIZE 2\nLUT_3D_INPUT_RANGE 0.0 2.0\n\n{}",
+            entries
+        );
+        let lut = Lut3d::parse_cube(&resolve).unwrap();
+        assert_eq!((lut.domain_min, lut.domain_max), ([0.; 3], [2.; 3]));
+        assert_eq!(lut.lookup([0.5, 1., 2.]), [0.75, 1., 0.5]);
+    }
+
+    #[test]
+    fn color_decision_list() {
+        let ccc = r#"<ColorCorrectionCollection xmlns="urn:ASC:CDL:v1.01">
+            <ColorCorrection id="shot_010">
+                <SOPNode>
+                    <Slope>2.0 1.0 1.0</Slope>
+                    <Offset>0.0 0.1 0.0</Offset>
+                    <Power>1.0 1.0 2.0</Power>
+                </SOPNode>
+                <SatNode><Saturation>0.0</Saturation></SatNode>
+            </ColorCorrection>
+            <ColorCorrection id="shot_020">
+                <SatNode><Saturation>0.5</Saturation></SatNode>
+            </ColorCorrection>
+        </ColorCorrectionCollection>"#;
+        let cdls = Cdl::parse_xml(ccc).unwrap();
+        assert_eq!(cdls.len(), 2);
+        assert_eq!(cdls[0].id.as_deref(), Some("shot_010"));
+        assert_eq!(cdls[0].slope, [2., 1., 1.]);
+        assert_eq!(cdls[1].slope, [1.; 3]);
+        assert_eq!(cdls[1].saturation, 0.5);
+
+        // Graded to (1.0, 0.6, 0.25), then desaturated to its luma
+        let image = DecodedImage {
+            width: 1,
+            height: 1,
+            channels: 3,
+            bit_depth: 32,
+            samples: Samples::F32(vec![0.5, 0.5, 0.5]),
+            color_space: ColorSpace::Linear,
+        };
+        let graded = cdls[0].apply(&image).unwrap();
+        let luma = 0.2126 + 0.7152 * 0.6 + 0.0722 * 0.25;
+        match graded.samples {
+            Samples::F32(samples) => {
+                assert!(samples.iter().all(|value| (value - luma).abs() < 1e-6))
+            }
+            _ => unreachable!(),
+        }
+        assert!(Cdl::parse_xml("<Slope>1 1</Slope>").is_err());
+    }
+
+    #[test]
+    fn ocio_description() {
+        let log = LogConversion::default();
+        let lut = ocio::spi1d(&log, 1024);
+        assert!(lut.starts_with("Version 1\nFrom 0.0 1.0\nLength 1024\nComponents 1\n{\n"));
+        let values: Vec<f32> = lut
+            .lines()
+            .skip(5)
+            .take_while(|line| *line != "}")
+            .map(|line| line.trim().parse().unwrap())
+            .collect();
+        assert_eq!(values.len(), 1024);
+        assert!(values[95].abs() < 1e-6 && (values[685] - 1.).abs() < 1e-6);
+
+        let header = Cineon.parse_header(DATA).unwrap();
+        let colorimetry = Colorimetry::from_image_info(&header.image_info).unwrap();
+        let colorspace = ocio::colorspace("cineon_scan", "scan.spi1d", &colorimetry.rgb_to_xyz());
+        assert!(colorspace.contains("name: cineon_scan\n"));
+        assert!(colorspace.contains("{src: scan.spi1d, interpolation: linear}"));
+        let matrix = colorspace.split("matrix: [").nth(1).unwrap();
+        assert_eq!(matrix.split(", ").count(), 16);
+    }
+
+    #[test]
+    fn printer_lights() {
+        let image = DecodedImage {
+            width: 1,
+            height: 1,
+            channels: 3,
+            bit_depth: 10,
+            samples: Samples::U16(vec![500, 500, 1020]),
+            color_space: ColorSpace::CineonLog,
+        };
+        // A printer point moves 7.5 code values, clamped to the code range
+        let adjusted = image
+            .adjust(Adjustment::PrinterPoints([2., -4., 1.]))
+            .unwrap();
+        assert_eq!(adjusted.samples, Samples::U16(vec![515, 470, 1023]));
+
+        let image = DecodedImage {
+            samples: Samples::F32(vec![0.25, 0.5, 1.]),
+            bit_depth: 32,
+            color_space: ColorSpace::Linear,
+            ..image
+        };
+        let adjusted = image.adjust(Adjustment::Stops([1., -1., 0.])).unwrap();
+        match adjusted.samples {
+            Samples::F32(samples) => {
+                for (value, expected) in samples.iter().zip([0.5, 0.25, 1.].iter()) {
+                    assert!((value - expected).abs() < 1e-6);
+                }
+            }
+            _ => unreachable!(),
+        }
+
+        // White balance gains neutralizing a cast
+        let balance = Adjustment::GainOffset {
+            gain: [2., 1., 0.5],
+            offset: [0.; 3],
+        };
+        let adjusted = image.adjust(balance).unwrap();
+        assert_eq!(adjusted.samples, Samples::F32(vec![0.5; 3]));
+    }
+
+    #[test]
+    fn gamut_mapping() {
+        let outside = [0.8, 0.3, -0.2];
+        assert_eq!(GamutMapping::Clip.map(outside), [0.8, 0.3, 0.]);
+
+        // Same luma, with the negative component brought to zero
+        let desaturated = GamutMapping::Desaturate.map(outside);
+        let luma = |rgb: [f64; 3]| 0.2126 * rgb[0] + 0.7152 * rgb[1] + 0.0722 * rgb[2];
+        assert!((luma(desaturated) - luma(outside)).abs() < 1e-9);
+        assert!(desaturated[2].abs() < 1e-9);
+        assert!(desaturated[0] < outside[0] && desaturated[1] > outside[1]);
+
+        // Inside colors stay, colors out to the limit are brought within
+        let inside = [0.5, 0.4, 0.3];
+        assert_eq!(GamutMapping::Compress.map(inside), inside);
+        let compressed = GamutMapping::Compress.map(outside);
+        assert!(compressed.iter().all(|value| *value >= 0.));
+        assert_eq!(compressed[0], 0.8);
+        assert!(compressed[2] < 0.1);
+    }
+
+    #[test]
+    fn histogram() {
+        let image = DecodedImage {
+            width: 2,
+            height: 2,
+            channels: 3,
+            bit_depth: 8,
+            samples: Samples::U8(vec![0, 128, 255, 0, 127, 255, 10, 128, 200, 255, 255, 255]),
+            color_space: ColorSpace::CineonLog,
+        };
+        let histogram = analysis::histogram(&image, 2).unwrap();
+        assert_eq!(histogram.counts, vec![vec![3, 1], vec![1, 3], vec![0, 4]]);
+        let full = analysis::histogram(&image, 256).unwrap();
+        assert_eq!(full.counts[0][0], 2);
+        assert_eq!(full.counts[0][10], 1);
+        assert_eq!(full.counts[2][255], 3);
+        assert!(analysis::histogram(&image, 0).is_err());
+    }
+
+    #[test]
+    fn channel_summary() {
+        let image = DecodedImage {
+            width: 5,
+            height: 1,
+            channels: 1,
+            bit_depth: 10,
+            samples: Samples::U16(vec![40, 10, 50, 20, 30]),
+            color_space: ColorSpace::CineonLog,
+        };
+        let summary = analysis::channel_summary(&image, &[0., 50., 90., 100.]).unwrap();
+        assert_eq!(summary.len(), 1);
+        assert_eq!((summary[0].min, summary[0].max), (10., 50.));
+        assert_eq!(summary[0].mean, 30.);
+        assert_eq!(summary[0].percentiles, vec![10., 30., 50., 50.]);
+        assert!(analysis::channel_summary(&image, &[101.]).is_err());
+    }
+
+    #[test]
+    fn clipping_report() {
+        let image = DecodedImage {
+            width: 2,
+            height: 2,
+            channels: 3,
+            bit_depth: 10,
+            samples: Samples::U16(vec![
+                500, 500, 500, 685, 600, 600, 95, 500, 700, 400, 400, 400,
+            ]),
+            color_space: ColorSpace::CineonLog,
+        };
+        let report = analysis::clipping(&image, None).unwrap();
+        assert_eq!(
+            report.channels,
+            vec![
+                analysis::ChannelClipping {
+                    crushed: 1,
+                    blown: 1
+                },
+                analysis::ChannelClipping {
+                    crushed: 0,
+                    blown: 0
+                },
+                analysis::ChannelClipping {
+                    crushed: 0,
+                    blown: 1
+                },
+            ]
+        );
+        assert_eq!(report.clipped_pixels, 2);
+        assert_eq!(report.clipped_percentage(), 50.);
+
+        let report = analysis::clipping(&image, Some((0., 1023.))).unwrap();
+        assert_eq!(report.clipped_pixels, 0);
+        assert!(analysis::clipping(&image, Some((600., 500.))).is_err());
+    }
+
+    #[cfg(feature = "half")]
+    #[test]
+    fn half_floats() {
+        let values = [
+            0.,
+            -0.,
+            1.,
+            -2.5,
+            65504.,
+            1e6,
+            6e-8,
+            1e-9,
+            0.1,
+            f32::INFINITY,
+        ];
+        assert_eq!(
+            values.map(half::f32_to_f16_bits),
+            [0x0000, 0x8000, 0x3c00, 0xc100, 0x7bff, 0x7c00, 0x0001, 0x0000, 0x2e66, 0x7c00]
+        );
+        assert_eq!(half::f32_to_f16_bits(f32::NAN) & 0x7e00, 0x7e00);
+
+        let image = DecodedImage {
+            width: 1,
+            height: 1,
+            channels: 3,
+            bit_depth: 32,
+            samples: Samples::F32(vec![0.5, 1., 2.]),
+            color_space: ColorSpace::Linear,
+        };
+        assert_eq!(image.to_f16_bits().unwrap(), vec![0x3800, 0x3c00, 0x4000]);
+    }
+
+    #[test]
+    fn decode_normalized() {
+        let image = Cineon.decode_normalized(DATA).unwrap();
+        assert_eq!(image.bit_depth, 32);
+        assert_eq!(image.color_space, ColorSpace::CineonLog);
+        let codes = Cineon.decode(DATA).unwrap();
+
+        // Same conversion as from code values
+        let header = Cineon.parse_header(DATA).unwrap();
+        let colorimetry = Colorimetry::from_image_info(&header.image_info).unwrap();
+        let log = LogConversion::default();
+        let convert = |image: &DecodedImage| {
+            image
+                .to_rec709(&colorimetry, &log, GamutMapping::Clip, 8)
+                .unwrap()
+        };
+        assert_eq!(convert(&image), convert(&codes));
+
+        match (&image.samples, &codes.samples) {
+            (Samples::F32(normalized), Samples::U8(codes)) => {
+                assert_eq!(normalized.len(), codes.len());
+                for (value, code) in normalized.iter().zip(codes.iter()).step_by(997) {
+                    assert_eq!(*value, *code as f32 * (1. / 255.));
+                }
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn preview() {
+        let image = Cineon.decode(DATA).unwrap();
+        let preview = image.preview().unwrap();
+        assert_eq!((preview.width, preview.height), (800, 600));
+        assert_eq!((preview.channels, preview.bit_depth), (3, 8));
+        assert_eq!(preview.color_space, ColorSpace::Srgb);
+
+        // Black stays black, and white rolls off below the top
+        let image = DecodedImage {
+            width: 2,
+            height: 1,
+            channels: 1,
+            bit_depth: 10,
+            samples: Samples::U16(vec![95, 685]),
+            color_space: ColorSpace::CineonLog,
+        };
+        match image.preview().unwrap().samples {
+            Samples::U8(samples) => {
+                assert_eq!(samples[0], 0);
+                assert!((220..255).contains(&samples[1]));
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn synthetic_images() {
+        let packings = [
+            Packing::Packed,
+            Packing::ByteLeft,
+            Packing::ByteRight,
+            Packing::WordLeft,
+            Packing::WordRight,
+            Packing::LongWordLeft,
+            Packing::LongWordRight,
+        ];
+        let patterns = [
+            synthetic::Pattern::ColorBars,
+            synthetic::Pattern::GrayRamp,
+            synthetic::Pattern::ResolutionTarget,
+        ];
+        for bit_depth in [1, 8, 10, 12, 16] {
+            for packing in packings.iter() {
+                for pattern in patterns.iter() {
+                    let expected = synthetic::image(*pattern, 37, 16, bit_depth).unwrap();
+                    let file = synthetic::file(*pattern, 37, 16, bit_depth, *packing).unwrap();
+                    assert_eq!(Cineon.decode(&file).unwrap().samples, expected.samples);
+                }
+            }
+        }
+
+        let bars = synthetic::image(synthetic::Pattern::ColorBars, 8, 1, 8).unwrap();
+        assert_eq!(
+            bars.samples,
+            Samples::U8(vec![
+                255, 255, 255, 255, 255, 0, 0, 255, 255, 0, 255, 0, 255, 0, 255, 255, 0, 0, 0, 0,
+                255, 0, 0, 0
+            ])
+        );
+    }
+
+    #[test]
+    fn normalize() {
+        let mut broken = DATA.to_vec();
+        // Wrong file size and a label with trailing garbage
+        broken[20..24].copy_from_slice(&0u32.to_be_bytes());
+        broken[452..460].copy_from_slice(b"label\0\x01x");
+
+        let normalized = Cineon.normalize(&broken).unwrap();
+        assert_eq!(normalized.len(), DATA.len());
+
+        let header = Cineon.parse_header(&normalized).unwrap();
+        assert_eq!(header.file_info.file_size as usize, DATA.len());
+        assert_eq!(header.image_info.label_text, "label");
+        assert_eq!(
+            Cineon.decode(&normalized).unwrap(),
+            Cineon.decode(DATA).unwrap()
+        );
+        assert_eq!(Cineon.normalize(&normalized).unwrap(), normalized);
+    }
+
+    #[test]
+    fn decode_into() {
+        let mut buffer = vec![0u16; 800 * 600 * 3];
+        let layout = Cineon.decode_into(DATA, &mut buffer).unwrap();
+        assert_eq!(layout.samples(), buffer.len());
+        assert!(buffer
+            .iter()
+            .zip(&DATA[2048..])
+            .all(|(a, b)| *a == *b as u16));
+
+        let mut small = vec![0u16; 10];
+        assert!(Cineon.decode_into(DATA, &mut small).is_err());
+    }
+
+    #[test]
+    fn rows() {
+        let decoder = Cineon.decoder(DATA).unwrap();
+        let rows = decoder.rows();
+        assert_eq!(rows.len(), 600);
+        for (row, expected) in rows.zip(DATA[2048..].chunks(2400)) {
+            assert_eq!(row.unwrap(), Samples::U8(expected.to_vec()));
+        }
+        assert!(decoder.row(600).is_err());
+    }
+
+    #[test]
+    fn metadata_card() {
+        let header = Cineon.parse_header(DATA).unwrap();
+        let card = burnin::metadata_card(&header, (640, 360));
+        assert_eq!((card.width, card.height, card.channels), (640, 360, 3));
+        match &card.samples {
+            Samples::U8(samples) => {
+                assert_eq!(samples.len(), 640 * 360 * 3);
+                assert!(samples.contains(&255));
+            }
+            _ => panic!("8-bit samples expected"),
+        }
+
+        // Cards are display images, not printing densities
+        assert_eq!(card.color_space, ColorSpace::Srgb);
+        assert!(Cineon.encode(&header, &card).is_err());
+    }
+
+    #[test]
+    fn lazy_rows() {
+        let mut image = Cineon.lazy(DATA).unwrap();
+        assert_eq!(image.layout().height, 600);
+        assert!(!image.is_decoded(3));
+        let expected = Samples::U8(DATA[2048 + 3 * 2400..2048 + 4 * 2400].to_vec());
+        assert_eq!(image.row(3).unwrap(), &expected);
+        assert!(image.is_decoded(3) && !image.is_decoded(2));
+        assert!(image.row(600).is_err());
+        image.clear();
+        assert!(!image.is_decoded(3));
+    }
+
+    #[test]
+    fn decode_region() {
+        let decoder = Cineon.decoder(DATA).unwrap();
+        let region = decoder.decode_region(Region::new(10, 20, 30, 40)).unwrap();
+        assert_eq!((region.width, region.height, region.channels), (30, 40, 3));
+
+        let expected: Vec<u8> = DATA[2048..]
+            .chunks(2400)
+            .skip(20)
+            .take(40)
+            .flat_map(|row| row[30..120].iter().copied())
+            .collect();
+        assert_eq!(region.samples, Samples::U8(expected));
+        assert!(decoder.decode_region(Region::new(790, 0, 20, 1)).is_err());
+    }
+
+    #[test]
+    fn tiles() {
+        let decoder = Cineon.decoder(DATA).unwrap();
+        let tiles: Vec<Tile> = decoder
+            .tiles(256, 256)
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(tiles.len(), 4 * 3);
+        assert_eq!(tiles[3].region, Region::new(768, 0, 32, 256));
+        assert_eq!(tiles[11].region, Region::new(768, 512, 32, 88));
+        assert_eq!(
+            tiles[5].image,
+            decoder
+                .decode_region(Region::new(256, 256, 256, 256))
+                .unwrap()
+        );
+        assert!(matches!(
+            decoder.tiles(0, 256),
+            Err(CineonError::OutOfBounds)
+        ));
+    }
+
+    #[test]
+    fn normalize_orientation() {
+        let stored = Cineon.decode(DATA).unwrap();
+        let pixel = |image: &DecodedImage, x: usize, y: usize| match &image.samples {
+            Samples::U8(samples) => {
+                let index = (y * image.width as usize + x) * 3;
+                samples[index..index + 3].to_vec()
+            }
+            _ => panic!("8-bit samples expected"),
+        };
+
+        let mut bottom_to_top = DATA.to_vec();
+        bottom_to_top[192] = 2;
+        let decoder = Cineon.decoder(&bottom_to_top).unwrap();
+        assert_eq!(decoder.decode().unwrap(), stored);
+        let image = decoder.normalize_orientation(true).decode().unwrap();
+        assert_eq!(pixel(&image, 5, 0), pixel(&stored, 5, 599));
+
+        let mut transposed = DATA.to_vec();
+        transposed[192] = 4;
+        let decoder = Cineon.decoder(&transposed).unwrap();
+        let image = decoder.normalize_orientation(true).decode().unwrap();
+        assert_eq!((image.width, image.height), (600, 800));
+        assert_eq!(pixel(&image, 7, 300), pixel(&stored, 300, 7));
+    }
+
+    #[test]
+    fn invert_negative() {
+        let positive = Cineon.decode(DATA).unwrap();
+        let decoder = Cineon.decoder(DATA).unwrap().invert_negative(true);
+        assert_eq!(decoder.decode().unwrap(), positive);
+
+        let mut negative = DATA.to_vec();
+        negative[683] = 1;
+        let image = Cineon
+            .decoder(&negative)
+            .unwrap()
+            .invert_negative(true)
+            .decode()
+            .unwrap();
+        match (image.samples, positive.samples) {
+            (Samples::U8(inverted), Samples::U8(samples)) => {
+                assert!(inverted.iter().zip(samples).all(|(a, b)| *a == 255 - b));
+            }
+            _ => panic!("8-bit samples expected"),
+        }
+    }
+
+    #[test]
+    fn region_stats() {
+        let image = Cineon.decode(DATA).unwrap();
+        let stats = analysis::region_stats(&image, &[Region::new(0, 0, 4, 1)]).unwrap();
+        for channel in stats[0].channels.iter() {
+            assert_eq!(channel.mean, 24.);
+            assert_eq!(channel.std_dev, 0.);
+            assert!((channel.mean_density - 24. * 2.046 / 255.).abs() < 1e-9);
+        }
+        assert!(analysis::region_stats(&image, &[Region::new(0, 0, 801, 1)]).is_err());
+    }
+
+    #[test]
+    fn scopes() {
+        let image = Cineon.decode(DATA).unwrap();
+        let waveform = analysis::waveform(&image, 100).unwrap();
+        // 8-bit samples fall on the level matching their code value
+        assert_eq!(waveform.count(0, 24, 0), {
+            let mut count = 0;
+            for y in 0..600 {
+                for x in 0..8 {
+                    count += (DATA[2048 + y * 2400 + x * 3] == 24) as u32;
+                }
+            }
+            count
+        });
+        assert_eq!(waveform.counts.iter().sum::<u32>(), 800 * 600 * 3);
+        assert_eq!(waveform.render().layout().samples(), 100 * 256 * 3);
+        assert!(analysis::waveform(&image, 801).is_err());
+
+        let scope = analysis::vectorscope(&image, 65).unwrap();
+        assert_eq!(scope.counts.iter().sum::<u32>(), 800 * 600);
+        let mono = image.to_monochrome(Weights::Rec709).unwrap();
+        assert!(analysis::vectorscope(&mono, 65).is_err());
+    }
+
+    #[test]
+    fn normalize_samples() {
+        // Reference code values from 24 to 224
+        let mut image = DATA.to_vec();
+        for channel in 0..3 {
+            let start = 196 + 28 * channel;
+            image[start + 12..start + 16].copy_from_slice(&24f32.to_be_bytes());
+            image[start + 20..start + 24].copy_from_slice(&224f32.to_be_bytes());
+        }
+        let row: Vec<f32> = DATA[2048..2048 + 2400]
+            .iter()
+            .map(|code| ((*code as f32 - 24.) * (1. / 200.)).clamp(0., 1.))
+            .collect();
+
+        let decoder = Cineon
+            .decoder(&image)
+            .unwrap()
+            .normalize(Some(Normalization::Unit));
+        assert_eq!(decoder.layout().bit_depth, 32);
+        assert_eq!(decoder.row(0).unwrap(), Samples::F32(row.clone()));
+        assert!(decoder.row_into(0, &mut [0; 2400]).is_err());
+
+        let decoder = decoder.normalize(Some(Normalization::FullRange));
+        let expected: Vec<u16> = row.iter().map(|v| (v * 65535.).round() as u16).collect();
+        assert_eq!(decoder.row(0).unwrap(), Samples::U16(expected.clone()));
+        let mut output = vec![0; 2400];
+        decoder.row_into(0, &mut output).unwrap();
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn decode_to_srgb8() {
+        let decoder = Cineon.decoder(DATA).unwrap();
+        let stride = 800 * 4 + 16;
+        let mut output = vec![0; stride * 600];
+        let layout = decoder.decode_to_srgb8(&mut output, stride).unwrap();
+        assert_eq!((layout.width, layout.height), (800, 600));
+        assert!(output
+            .chunks(stride)
+            .all(|row| row[..3200].chunks(4).all(|pixel| pixel[3] == 0xff)
+                && row[3200..].iter().all(|padding| *padding == 0)));
+
+        // Reference black is shown as black, reference white as white
+        let mut black = tiny_image(10, &[0; 8]);
+        black[2048..2052].copy_from_slice(&(95u32 << 22 | 95 << 12 | 95 << 2).to_be_bytes());
+        black[2052..2056].copy_from_slice(&(685u32 << 22 | 685 << 12 | 685 << 2).to_be_bytes());
+        let mut output = vec![0; 8];
+        Cineon
+            .decoder(&black)
+            .unwrap()
+            .decode_to_srgb8(&mut output, 8)
+            .unwrap();
+        assert_eq!(output, vec![0, 0, 0, 0xff, 0xff, 0xff, 0xff, 0xff]);
+        assert!(Cineon
+            .decoder(DATA)
+            .unwrap()
+            .decode_to_srgb8(&mut output, 8)
+            .is_err());
+    }
+
+    #[test]
+    fn dither_to_8bit() {
+        // A 10-bit ramp from 0 to 1023 over 1024 pixels
+        let ramp = DecodedImage {
+            width: 1024,
+            height: 4,
+            channels: 1,
+            bit_depth: 10,
+            samples: Samples::U16((0..4096).map(|v| v % 1024).collect()),
+            color_space: ColorSpace::CineonLog,
+        };
+        let mean = |image: &DecodedImage| match &image.samples {
+            Samples::U8(samples) => samples.iter().map(|v| *v as f64).sum::<f64>() / 4096.,
+            _ => panic!("8-bit samples expected"),
+        };
+        for dither in [Dither::None, Dither::Ordered, Dither::ErrorDiffusion].iter() {
+            let image = ramp.to_8bit(*dither).unwrap();
+            assert_eq!(image.bit_depth, 8);
+            assert!((mean(&image) - 127.5).abs() < 0.5);
+        }
+        let image = Cineon.decode(DATA).unwrap();
+        assert_eq!(image.to_8bit(Dither::ErrorDiffusion).unwrap(), image);
+    }
+
+    #[test]
+    fn watermark() {
+        let original = Cineon.decode(DATA).unwrap();
+        let mark = watermark::Watermark {
+            facility_id: 0xface,
+            job_id: 1296,
+        };
+        let mut image = original.clone();
+        watermark::embed(&mut image, 42, mark).unwrap();
+        assert_ne!(image, original);
+
+        let extracted = watermark::extract(&image, 42).unwrap();
+        assert_eq!(extracted.watermark, mark);
+        assert_eq!(extracted.confidence, 1.);
+        assert!(watermark::detect(&image, 42, mark).unwrap());
+        assert!(!watermark::detect(&image, 43, mark).unwrap());
+        assert!(!watermark::detect(&original, 42, mark).unwrap());
+    }
+
+    #[test]
+    fn decode_proxy() {
+        let image = Cineon.decode(DATA).unwrap();
+        let decoder = Cineon.decoder(DATA).unwrap();
+        let sample = |x: usize, y: usize, channel: usize| DATA[2048 + y * 2400 + x * 3 + channel];
+
+        let proxy = decoder.decode_proxy(4, ProxyFilter::Nearest).unwrap();
+        assert_eq!((proxy.width, proxy.height), (200, 150));
+        assert_eq!(
+            proxy.samples.get(3 * (7 * 200 + 5) + 1),
+            sample(20, 28, 1) as f64
+        );
+
+        let proxy = decoder.decode_proxy(2, ProxyFilter::Box).unwrap();
+        assert_eq!((proxy.width, proxy.height), (400, 300));
+        let block: u32 = [(10, 6), (11, 6), (10, 7), (11, 7)]
+            .iter()
+            .map(|(x, y)| sample(*x, *y, 2) as u32)
+            .sum();
+        assert_eq!(
+            proxy.samples.get(3 * (3 * 400 + 5) + 2),
+            ((block + 2) / 4) as f64
+        );
+
+        assert_eq!(decoder.decode_proxy(1, ProxyFilter::Box).unwrap(), image);
+
+        // Signed samples are averaged by value
+        let mut signed = tiny_image(8, &[0xff, 0xff, 0xfd, 1, 1, 1, 0, 0]);
+        signed[682] = 1;
+        let decoder = Cineon.decoder(&signed).unwrap();
+        assert_eq!(
+            decoder.decode_proxy(2, ProxyFilter::Box).unwrap().samples,
+            Samples::I8(vec![0, 0, -1])
+        );
+
+        // Sums of large blocks of 16-bit samples do not overflow
+        let white = DecodedImage {
+            width: 257,
+            height: 257,
+            channels: 3,
+            bit_depth: 16,
+            samples: Samples::U16(vec![0xffff; 3 * 257 * 257]),
+            color_space: ColorSpace::CineonLog,
+        };
+        let encoded = Cineon
+            .encode(&Cineon.parse_header(DATA).unwrap(), &white)
+            .unwrap();
+        let decoder = Cineon.decoder(&encoded).unwrap();
+        assert_eq!(
+            decoder.decode_proxy(257, ProxyFilter::Box).unwrap().samples,
+            Samples::U16(vec![0xffff; 3])
+        );
+        assert!(decoder.decode_proxy(0, ProxyFilter::Box).is_err());
+    }
+
+    #[test]
+    fn decode_luma() {
+        let decoder = Cineon.decoder(DATA).unwrap();
+        let expected = decoder
+            .decode()
+            .unwrap()
+            .to_monochrome(Weights::Rec709)
+            .unwrap();
+        assert_eq!(decoder.decode_luma(Weights::Rec709).unwrap(), expected);
+
+        let mono = Cineon.encode(decoder.header(), &expected).unwrap();
+        assert!(Cineon
+            .decoder(&mono)
+            .unwrap()
+            .decode_luma(Weights::Equal)
+            .is_err());
+    }
+
+    #[test]
+    fn decode_rgba16() {
+        let decoder = Cineon.decoder(DATA).unwrap();
+        let mut output = vec![0; 800 * 600 * 4];
+        decoder.decode_rgba16(&mut output).unwrap();
+        let expected: Vec<u16> = DATA[2048..2048 + 3]
+            .iter()
+            .map(|sample| *sample as u16 * 257)
+            .chain(std::iter::once(0xffff))
+            .collect();
+        assert_eq!(&output[..4], &expected[..]);
+        assert!(decoder.decode_rgba16(&mut output[1..]).is_err());
+    }
+
+    #[test]
+    fn cancel_decoding() {
+        let token = CancelToken::new();
+        let decoder = Cineon
+            .decoder(DATA)
+            .unwrap()
+            .cancel_token(Some(token.clone()));
+        assert!(decoder.decode().is_ok());
+        token.cancel();
+        assert!(matches!(decoder.decode(), Err(CineonError::Cancelled)));
+        assert!(matches!(decoder.row(0), Err(CineonError::Cancelled)));
+    }
+
+    #[test]
+    fn decode_to_float() {
+        // Reference black and white of printing density, as 10-bit codes
+        let mut image = tiny_image(10, &[0; 8]);
+        image[2048..2052].copy_from_slice(&(95u32 << 22 | 95 << 12 | 95 << 2).to_be_bytes());
+        image[2052..2056].copy_from_slice(&(685u32 << 22 | 685 << 12 | 685 << 2).to_be_bytes());
+        let decoder = Cineon.decoder(&image).unwrap();
+
+        let linear = decoder.decode_to_float(None).unwrap();
+        assert_eq!(linear.color_space, ColorSpace::LinearPd);
+        match linear.samples {
+            Samples::F32(samples) => {
+                assert!(samples[..3].iter().all(|v| v.abs() < 1e-6));
+                assert!(samples[3..].iter().all(|v| (v - 1.).abs() < 1e-6));
+            }
+            _ => panic!("real samples expected"),
+        }
+
+        let linear = decoder.decode_to_float(Some(Transfer::Linear)).unwrap();
+        assert_eq!(linear.color_space, ColorSpace::Linear);
+        assert_eq!(linear.samples.get(0), (95. / 1023f32) as f64);
+
+        // Red, green and blue video elements
+        for channel in 0..3 {
+            image[197 + 28 * channel] = 4 + channel as u8;
+        }
+        let decoder = Cineon.decoder(&image).unwrap();
+        let video = decoder.decode_to_float(Some(Transfer::Video)).unwrap();
+        assert_eq!(decoder.decode_to_float(None).unwrap(), video);
+    }
+
+    #[test]
+    fn sample_transform() {
+        let expected: Vec<u8> = DATA[2048..2048 + 2400]
+            .iter()
+            .map(|sample| (*sample as u16 * 2 + 1).min(255) as u8)
+            .collect();
+        let decoder = Cineon
+            .decoder(DATA)
+            .unwrap()
+            .transform(Some(SampleTransform::GainOffset {
+                gain: 2.,
+                offset: 1.,
+            }));
+        assert_eq!(decoder.row(0).unwrap(), Samples::U8(expected));
+
+        let decoder = decoder.transform(Some(SampleTransform::Lut(vec![7; 16])));
+        assert_eq!(decoder.row(0).unwrap(), Samples::U8(vec![7; 2400]));
+    }
+
+    #[test]
+    fn signed_samples() {
+        let mut signed = DATA.to_vec();
+        signed[682] = 1;
+        let expected: Vec<i8> = DATA[2048..2048 + 2400]
+            .iter()
+            .map(|sample| *sample as i8)
+            .collect();
+        let decoder = Cineon.decoder(&signed).unwrap();
+        assert_eq!(decoder.row(0).unwrap(), Samples::I8(expected));
+
+        assert_eq!(
+            Samples::from_u16(10, true, vec![0, 511, 512, 1023]),
+            Samples::I16(vec![0, 511, -512, -1])
+        );
+    }
+
+    /// Sample image header turned into a 2x1 image of `bit_depth` bits,
+    /// followed by `data`.
+    fn tiny_image(bit_depth: u8, data: &[u8]) -> Vec<u8> {
+        let mut image = DATA[..2048].to_vec();
+        for channel in 0..3 {
+            let start = 196 + 28 * channel;
+            image[start + 2] = bit_depth;
+            image[start + 4..start + 8].copy_from_slice(&2u32.to_be_bytes());
+            image[start + 8..start + 12].copy_from_slice(&1u32.to_be_bytes());
+        }
+        image.extend_from_slice(data);
+        image
+    }
+
+    #[test]
+    fn real_and_bitmap_samples() {
+        let reals = [0.5f32, -1., 2., 0.25, 8., 1e-3];
+        let data: Vec<u8> = reals.iter().flat_map(|v| v.to_be_bytes()).collect();
+        let image = tiny_image(32, &data);
+        let decoder = Cineon.decoder(&image).unwrap();
+        assert_eq!(
+            decoder.decode().unwrap().samples,
+            Samples::F32(reals.to_vec())
+        );
+        assert!(decoder.decode_into(&mut [0; 6]).is_err());
+
+        let reals = [0.5f64, -1., 2., 0.25, 8., 1e-3];
+        let data: Vec<u8> = reals.iter().flat_map(|v| v.to_be_bytes()).collect();
+        let image = Cineon.decode(&tiny_image(64, &data)).unwrap();
+        assert_eq!(image.samples, Samples::F64(reals.to_vec()));
+
+        let image = Cineon
+            .decode(&tiny_image(1, &[0b1011_0100, 0, 0, 0]))
+            .unwrap();
+        assert_eq!(image.samples, Samples::U8(vec![1, 0, 1, 1, 0, 1]));
+    }
+
+    #[test]
+    fn encode_monochrome() {
+        let header = Cineon.parse_header(DATA).unwrap();
+        let image = Cineon.decode(DATA).unwrap();
+        let mono = image.to_monochrome(Weights::Equal).unwrap();
+        assert_eq!(mono.channels, 1);
+        assert!(image.to_monochrome(Weights::Custom([0., 0., 0.])).is_ok());
+
+        let encoded = Cineon.encode(&header, &mono).unwrap();
+        let header = Cineon.parse_header(&encoded).unwrap();
+        assert_eq!(header.image_info.number_of_elements, 1);
+        assert_eq!(
+            header.image_info.channel[0].designator,
+            Designator::Luminance
+        );
+        assert_eq!(Cineon.decode(&encoded).unwrap(), mono);
+
+        let round_trip = Cineon.encode(&header, &image).unwrap();
+        assert_eq!(Cineon.decode(&round_trip).unwrap(), image);
+
+        let display = DecodedImage {
+            color_space: ColorSpace::Srgb,
+            ..image
+        };
+        assert!(Cineon.encode(&header, &display).is_err());
+    }
+
+    #[test]
+    fn film_stock_lookup() {
+        let film_info = FilmInfo {
+            film_manufacturing_id_code: 1,
+            film_type: 19,
+            ..FilmInfo::default()
+        };
+        assert_eq!(film_info.manufacturer(), Manufacturer::Kodak);
+        assert_eq!(film_info.stock_name(), Some("Kodak Vision3 500T 5219/7219"));
+        assert_eq!(
+            film_stock(Manufacturer::Kodak, 54),
+            Some("Kodak Vision3 Color Digital Intermediate 5254/2254")
+        );
+        assert_eq!(Manufacturer::from(2), Manufacturer::Other(2));
+        assert_eq!(film_stock(Manufacturer::Kodak, 255), None);
+        assert_eq!(Manufacturer::from(42), Manufacturer::Other(42));
+        assert_eq!(u8::from(Manufacturer::Other(42)), 42);
+        assert_eq!(Manufacturer::Other(42).name(), None);
+    }
+
+    #[test]
+    fn film_format() {
+        let film_info = FilmInfo {
+            format: "Academy".to_string(),
+            ..FilmInfo::default()
+        };
+        assert_eq!(film_info.film_format(), FilmFormat::Academy);
+        assert_eq!(FilmFormat::from("Full Aperture"), FilmFormat::Full);
+        assert_eq!(FilmFormat::from("super-35"), FilmFormat::Super35);
+        assert_eq!(FilmFormat::Academy.aperture(), Some((21.95, 16.00)));
+        assert!((FilmFormat::Full.aspect_ratio().unwrap() - 1.333).abs() < 1e-3);
+
+        let other = FilmFormat::from("Techniscope");
+        assert_eq!(other, FilmFormat::Other("Techniscope".to_string()));
+        assert_eq!(other.aperture(), None);
+    }
+
+    #[test]
+    fn channel_designator() {
+        let header = Cineon.parse_header(DATA).unwrap();
+        let channel = &header.image_info.channel;
+        assert_eq!(channel[0].metric, Metric::Universal);
+        assert_eq!(channel[0].designator, Designator::Luminance);
+        assert_eq!(
+            Transfer::from_designator(Metric::Universal, Designator::VideoRed),
+            Transfer::Video
+        );
+        assert_eq!(
+            Transfer::from_designator(Metric::Vendor(3), Designator::VideoRed),
+            Transfer::PrintingDensity
+        );
+        assert_eq!(Designator::from(7), Designator::Other(7));
+        assert_eq!(u8::from(Designator::VideoBlue), 6);
+    }
+
+    #[test]
+    fn film_keycode() {
+        let film_info = FilmInfo {
+            film_manufacturing_id_code: 1,
+            film_type: 19,
+            perfs_offset: 12,
+            prefix: 123_456,
+            count: 7890,
+            ..FilmInfo::default()
+        };
+        assert_eq!(film_info.keycode(), "01 19 12 3456 7890+12");
+        assert_eq!(FilmInfo::default().keycode(), "00 00 00 0000 0000+00");
+    }
+
+    #[test]
+    fn film_timecode() {
+        let film_info = FilmInfo {
+            frame_position: 86_400 + 3_600 + 12,
+            frame_rate: Some(24.),
+            ..FilmInfo::default()
+        };
+        let timecode = film_info.timecode().unwrap();
+        assert_eq!(timecode.to_string(), "01:02:30:12");
+        assert!(FilmInfo::default().timecode().is_none());
+
+        let ntsc = |frame| Timecode::from_frames(frame, 29.97).unwrap().to_string();
+        assert_eq!(ntsc(1799), "00:00:59;29");
+        assert_eq!(ntsc(1800), "00:01:00;02");
+        assert_eq!(ntsc(17_982), "00:10:00;00");
+        assert_eq!(ntsc(107_892), "01:00:00;00");
+        assert_eq!(
+            Timecode::from_frames(3598, 59.94).unwrap().to_string(),
+            "00:00:59;58"
+        );
+        assert_eq!(
+            Timecode::from_frames(254, 255.).unwrap().to_string(),
+            "00:00:00:254"
+        );
+        assert_eq!(Timecode::from_frames(300, 300.), None);
+        assert_eq!(Timecode::from_frames(0, 255.5), None);
+    }
+
+    #[test]
+    fn header_date_time() {
+        let mut header = Cineon.parse_header(DATA).unwrap();
+        assert_eq!(
+            header.file_info.creation().unwrap().to_string(),
+            "2020:02:09 15:33:48"
+        );
+
+        let date_time = DateTime::parse("1998:07:14", "13:05:09EST").unwrap();
+        assert_eq!(date_time.to_string(), "1998:07:14 13:05:09");
+        assert_eq!(
+            DateTime::parse("98-07-14", ""),
+            DateTime::parse("19980714", "00:00")
+        );
+        assert_eq!(DateTime::parse("05/01/02", "").unwrap().year, 2005);
+        assert_eq!(DateTime::parse("1998:13:14", ""), None);
+        assert_eq!(DateTime::parse("1998:07:14", "25:00:00"), None);
+
+        header.file_info.set_creation(date_time);
+        header.origination_info.set_source(date_time);
+        let image = Cineon.decode(DATA).unwrap();
+        let encoded = Cineon.encode(&header, &image).unwrap();
+        let header = Cineon.parse_header(&encoded).unwrap();
+        assert_eq!(header.file_info.creation(), Some(date_time));
+        assert_eq!(header.origination_info.source(), Some(date_time));
+    }
+
+    #[test]
+    fn pixel_aspect_ratio() {
+        let anamorphic = OriginationInfo {
+            x_device_pitch: Some(40.),
+            y_device_pitch: Some(80.),
+            ..OriginationInfo::default()
+        };
+        assert_eq!(anamorphic.pixel_aspect_ratio(), Some(2.));
+
+        let undefined = OriginationInfo {
+            x_device_pitch: None,
+            ..anamorphic
+        };
+        assert_eq!(undefined.pixel_aspect_ratio(), None);
+        assert_eq!(OriginationInfo::default().pixel_aspect_ratio(), None);
+    }
+
+    #[test]
+    fn header_display() {
+        let header = Cineon.parse_header(DATA).unwrap();
+        let summary = header.to_string();
+        assert!(summary.starts_with("File Information\n"));
+        assert!(summary.contains("  Creation time           15:33:48UT\n"));
+        assert!(summary.contains("    Size                  800x600\n"));
+        assert!(summary.contains("  Packing                 LongWordLeft\n"));
+        assert!(
+            summary.contains("Film Information\n  Keycode                 00 00 00 0000 0000+00\n")
+        );
+        assert!(!summary.contains("Element 3"));
+    }
+
+    #[test]
+    fn header_json() {
+        let mut header = Cineon.parse_header(DATA).unwrap();
+        header.image_info.label_text = "scan \"A\"\n".to_string();
+        header.origination_info.gamma = None;
+
+        let json = header.to_json();
+        assert!(json.starts_with("{\"file_info\":{\"magic_number\":2150260695,"));
+        assert!(json.contains("\"label_text\":\"scan \\\"A\\\"\\n\""));
+        assert!(json.contains("\"white_point\":[0.3127,0.329]"));
+        assert!(json.contains("\"gamma\":null"));
+        assert!(json.contains("\"film_info\":{\"film_manufacturing_id_code\":0,"));
+        assert!(json.ends_with("\"slate_info\":\"\"},\"user_info_size\":null}"));
+        assert_eq!(json.matches("\"bit_depth\":8").count(), 3);
+
+        let pretty = header.to_json_pretty();
+        assert!(pretty.starts_with("{\n  \"file_info\": {\n    \"magic_number\": 2150260695,\n"));
+        let compact: String = pretty
+            .lines()
+            .map(|line| line.trim_start().replace("\": ", "\":"))
+            .collect();
+        assert_eq!(compact, json);
+    }
+
+    #[test]
+    fn header_xml() {
+        let mut header = Cineon.parse_header(DATA).unwrap();
+        header.image_info.label_text = "R&D <scan>".to_string();
+
+        let xml = header.to_xml();
+        assert!(xml.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<CineonHeader"));
+        assert!(xml.contains("\n    <MagicNumber>2150260695</MagicNumber>\n"));
+        assert!(xml.contains("<Label>R&amp;D &lt;scan&gt;</Label>"));
+        assert!(xml.contains("<WhitePoint>0.3127 0.329</WhitePoint>"));
+        assert_eq!(xml.matches("    <Channel>\n").count(), 3);
+        assert_eq!(xml.matches("<BitsPerPixel>8</BitsPerPixel>").count(), 3);
+        assert!(xml.contains("  <MotionPictureFilmInformation>\n"));
+        assert!(xml.ends_with("</MotionPictureFilmInformation>\n</CineonHeader>\n"));
+    }
+
+    #[test]
+    fn ale_export() {
+        let header = Cineon.parse_header(DATA).unwrap();
+        let scanned = Header {
+            film_info: Some(FilmInfo {
+                film_manufacturing_id_code: 1,
+                film_type: 19,
+                prefix: 123_456,
+                count: 7890,
+                frame_position: 86_400,
+                frame_rate: Some(24.),
+                ..FilmInfo::default()
+            }),
+            ..header.clone()
+        };
+
+        let ale = ale(&[scanned, header]);
+        let lines: Vec<&str> = ale.lines().collect();
+        assert_eq!(
+            lines[..4],
+            [
+                "Heading",
+                "FIELD_DELIM\tTABS",
+                "VIDEO_FORMAT\tFILM",
+                "FPS\t24"
+            ]
+        );
+        assert_eq!(lines[lines.len() - 3], "Data");
+        assert_eq!(
+            lines[lines.len() - 2],
+            "abydos\tV\t01:00:00:00\t01:00:00:01\t24\t01 19 12 3456 7890+00\tabydos.cin"
+        );
+        assert_eq!(lines[lines.len() - 1], "abydos\tV\t\t\t\t\tabydos.cin");
+    }
+
+    #[test]
+    fn csv_export() {
+        let header = Cineon.parse_header(DATA).unwrap();
+        let labelled = Header {
+            image_info: ImageInfo {
+                label_text: "take \"2\", reel 1".to_string(),
+                ..header.image_info.clone()
+            },
+            ..header.clone()
+        };
+
+        let csv = csv([&header, &labelled], CsvColumn::DEFAULT);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(
+            lines[0],
+            "file_name,width,height,bit_depth,keycode,creation_date,creation_time"
+        );
+        assert_eq!(lines[1], "abydos.cin,800,600,8,,2020:02:09,15:33:48UT");
+        assert_eq!(lines.len(), 3);
+
+        let csv = super::csv(&[labelled], &[CsvColumn::Elements, CsvColumn::Label]);
+        assert_eq!(csv, "elements,label\n3,\"take \"\"2\"\", reel 1\"\n");
+    }
+
+    #[test]
+    fn header_xmp() {
+        let header = Cineon.parse_header(DATA).unwrap();
+        let xmp = header.to_xmp();
+        assert!(xmp.starts_with("<?xpacket begin="));
+        assert!(xmp.contains("   <dc:source>abydos.cin</dc:source>\n"));
+        assert!(xmp.contains("   <xmp:CreateDate>2020-02-09T15:33:48</xmp:CreateDate>\n"));
+        assert!(xmp.contains(
+            "<tiff:BitsPerSample><rdf:Seq><rdf:li>8</rdf:li><rdf:li>8</rdf:li><rdf:li>8</rdf:li>"
+        ));
+        assert!(xmp.contains("<stDim:w>800</stDim:w><stDim:h>600</stDim:h>"));
+        assert!(!xmp.contains("dc:title"));
+        assert!(!xmp.contains("xmpDM:startTimecode"));
+
+        let scanned = Header {
+            film_info: Some(FilmInfo {
+                frame_position: 1800,
+                frame_rate: Some(29.97),
+                ..FilmInfo::default()
+            }),
+            ..header
+        };
+        assert!(scanned.to_xmp().contains(concat!(
+            "<xmpDM:timeFormat>2997DropTimecode</xmpDM:timeFormat>",
+            "<xmpDM:timeValue>00:01:00;02</xmpDM:timeValue>"
+        )));
+    }
+
+    #[test]
+    fn header_diff() {
+        let header = Cineon.parse_header(DATA).unwrap();
+        assert!(header.diff(&header).is_empty());
+
+        let mut rescan = header.clone();
+        rescan.image_info.channel[1].bit_depth = 10;
+        rescan.file_info.filename = "rescan.cin".to_string();
+        rescan.image_info.number_of_elements = 4;
+        let differences = header.diff(&rescan);
+        let field = |field: &str, left: &str, right: &str| FieldDifference {
+            field: field.to_string(),
+            left: left.to_string(),
+            right: right.to_string(),
+        };
+        assert_eq!(
+            differences[0],
+            field("file_info.filename", "\"abydos.cin\"", "\"rescan.cin\"")
+        );
+        assert_eq!(
+            differences[1],
+            field("image_info.number_of_elements", "3", "4")
+        );
+        assert_eq!(
+            differences[2],
+            field("image_info.channel[1].bit_depth", "8", "10")
+        );
+        assert_eq!(
+            differences[3],
+            field("image_info.channel[3].metric", "none", "0")
+        );
+        assert_eq!(differences.len(), 12);
+    }
+
+    #[test]
+    fn validate_header() {
+        let header = Cineon.parse_header(DATA).unwrap();
+        let report = validate(&header);
+        assert!(report.is_valid());
+        assert!(report.findings.is_empty());
+
+        let mut broken = header;
+        broken.file_info.image_offset = 1024;
+        broken.image_info.channel[1].bit_depth = 40;
+        broken.image_info.channel[2].bit_depth = 7;
+        broken.data_format_info.packing = Packing::Undefined;
+        let report = validate(&broken);
+        assert!(!report.is_valid());
+        let fields: Vec<(Severity, &str)> = report
+            .findings
+            .iter()
+            .map(|finding| (finding.severity, finding.field.as_str()))
+            .collect();
+        assert_eq!(
+            fields,
+            [
+                (Severity::Error, "file_info.image_offset"),
+                (Severity::Error, "image_info.channel[1].bit_depth"),
+                (Severity::Warning, "image_info.channel[2].bit_depth"),
+                (Severity::Warning, "data_format_info.packing"),
+            ]
+        );
+        assert_eq!(report.errors().count(), 2);
+        assert_eq!(report.warnings().count(), 2);
+    }
+
+    #[test]
+    fn lossy_strings() {
+        let mut image = DATA.to_vec();
+        let label = StringField::Label.offset();
+        image[label..label + 9].copy_from_slice(b"Caf\xe9\0junk");
+        let source = StringField::SourceImageFileName.offset();
+        image[source..source + 2].copy_from_slice(b"\0\0");
+
+        let header = Cineon.parse_header(&image).unwrap();
+        assert_eq!(header.image_info.label_text, "Caf\u{e9}");
+        assert_eq!(
+            header.origination_info.source_image_file_name,
+            "abydos.cin"[2..]
+        );
+        assert_eq!(header.file_info.version, "V4.5");
+
+        let raw = StringField::Label.raw(&image).unwrap();
+        assert_eq!(raw.len(), 200);
+        assert_eq!(&raw[..9], b"Caf\xe9\0junk");
+        assert_eq!(
+            StringField::Filename.raw(&image).unwrap()[..10],
+            *b"abydos.cin"
+        );
+        assert_eq!(StringField::SlateInfo.raw(&image[..1200]), None);
+    }
+
+    #[test]
+    fn undefined_reals() {
+        let header = Cineon.parse_header(DATA).unwrap();
+        assert_eq!(header.image_info.white_point, [Some(0.3127), Some(0.329)]);
+        assert_eq!(header.origination_info.gamma, Some(0.45454547));
+
+        let mut image = DATA.to_vec();
+        let undefined = 0x7F80_0000u32.to_be_bytes();
+        image[424..428].copy_from_slice(&undefined);
+        image[980..984].copy_from_slice(&undefined);
+        let header = Cineon.parse_header(&image).unwrap();
+        assert_eq!(header.image_info.white_point, [Some(0.3127), None]);
+        assert_eq!(header.image_info.red_primary, [Some(0.64), Some(0.33)]);
+        assert_eq!(header.origination_info.gamma, None);
+        assert!(Colorimetry::from_image_info(&header.image_info).is_err());
+
+        let encoded = Cineon
+            .encode(&header, &Cineon.decode(&image).unwrap())
+            .unwrap();
+        assert_eq!(encoded[420..424], DATA[420..424]);
+        assert_eq!(encoded[424..428], undefined);
+        assert_eq!(encoded[980..984], undefined);
+    }
+
+    #[test]
+    fn industry_and_user_sections() {
+        let header = Cineon.parse_header(DATA).unwrap();
+        let image = Cineon.decode(DATA).unwrap();
+        assert_eq!(
+            (header.film_info.is_some(), header.user_info.is_some()),
+            (true, false)
+        );
+        // The industry section of the sample is zeroed
+        assert!(header.film_info.as_ref().unwrap().is_blank());
+
+        let film_info = FilmInfo {
+            film_type: 19,
+            frame_rate: Some(24.),
+            ..FilmInfo::default()
+        };
+        let user_info = vec![1, 2, 3, 4];
+        for (film_info, user_info) in [
+            (Some(film_info.clone()), Some(user_info.clone())),
+            (Some(film_info), None),
+            (None, Some(user_info.clone())),
+        ] {
+            let sections = Header {
+                film_info: film_info.clone(),
+                user_info: user_info.clone(),
+                ..header.clone()
+            };
+            let encoded = Cineon.encode(&sections, &image).unwrap();
+            let parsed = Cineon.parse_header(&encoded).unwrap();
+            // Missing film information is written as undefined fields
+            match film_info {
+                Some(_) => assert_eq!(parsed.film_info, film_info),
+                None => assert!(parsed.film_info.unwrap().is_blank()),
+            }
+            assert_eq!(parsed.user_info, user_info);
+        }
+
+        // Without industry section, user data follows the generic one
+        let sections = Header {
+            user_info: Some(user_info.clone()),
+            ..header
+        };
+        let mut encoded = Cineon.encode(&sections, &image).unwrap();
+        encoded.drain(1024..2048);
+        encoded[4..8].copy_from_slice(&(1024 + 4u32).to_be_bytes());
+        encoded[12..16].copy_from_slice(&0u32.to_be_bytes());
+        let parsed = Cineon.parse_header(&encoded).unwrap();
+        assert_eq!(parsed.film_info, None);
+        assert_eq!(parsed.user_info, Some(user_info));
+        assert!(validate(&parsed).findings.is_empty());
+        assert_eq!(Cineon.decode(&encoded).unwrap(), image);
+    }
+
+    #[test]
+    fn active_elements() {
+        let mut header = Cineon.parse_header(DATA).unwrap();
+        assert_eq!(header.image_info.elements().len(), 3);
+        assert!(header
+            .image_info
+            .elements()
+            .all(|channel| channel.pixels_per_line == 800));
+
+        header.image_info.number_of_elements = 0;
+        assert_eq!(header.image_info.elements().count(), 0);
+        header.image_info.number_of_elements = 200;
+        assert_eq!(header.image_info.elements().len(), MAX_ELEMENTS);
+    }
+
+    #[test]
+    fn scanner_presets() {
+        use crate::presets::Scanner;
+
+        let header = Scanner::Arriscan.header();
+        let channels: Vec<_> = header.image_info.elements().collect();
+        assert_eq!(channels.len(), 3);
+        assert_eq!(channels[2].designator, Designator::Blue);
+        assert_eq!(channels[0].pixels_per_line, 4096);
+        assert_eq!(header.origination_info.input_device, "ARRISCAN");
+        assert_eq!(header.film_info.unwrap().film_format(), FilmFormat::Full);
+
+        let genesis = Scanner::Genesis.header();
+        assert!(genesis.film_info.is_none());
+        assert!(Colorimetry::from_image_info(&genesis.image_info).is_ok());
+
+        let image = Cineon.decode(&tiny_image(10, &[0; 8])).unwrap();
+        let encoded = Cineon.encode(&genesis, &image).unwrap();
+        let parsed = Cineon.parse_header(&encoded).unwrap();
+        assert_eq!(parsed.image_info.channel[0].designator, Designator::Red);
+        assert_eq!(parsed.origination_info.input_device, "Genesis");
+        assert!(validate(&parsed).is_valid());
+        assert_eq!(Cineon.decode(&encoded).unwrap(), image);
+    }
+
+    #[test]
+    fn user_data_registry() {
+        use crate::user_data::UserDataParser;
+        use std::any::Any;
+
+        struct Tagged;
+
+        impl UserDataParser for Tagged {
+            fn name(&self) -> &'static str {
+                "tagged"
+            }
+
+            fn sniff(&self, data: &[u8]) -> bool {
+                data.starts_with(b"TAG:")
+            }
+
+            fn parse(&self, data: &[u8]) -> Result<Box<dyn Any + Send + Sync>, CineonError> {
+                let text = std::str::from_utf8(&data[4..]).map_err(|_| CineonError::ParserError)?;
+                Ok(Box::new(text.to_string()))
+            }
+        }
+
+        let registry = UserDataRegistry::new().register(Tagged);
+        assert_eq!(registry.names().collect::<Vec<_>>(), ["tagged"]);
+
+        let image = Cineon.decode(DATA).unwrap();
+        let mut header = Cineon.parse_header(DATA).unwrap();
+        let (_, user_data) = Cineon.parse_header_with_user_data(DATA, &registry).unwrap();
+        assert!(user_data.is_none());
+
+        header.user_info = Some(b"TAG:reel 7".to_vec());
+        let encoded = Cineon.encode(&header, &image).unwrap();
+        let (_, user_data) = Cineon
+            .parse_header_with_user_data(&encoded, &registry)
+            .unwrap();
+        let user_data = user_data.unwrap();
+        assert_eq!(user_data.name(), "tagged");
+        assert!(!user_data.is::<u32>());
+        assert_eq!(user_data.downcast_ref::<String>().unwrap(), "reel 7");
+        assert_eq!(user_data.downcast::<String>().unwrap(), "reel 7");
+
+        header.user_info = Some(b"TAG:\xff".to_vec());
+        let encoded = Cineon.encode(&header, &image).unwrap();
+        assert!(Cineon
+            .parse_header_with_user_data(&encoded, &registry)
+            .is_err());
+        header.user_info = Some(b"other".to_vec());
+        let encoded = Cineon.encode(&header, &image).unwrap();
+        let (_, user_data) = Cineon
+            .parse_header_with_user_data(&encoded, &registry)
+            .unwrap();
+        assert!(user_data.is_none());
+    }
+
+    #[test]
+    fn postage_stamp_parser() {
+        use crate::user_data::PostageStamp;
+
+        let registry = UserDataRegistry::new().with_builtin();
+        assert_eq!(registry.names().collect::<Vec<_>>(), ["postage stamp"]);
+
+        let mut user_info: Vec<u8> = (0..96 * 64 * 3).map(|i| i as u8).collect();
+        user_info.extend_from_slice(b"vendor");
+        let header = Header {
+            user_info: Some(user_info),
+            ..Cineon.parse_header(DATA).unwrap()
+        };
+        let encoded = Cineon
+            .encode(&header, &Cineon.decode(DATA).unwrap())
+            .unwrap();
+        let (header, user_data) = Cineon
+            .parse_header_with_user_data(&encoded, &registry)
+            .unwrap();
+        let stamp = user_data.unwrap().downcast::<PostageStamp>().unwrap();
+        assert_eq!(Some(stamp.image), header.postage_stamp());
+        assert_eq!(stamp.remainder, b"vendor");
+
+        assert!(registry.parse(&[0; 100]).is_none());
+    }
+
+    #[test]
+    fn header_version() {
+        let header = Cineon.parse_header(DATA).unwrap();
+        assert_eq!(header.file_info.header_version(), Version::V4_5);
+        assert_eq!(Version::from("v4.2"), Version::Other(4, 2));
+        assert_eq!(Version::from("v4.2").is_legacy(), Some(true));
+        assert_eq!(Version::from("V5.0").is_legacy(), Some(false));
+        assert_eq!(Version::from("beta"), Version::Unknown("beta".to_string()));
+        assert_eq!(Version::from("").is_legacy(), None);
+    }
+
+    #[test]
+    fn header_field_locations() {
+        let header = Cineon.parse_header(DATA).unwrap();
+        let locations = header.field_locations();
+        let location = |field: &str| {
+            let location = locations.iter().find(|location| location.field == field);
+            location.map(|location| (location.offset, location.size))
+        };
+        assert_eq!(location("file_info.image_offset"), Some((4, 4)));
+        assert_eq!(location("image_info.image_orientation"), Some((192, 1)));
+        assert_eq!(location("image_info.channel[1].bit_depth"), Some((226, 1)));
+        assert_eq!(location("image_info.white_point"), Some((420, 8)));
+        assert_eq!(
+            location("image_info.label_text"),
+            Some((StringField::Label.offset(), 200))
+        );
+        assert_eq!(location("origination_info.gamma"), Some((980, 4)));
+        assert_eq!(
+            location("film_info.slate_info"),
+            Some((StringField::SlateInfo.offset(), 200))
+        );
+        assert_eq!(location("user_info"), None);
+
+        // Fields are contiguous and cover both sections
+        let end = locations.iter().try_fold(0, |offset, location| {
+            (location.offset == offset).then_some(offset + location.size)
+        });
+        assert_eq!(end, Some(2048));
+    }
+
+    #[test]
+    fn header_hexdump() {
+        let header = Cineon.parse_header(DATA).unwrap();
+        let dump = header.hexdump(DATA);
+        assert!(dump.starts_with("00000000  file_info.magic_number = 2150260695\n"));
+        assert!(dump.contains("00000004  file_info.image_offset = 2048\n  00000004  00 00 08 00"));
+        assert!(dump.contains("|abydos.cin......|"));
+        assert!(dump.contains("image_info.white_point = (0.3127, 0.329)"));
+        assert!(dump.contains("film_info.reserved\n  0000051c  00"));
+        assert!(dump.contains("\n  *\n"));
+
+        // The dump stops at the end of the input
+        let dump = header.hexdump(&DATA[..6]);
+        assert!(dump.ends_with("00000004  file_info.image_offset = 2048\n  00000004  00 00                                            |..|\n"));
+    }
+
+    #[test]
+    fn repair_header_sizes() {
+        let mut header = Cineon.parse_header(DATA).unwrap();
+        // The file size of the sample is larger than the file
+        let changes = repair(&mut header, DATA.len() as u64);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].left, "1928192");
+        assert!(repair(&mut header, DATA.len() as u64).is_empty());
+
+        header.file_info.image_offset = 12;
+        header.file_info.generic_size = 0;
+        header.file_info.file_size = 7;
+        let changes = repair(&mut header, DATA.len() as u64);
+        let changed = |field: &str| {
+            let change = changes.iter().find(|change| change.field == field);
+            change.map(|change| (change.left.as_str(), change.right.as_str()))
+        };
+        assert_eq!(changes.len(), 3);
+        assert_eq!(changed("file_info.image_offset"), Some(("12", "2048")));
+        assert_eq!(changed("file_info.generic_size"), Some(("0", "1024")));
+        let file_size = DATA.len().to_string();
+        assert_eq!(
+            changed("file_info.file_size"),
+            Some(("7", file_size.as_str()))
+        );
+        assert!(validate(&header).is_valid());
+
+        // Image offsets past the end of the file are moved after the header
+        header.file_info.image_offset = DATA.len() as u32;
+        repair(&mut header, DATA.len() as u64);
+        assert_eq!(header.file_info.image_offset, 2048);
+    }
+
+    #[test]
+    fn frame_name() {
+        let name = FrameName::parse("/scans/reel1/scan_0086400.cin").unwrap();
+        assert_eq!(name.stem, "scan_");
+        assert_eq!(name.frame, 86400);
+        assert_eq!(name.padding, 7);
+        assert_eq!(name.extension, "cin");
+        assert_eq!(name.with_frame(86401).to_string(), "scan_0086401.cin");
+
+        let name = FrameName::parse("shot.12").unwrap();
+        assert_eq!((name.stem.as_str(), name.frame), ("shot.", 12));
+        assert_eq!(name.to_string(), "shot.12");
+        assert_eq!(FrameName::parse("abydos.cin"), None);
+        assert_eq!(FrameName::parse("99999999999.cin"), None);
+    }
+
+    #[test]
+    fn sample_transform_clamped_to_code_range() {
+        // Samples of 257, 1 and 1 packed into long words
+        let image = tiny_image(10, &[0x40, 0x40, 0x10, 0x04, 0x40, 0x40, 0x10, 0x04]);
+        let gain = || {
+            Cineon
+                .decoder(&image)
+                .unwrap()
+                .transform(Some(SampleTransform::GainOffset {
+                    gain: 100.,
+                    offset: 0.,
+                }))
+        };
+        assert_eq!(
+            gain().decode().unwrap().samples,
+            Samples::U16(vec![1023, 100, 100, 1023, 100, 100])
+        );
+
+        // Transformed codes index the tables of the code range
+        let clamped = || {
+            let lut = (0..1024u32)
+                .map(|code| (code * 100).min(1023) as u16)
+                .collect();
+            Cineon
+                .decoder(&image)
+                .unwrap()
+                .transform(Some(SampleTransform::Lut(lut)))
+        };
+        assert_eq!(
+            gain().decode_to_float(None).unwrap(),
+            clamped().decode_to_float(None).unwrap()
+        );
+        let (mut display, mut expected) = ([0; 8], [0; 8]);
+        gain().decode_to_srgb8(&mut display, 8).unwrap();
+        clamped().decode_to_srgb8(&mut expected, 8).unwrap();
+        assert_eq!(display, expected);
+        let mut rgba = [0; 8];
+        gain().decode_rgba16(&mut rgba).unwrap();
+        assert_eq!(
+            rgba,
+            [0xffff, 6406, 6406, 0xffff, 0xffff, 6406, 6406, 0xffff]
+        );
     }
 }
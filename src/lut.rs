@@ -0,0 +1,135 @@
+//! 3D lookup tables, such as show LUTs.
+
+use crate::image::{DecodedImage, Samples};
+use crate::CineonError;
+
+/// 3D LUT mapping RGB triplets to RGB triplets, on a cubic lattice
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lut3d {
+    /// Entries along each axis
+    pub size: usize,
+    /// Input values mapped to the first entry of each axis
+    pub domain_min: [f32; 3],
+    /// Input values mapped to the last entry of each axis
+    pub domain_max: [f32; 3],
+    /// Output triplets, with red changing fastest and blue slowest
+    pub table: Vec<[f32; 3]>,
+}
+
+impl Lut3d {
+    /// Reads a Resolve or Adobe `.cube` 3D LUT.
+    pub fn parse_cube(text: &str) -> Result<Self, CineonError> {
+        let triplet = |fields: &[&str]| -> Result<[f32; 3], CineonError> {
+            match fields {
+                [r, g, b] => {
+                    let value = |field: &str| field.parse().map_err(|_| CineonError::ParserError);
+                    Ok([value(r)?, value(g)?, value(b)?])
+                }
+                _ => Err(CineonError::ParserError),
+            }
+        };
+        let mut size = 0usize;
+        let mut domain_min = [0.; 3];
+        let mut domain_max = [1.; 3];
+        let mut table = Vec::new();
+        for line in text.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            match fields[0] {
+                "TITLE" => {}
+                "LUT_3D_SIZE" => {
+                    size = match fields[1..] {
+                        [size] => size.parse().map_err(|_| CineonError::ParserError)?,
+                        _ => return Err(CineonError::ParserError),
+                    }
+                }
+                "DOMAIN_MIN" => domain_min = triplet(&fields[1..])?,
+                "DOMAIN_MAX" => domain_max = triplet(&fields[1..])?,
+                // Resolve's domain, shared by the three axes
+                "LUT_3D_INPUT_RANGE" => match fields[1..] {
+                    [min, max] => {
+                        let value =
+                            |field: &str| field.parse().map_err(|_| CineonError::ParserError);
+                        domain_min = [value(min)?; 3];
+                        domain_max = [value(max)?; 3];
+                    }
+                    _ => return Err(CineonError::ParserError),
+                },
+                // 1D tables, on their own or as shapers, are not supported
+                "LUT_1D_SIZE" | "LUT_1D_INPUT_RANGE" => return Err(CineonError::UnsupportedFormat),
+                keyword if keyword.starts_with(|c: char| c.is_ascii_alphabetic()) => {
+                    return Err(CineonError::UnsupportedFormat)
+                }
+                _ => table.push(triplet(&fields)?),
+            }
+        }
+        let valid_domain = (0..3).all(|k| domain_min[k] < domain_max[k]);
+        if size < 2 || !valid_domain || Some(table.len()) != size.checked_pow(3) {
+            return Err(CineonError::ParserError);
+        }
+        Ok(Self {
+            size,
+            domain_min,
+            domain_max,
+            table,
+        })
+    }
+
+    /// Output of `rgb`, interpolated trilinearly between the surrounding
+    /// entries, inputs outside the domain being clamped to it.
+    pub fn lookup(&self, rgb: [f32; 3]) -> [f32; 3] {
+        let last = (self.size - 1) as f32;
+        let mut base = [0; 3];
+        let mut fraction = [0.; 3];
+        for k in 0..3 {
+            let position =
+                ((rgb[k] - self.domain_min[k]) / (self.domain_max[k] - self.domain_min[k]) * last)
+                    .clamp(0., last);
+            // NaN inputs take the first entry
+            let index = (position as usize).min(self.size - 2);
+            base[k] = index;
+            fraction[k] = if position.is_nan() {
+                0.
+            } else {
+                position - index as f32
+            };
+        }
+        let entry = |r: usize, g: usize, b: usize| {
+            self.table[((base[2] + b) * self.size + base[1] + g) * self.size + base[0] + r]
+        };
+        let mut output = [0.; 3];
+        for corner in 0..8 {
+            let (r, g, b) = (corner & 1, corner >> 1 & 1, corner >> 2 & 1);
+            let weight = [r, g, b]
+                .iter()
+                .zip(fraction.iter())
+                .map(|(side, fraction)| if *side == 1 { *fraction } else { 1. - fraction })
+                .product::<f32>();
+            for (value, entry) in output.iter_mut().zip(entry(r, g, b).iter()) {
+                *value += weight * entry;
+            }
+        }
+        output
+    }
+
+    /// Applies the LUT to every pixel of an RGB or RGBA image of reals,
+    /// such as one decoded to float, mattes being kept as they are.
+    pub fn apply(&self, image: &DecodedImage) -> Result<DecodedImage, CineonError> {
+        let channels = image.channels as usize;
+        let samples = match &image.samples {
+            Samples::F32(samples) if channels == 3 || channels == 4 => samples,
+            _ => return Err(CineonError::UnsupportedFormat),
+        };
+        let mut output = samples.clone();
+        for pixel in output.chunks_exact_mut(channels) {
+            let rgb = self.lookup([pixel[0], pixel[1], pixel[2]]);
+            pixel[..3].copy_from_slice(&rgb);
+        }
+        Ok(DecodedImage {
+            samples: Samples::F32(output),
+            ..*image
+        })
+    }
+}
@@ -0,0 +1,52 @@
+//! Descriptions of the conversions of this crate for OpenColorIO configs,
+//! so that Nuke or Resolve pipelines convert scans the same way.
+
+use std::fmt::Write;
+
+use crate::color::{LogConversion, Matrix};
+
+/// Sony Pictures Imageworks `.spi1d` LUT converting printing density code
+/// values, normalized from 0.0 to 1.0, to linear light with `log`, over
+/// `entries` evenly spaced code values.
+pub fn spi1d(log: &LogConversion, entries: usize) -> String {
+    let entries = entries.max(2);
+    let mut lut = format!(
+        "Version 1\nFrom 0.0 1.0\nLength {}\nComponents 1\n{{\n",
+        entries
+    );
+    for entry in 0..entries {
+        let code = entry as f32 * 1023. / (entries - 1) as f32;
+        let _ = writeln!(lut, "    {:.8}", log.linear(code));
+    }
+    lut.push_str("}\n");
+    lut
+}
+
+/// OCIO colorspace named `name`, converting scans to the reference space
+/// with the `.spi1d` LUT at `lut_file`, such as the one written by
+/// [`spi1d`], followed by `to_reference`, such as the RGB to XYZ matrix of
+/// a [`Colorimetry`](crate::Colorimetry).
+pub fn colorspace(name: &str, lut_file: &str, to_reference: &Matrix) -> String {
+    let matrix: Vec<String> = to_reference
+        .iter()
+        .flat_map(|row| row.iter().copied().chain(Some(0.)))
+        .chain([0., 0., 0., 1.])
+        .map(|value| format!("{}", value))
+        .collect();
+    format!(
+        concat!(
+            "  - !<ColorSpace>\n",
+            "    name: {}\n",
+            "    family: Cineon\n",
+            "    bitdepth: 10ui\n",
+            "    isdata: false\n",
+            "    to_reference: !<GroupTransform>\n",
+            "      children:\n",
+            "        - !<FileTransform> {{src: {}, interpolation: linear}}\n",
+            "        - !<MatrixTransform> {{matrix: [{}]}}\n",
+        ),
+        name,
+        lut_file,
+        matrix.join(", ")
+    )
+}
@@ -0,0 +1,233 @@
+//! Locations of the header fields in cineon files.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use crate::diff::flatten;
+use crate::export::header_value;
+use crate::header::{Header, MAX_ELEMENTS};
+
+/// Number of bytes in a row of the hex dump
+const ROW_SIZE: usize = 16;
+
+/// Location of a header field in the file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldLocation {
+    /// Path of the field, e.g. `image_info.channel[0].bit_depth`, or of
+    /// the reserved bytes of a section, e.g. `file_info.reserved`
+    pub field: String,
+    /// Offset from the start of the file, in bytes
+    pub offset: usize,
+    /// Size, in bytes
+    pub size: usize,
+}
+
+/// Builder of the locations of consecutive fields
+struct Locations {
+    offset: usize,
+    fields: Vec<FieldLocation>,
+}
+
+impl Locations {
+    /// Adds the fields of `section` with their sizes, following the last
+    /// one.
+    fn section(&mut self, section: &str, fields: &[(&str, usize)]) {
+        for (name, size) in fields {
+            self.fields.push(FieldLocation {
+                field: format!("{}.{}", section, name),
+                offset: self.offset,
+                size: *size,
+            });
+            self.offset += size;
+        }
+    }
+}
+
+impl Header {
+    /// Location of every field of the header in its file, reserved bytes
+    /// included, in file order.
+    ///
+    /// The channels of all the eight elements are listed, as they are
+    /// always stored. Film information and user area are listed when the
+    /// header tells the file holds them.
+    pub fn field_locations(&self) -> Vec<FieldLocation> {
+        let mut locations = Locations {
+            offset: 0,
+            fields: Vec::new(),
+        };
+        locations.section(
+            "file_info",
+            &[
+                ("magic_number", 4),
+                ("image_offset", 4),
+                ("generic_size", 4),
+                ("industry_size", 4),
+                ("user_size", 4),
+                ("file_size", 4),
+                ("version", 8),
+                ("filename", 100),
+                ("creation_date", 12),
+                ("creation_time", 12),
+                ("reserved", 36),
+            ],
+        );
+        locations.section(
+            "image_info",
+            &[
+                ("image_orientation", 1),
+                ("number_of_elements", 1),
+                ("reserved", 2),
+            ],
+        );
+        for element in 0..MAX_ELEMENTS {
+            locations.section(
+                &format!("image_info.channel[{}]", element),
+                &[
+                    ("metric", 1),
+                    ("designator", 1),
+                    ("bit_depth", 1),
+                    ("reserved", 1),
+                    ("pixels_per_line", 4),
+                    ("lines_per_element", 4),
+                    ("min_data", 4),
+                    ("min_quantity", 4),
+                    ("max_data", 4),
+                    ("max_quantity", 4),
+                ],
+            );
+        }
+        locations.section(
+            "image_info",
+            &[
+                ("white_point", 8),
+                ("red_primary", 8),
+                ("green_primary", 8),
+                ("blue_primary", 8),
+                ("label_text", 200),
+                ("reserved", 28),
+            ],
+        );
+        locations.section(
+            "data_format_info",
+            &[
+                ("interleave", 1),
+                ("packing", 1),
+                ("data_sign", 1),
+                ("image_sense", 1),
+                ("line_padding", 4),
+                ("channel_padding", 4),
+                ("reserved", 20),
+            ],
+        );
+        locations.section(
+            "origination_info",
+            &[
+                ("x_offset", 4),
+                ("y_offset", 4),
+                ("source_image_file_name", 100),
+                ("source_date", 12),
+                ("source_time", 12),
+                ("input_device", 64),
+                ("input_device_model_number", 32),
+                ("input_device_serial_number", 32),
+                ("x_device_pitch", 4),
+                ("y_device_pitch", 4),
+                ("gamma", 4),
+                ("reserved", 40),
+            ],
+        );
+        if self.file_info.industry_size != 0 {
+            locations.section(
+                "film_info",
+                &[
+                    ("film_manufacturing_id_code", 1),
+                    ("film_type", 1),
+                    ("perfs_offset", 1),
+                    ("reserved", 1),
+                    ("prefix", 4),
+                    ("count", 4),
+                    ("format", 32),
+                    ("frame_position", 4),
+                    ("frame_rate", 4),
+                    ("frame_id", 32),
+                    ("slate_info", 200),
+                    ("reserved", 740),
+                ],
+            );
+        }
+        let user_size = self.file_info.user_size as usize;
+        if user_size != 0 {
+            locations.fields.push(FieldLocation {
+                field: "user_info".to_string(),
+                offset: locations.offset,
+                size: user_size,
+            });
+        }
+        locations.fields
+    }
+
+    /// Hex dump of the header bytes of `input`, the file this header was
+    /// read from, with each field preceded by its name and decoded value.
+    ///
+    /// Fields are laid out as in [`field_locations`](Self::field_locations),
+    /// the dump stopping at the end of `input`. Rows repeating the previous
+    /// one within a field are collapsed into a `*` line.
+    pub fn hexdump(&self, input: &[u8]) -> String {
+        let mut fields = Vec::new();
+        flatten(String::new(), &header_value(self), &mut fields);
+        let values: HashMap<&str, &str> = fields
+            .iter()
+            .map(|(field, value)| (field.as_str(), value.as_str()))
+            .collect();
+        // Pairs of reals are split into their components
+        let value = |field: &str| match values.get(field) {
+            Some(value) => Some(value.to_string()),
+            None => {
+                let x = values.get(format!("{}[0]", field).as_str())?;
+                let y = values.get(format!("{}[1]", field).as_str())?;
+                Some(format!("({}, {})", x, y))
+            }
+        };
+
+        let mut dump = String::new();
+        for location in self.field_locations() {
+            if location.offset >= input.len() {
+                break;
+            }
+            let _ = write!(dump, "{:08x}  {}", location.offset, location.field);
+            if let Some(value) = value(&location.field) {
+                let _ = write!(dump, " = {}", value);
+            }
+            dump.push('\n');
+
+            let end = input.len().min(location.offset + location.size);
+            let mut previous: Option<&[u8]> = None;
+            let mut collapsed = false;
+            for (index, row) in input[location.offset..end].chunks(ROW_SIZE).enumerate() {
+                if previous == Some(row) {
+                    if !collapsed {
+                        dump.push_str("  *\n");
+                        collapsed = true;
+                    }
+                    continue;
+                }
+                previous = Some(row);
+                collapsed = false;
+                let _ = write!(dump, "  {:08x} ", location.offset + index * ROW_SIZE);
+                for byte in row {
+                    let _ = write!(dump, " {:02x}", byte);
+                }
+                let text: String = row
+                    .iter()
+                    .map(|&byte| match byte {
+                        0x20..=0x7e => byte as char,
+                        _ => '.',
+                    })
+                    .collect();
+                let padding = 3 * (ROW_SIZE - row.len());
+                let _ = writeln!(dump, "{:padding$}  |{}|", "", text, padding = padding);
+            }
+        }
+        dump
+    }
+}
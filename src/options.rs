@@ -0,0 +1,120 @@
+use crate::header::{
+    Header, ImageInfo, Interleave, Orientation, Packing, GENERIC_SIZE, INDUSTRY_SIZE, MAX_ELEMENTS,
+};
+use crate::CineonError;
+
+/// Bounds on the resources a cineon image may claim, checked while parsing
+/// its header, before anything is allocated. Bounds left to `None` are not
+/// checked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub struct Limits {
+    /// Largest number of pixels per line of an element
+    pub max_width: Option<u32>,
+    /// Largest number of lines of an element
+    pub max_height: Option<u32>,
+    /// Largest size in bytes of the decoded samples of all elements
+    pub max_decoded_bytes: Option<u64>,
+    /// Largest size in bytes of the user area
+    pub max_user_size: Option<u32>,
+}
+
+impl Limits {
+    /// Checks the size of the user area.
+    pub(crate) fn check_user_size(&self, user_size: u32) -> Result<(), CineonError> {
+        if self.max_user_size.is_some_and(|max| user_size > max) {
+            return Err(CineonError::LimitExceeded);
+        }
+        Ok(())
+    }
+
+    /// Checks the dimensions of every element and the size of their
+    /// decoded samples.
+    pub(crate) fn check_image(&self, image_info: &ImageInfo) -> Result<(), CineonError> {
+        let mut decoded_bytes = 0u128;
+        for channel in image_info.elements() {
+            if self
+                .max_width
+                .is_some_and(|max| channel.pixels_per_line > max)
+                || self
+                    .max_height
+                    .is_some_and(|max| channel.lines_per_element > max)
+            {
+                return Err(CineonError::LimitExceeded);
+            }
+            let sample_bytes = match channel.bit_depth {
+                0..=8 => 1,
+                9..=16 => 2,
+                17..=32 => 4,
+                _ => 8,
+            };
+            // Cannot overflow, as each element takes less than 2^67 bytes
+            decoded_bytes +=
+                channel.pixels_per_line as u128 * channel.lines_per_element as u128 * sample_bytes;
+        }
+        if self
+            .max_decoded_bytes
+            .is_some_and(|max| decoded_bytes > max as u128)
+        {
+            return Err(CineonError::LimitExceeded);
+        }
+        Ok(())
+    }
+}
+
+/// Header value outside the specification, which real-world files may
+/// still carry
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Warning {
+    /// The number of elements is not from 1 to 8
+    ElementCount(u8),
+    /// The orientation code is not defined
+    UndefinedOrientation,
+    /// The interleave code is not defined
+    UndefinedInterleave,
+    /// The packing code is not defined
+    UndefinedPacking,
+    /// The image offset points inside the header
+    ImageOffset(u32),
+}
+
+/// Values of `header` outside the specification, in header order.
+pub(crate) fn warnings(header: &Header) -> Vec<Warning> {
+    let mut warnings = Vec::new();
+    let number_of_elements = header.image_info.number_of_elements;
+    if !(1..=MAX_ELEMENTS as u8).contains(&number_of_elements) {
+        warnings.push(Warning::ElementCount(number_of_elements));
+    }
+    if header.image_info.image_orientation == Orientation::Undefined {
+        warnings.push(Warning::UndefinedOrientation);
+    }
+    if header.data_format_info.interleave == Interleave::Undefined {
+        warnings.push(Warning::UndefinedInterleave);
+    }
+    if header.data_format_info.packing == Packing::Undefined {
+        warnings.push(Warning::UndefinedPacking);
+    }
+    let image_offset = header.file_info.image_offset;
+    let industry_size = if header.file_info.industry_size != 0 {
+        INDUSTRY_SIZE
+    } else {
+        0
+    };
+    let header_size = (GENERIC_SIZE + industry_size).saturating_add(header.file_info.user_size);
+    if image_offset != u32::MAX && image_offset < header_size {
+        warnings.push(Warning::ImageOffset(image_offset));
+    }
+    warnings
+}
+
+/// Options controlling how cineon images are parsed
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ParseOptions {
+    /// Whether values outside the specification are rejected, rather than
+    /// reported as warnings
+    pub strict: bool,
+    /// Bounds on the resources an image may claim
+    pub limits: Limits,
+}
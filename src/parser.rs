@@ -1,7 +1,5 @@
 use nom::bytes::complete::{tag, take};
-use nom::number::complete::{
-    be_f32, be_i32, be_u16, be_u32, be_u8, le_f32, le_i32, le_u16, le_u32, le_u8,
-};
+use nom::number::complete::{be_f32, be_i32, be_u32, be_u8, le_f32, le_i32, le_u32, le_u8};
 use nom::sequence::pair;
 use nom::{Finish, IResult, ToUsize};
 
@@ -13,9 +11,11 @@ impl From<nom::Err<nom::error::Error<&[u8]>>> for CineonError {
     }
 }
 
+/// Remaining input paired with a parsed value
+pub(crate) type ParseResult<'a, T> = Result<(&'a [u8], T), CineonError>;
+
 pub(crate) trait ReadBytes {
     fn read_u8<'a>(&self, input: &'a [u8]) -> IResult<&'a [u8], u8>;
-    fn read_u16<'a>(&self, input: &'a [u8]) -> IResult<&'a [u8], u16>;
     fn read_u32<'a>(&self, input: &'a [u8]) -> IResult<&'a [u8], u32>;
     fn read_i32<'a>(&self, input: &'a [u8]) -> IResult<&'a [u8], i32>;
     fn read_f32<'a>(&self, input: &'a [u8]) -> IResult<&'a [u8], f32>;
@@ -27,15 +27,25 @@ pub(crate) trait BoxClone: ReadBytes {
     fn box_clone(&self) -> Box<dyn ReadBytes>;
 }
 
-pub(crate) fn check_magick(input: &[u8], magick: u32) -> Result<(&[u8], &[u8]), CineonError> {
+pub(crate) fn check_magick(input: &[u8], magick: u32) -> ParseResult<'_, &[u8]> {
     tag(magick.to_be_bytes())(input)
         .finish()
         .map_err(|_: nom::error::Error<&[u8]>| CineonError::NotCineonImage)
 }
 
-pub(crate) fn read_bytes<C: ToUsize>(
-    count: C,
-) -> impl Fn(&[u8]) -> Result<(&[u8], &[u8]), CineonError> {
+/// Real read from a header field, `None` when it holds the undefined value
+/// of the specification, 0x7F800000, or any other value which is not
+/// finite.
+pub(crate) fn defined(value: f32) -> Option<f32> {
+    value.is_finite().then_some(value)
+}
+
+/// Pair of reals read from a header field, each `None` when undefined.
+pub(crate) fn defined_pair((x, y): (f32, f32)) -> [Option<f32>; 2] {
+    [defined(x), defined(y)]
+}
+
+pub(crate) fn read_bytes<C: ToUsize>(count: C) -> impl Fn(&[u8]) -> ParseResult<'_, &[u8]> {
     let c = count.to_usize();
     move |input: &[u8]| {
         take(c)(input)
@@ -44,19 +54,29 @@ pub(crate) fn read_bytes<C: ToUsize>(
     }
 }
 
-pub(crate) fn read_string<C: ToUsize>(
-    count: C,
-) -> impl Fn(&[u8]) -> Result<(&[u8], String), CineonError> {
+/// Text of a string field, up to its first NUL after any leading ones.
+///
+/// Fields which are not UTF-8, as written by old tools, are decoded as
+/// Latin-1, so that no byte makes the header unreadable.
+pub(crate) fn decode_string(bytes: &[u8]) -> String {
+    let start = bytes.iter().position(|&b| b != 0).unwrap_or(bytes.len());
+    let bytes = &bytes[start..];
+    let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    let bytes = &bytes[..end];
+    match std::str::from_utf8(bytes) {
+        Ok(text) => text.to_owned(),
+        Err(_) => bytes.iter().map(|&b| char::from(b)).collect(),
+    }
+}
+
+pub(crate) fn read_string<C: ToUsize>(count: C) -> impl Fn(&[u8]) -> ParseResult<'_, String> {
     let c = count.to_usize();
     move |input: &[u8]| {
         match take(c)(input)
             .finish() // Using complete and not streaming functions, so acceptable
             .map_err(|_: nom::error::Error<&[u8]>| CineonError::ParserError)
         {
-            Ok((i, v)) => match std::str::from_utf8(v) {
-                Ok(str_value) => Ok((i, str_value.trim_matches(char::from(0)).to_owned())),
-                Err(_) => Err(CineonError::StringConversion),
-            },
+            Ok((i, v)) => Ok((i, decode_string(v))),
             Err(e) => Err(e),
         }
     }
@@ -69,9 +89,6 @@ impl ReadBytes for LittleEndian {
     fn read_u8<'a>(&self, input: &'a [u8]) -> IResult<&'a [u8], u8> {
         le_u8(input)
     }
-    fn read_u16<'a>(&self, input: &'a [u8]) -> IResult<&'a [u8], u16> {
-        le_u16(input)
-    }
     fn read_u32<'a>(&self, input: &'a [u8]) -> IResult<&'a [u8], u32> {
         le_u32(input)
     }
@@ -102,9 +119,6 @@ impl ReadBytes for BigEndian {
     fn read_u8<'a>(&self, input: &'a [u8]) -> IResult<&'a [u8], u8> {
         be_u8(input)
     }
-    fn read_u16<'a>(&self, input: &'a [u8]) -> IResult<&'a [u8], u16> {
-        be_u16(input)
-    }
     fn read_u32<'a>(&self, input: &'a [u8]) -> IResult<&'a [u8], u32> {
         be_u32(input)
     }
@@ -0,0 +1,99 @@
+//! In-place byte patches, to fix known bad fields across many files without
+//! rewriting them.
+
+use crate::header::{Header, GENERIC_SIZE, INDUSTRY_SIZE};
+use crate::writer::HeaderWriter;
+use crate::{Cineon, CineonError};
+
+/// Bytes to write at an offset of a file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BytePatch {
+    /// Offset of the first byte from the start of the file
+    pub offset: usize,
+    /// Replacement bytes
+    pub bytes: Vec<u8>,
+}
+
+/// Region of a file patches may touch
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scope {
+    /// Only the header sections, so that image data cannot be damaged
+    Header,
+    /// Anywhere within the file
+    Anywhere,
+}
+
+/// Applies `patches` in order to `file`, after checking that every one of
+/// them lies within `scope`, so that either all or none are applied.
+pub fn apply(file: &mut [u8], patches: &[BytePatch], scope: Scope) -> Result<(), CineonError> {
+    let limit = match scope {
+        Scope::Header => {
+            let file_info = Cineon.parse_header(file)?.file_info;
+            let industry_size = if file_info.industry_size != 0 {
+                INDUSTRY_SIZE
+            } else {
+                0
+            };
+            let header_size =
+                GENERIC_SIZE as usize + industry_size as usize + file_info.user_size as usize;
+            header_size.min(file.len())
+        }
+        Scope::Anywhere => file.len(),
+    };
+    if patches
+        .iter()
+        .any(|patch| patch.offset.saturating_add(patch.bytes.len()) > limit)
+    {
+        return Err(CineonError::OutOfBounds);
+    }
+    for patch in patches {
+        file[patch.offset..patch.offset + patch.bytes.len()].copy_from_slice(&patch.bytes);
+    }
+    Ok(())
+}
+
+/// Patches turning the header of `file` into `edited`, one per changed
+/// field, so that fields left alone keep their bytes as they are.
+///
+/// Changed fields are rewritten whole, so that no byte of the file, such as
+/// text after the NUL ending a string, outlives the edit.
+///
+/// Only big-endian files can be patched, and the user area must keep its
+/// size. Files without industry section can only have their generic
+/// section patched.
+pub fn from_edit(file: &[u8], edited: &Header) -> Result<Vec<BytePatch>, CineonError> {
+    let original = Cineon.parse_header(file)?;
+    if file.first() != Some(&0x80) {
+        return Err(CineonError::UnsupportedFormat);
+    }
+    let serialize = |header: &Header| {
+        let mut writer = HeaderWriter::new();
+        writer.write_header(header);
+        writer.into_bytes()
+    };
+    let (before, after) = (serialize(&original), serialize(edited));
+    if before.len() != after.len() {
+        return Err(CineonError::UnsupportedFormat);
+    }
+
+    // Serialized headers always hold an industry section
+    let mut layout = original.clone();
+    layout.file_info.industry_size = INDUSTRY_SIZE;
+    layout.file_info.user_size = (before.len() - (GENERIC_SIZE + INDUSTRY_SIZE) as usize) as u32;
+
+    let mut patches = Vec::new();
+    for location in layout.field_locations() {
+        let range = location.offset..location.offset + location.size;
+        if before[range.clone()] == after[range.clone()] {
+            continue;
+        }
+        if original.file_info.industry_size == 0 && location.offset >= GENERIC_SIZE as usize {
+            return Err(CineonError::UnsupportedFormat);
+        }
+        patches.push(BytePatch {
+            offset: location.offset,
+            bytes: after[range].to_vec(),
+        });
+    }
+    Ok(patches)
+}
@@ -0,0 +1,72 @@
+//! Frames of image sequences, numbered in their file names.
+
+use std::fmt;
+use std::path::Path;
+
+/// File name of a frame of an image sequence, such as `scan_0086400.cin`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FrameName {
+    /// Text before the frame number, separator included, e.g. `scan_`
+    pub stem: String,
+    /// Frame number
+    pub frame: u32,
+    /// Number of digits of the frame number, leading zeros included
+    pub padding: usize,
+    /// Extension, without the dot, empty when the name has none
+    pub extension: String,
+}
+
+impl FrameName {
+    /// Splits the file name of `path` into stem, frame number and
+    /// extension, the frame number being the digits ending the name before
+    /// its extension, or ending the name when the extension is numeric.
+    ///
+    /// Returns `None` when the name ends without digits or the frame
+    /// number does not fit 32 bits.
+    pub fn parse(path: impl AsRef<Path>) -> Option<Self> {
+        let name = path.as_ref().file_name()?.to_str()?;
+        let is_number = |text: &str| text.bytes().all(|byte| byte.is_ascii_digit());
+        // A numeric extension, as in `shot.0012`, is the frame number
+        let (base, extension) = match name.rfind('.') {
+            Some(dot) if dot > 0 && !is_number(&name[dot + 1..]) => {
+                (&name[..dot], &name[dot + 1..])
+            }
+            _ => (name, ""),
+        };
+        let digits = base.len() - base.trim_end_matches(|c: char| c.is_ascii_digit()).len();
+        if digits == 0 {
+            return None;
+        }
+        let (stem, number) = base.split_at(base.len() - digits);
+        Some(Self {
+            stem: stem.to_owned(),
+            frame: number.parse().ok()?,
+            padding: digits,
+            extension: extension.to_owned(),
+        })
+    }
+
+    /// Name of the frame `frame` of the same sequence, padded alike.
+    pub fn with_frame(&self, frame: u32) -> Self {
+        Self {
+            frame,
+            ..self.clone()
+        }
+    }
+}
+
+impl fmt::Display for FrameName {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}{:0padding$}",
+            self.stem,
+            self.frame,
+            padding = self.padding
+        )?;
+        if !self.extension.is_empty() {
+            write!(f, ".{}", self.extension)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,93 @@
+//! Synthetic test images, so that decode paths can be exercised without
+//! shipping binary assets.
+
+use crate::color::ColorSpace;
+use crate::header::{Header, Packing};
+use crate::image::{DecodedImage, Samples};
+use crate::{Cineon, CineonError};
+
+/// Content of a synthetic image
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pattern {
+    /// Eight vertical bars of white, yellow, cyan, green, magenta, red,
+    /// blue and black at the full code range
+    ColorBars,
+    /// Gray rising linearly from the lowest code value on the left to the
+    /// highest on the right
+    GrayRamp,
+    /// Gray vertical stripes whose width doubles at every eighth of the
+    /// image height, from one pixel at the top
+    ResolutionTarget,
+}
+
+/// Bars of [`Pattern::ColorBars`], as red, green and blue levels
+const BARS: [[bool; 3]; 8] = [
+    [true, true, true],
+    [true, true, false],
+    [false, true, true],
+    [false, true, false],
+    [true, false, true],
+    [true, false, false],
+    [false, false, true],
+    [false, false, false],
+];
+
+/// RGB image of `width`x`height` pixels showing `pattern` with code values
+/// of `bit_depth` bits, from 1 to 16.
+pub fn image(
+    pattern: Pattern,
+    width: u32,
+    height: u32,
+    bit_depth: u8,
+) -> Result<DecodedImage, CineonError> {
+    if !(1..=16).contains(&bit_depth) {
+        return Err(CineonError::UnsupportedFormat);
+    }
+    let max = (1u32 << bit_depth) - 1;
+    let mut codes = Vec::with_capacity(3 * width as usize * height as usize);
+    for y in 0..height {
+        for x in 0..width {
+            let rgb = match pattern {
+                Pattern::ColorBars => {
+                    let bar = BARS[(x as u64 * 8 / width as u64) as usize];
+                    bar.map(|on| if on { max } else { 0 })
+                }
+                Pattern::GrayRamp => {
+                    let level = (x as u64 * max as u64 / (width as u64 - 1).max(1)) as u32;
+                    [level; 3]
+                }
+                Pattern::ResolutionTarget => {
+                    let stripe = 1 << (y as u64 * 8 / height as u64);
+                    [if (x / stripe) & 1 == 0 { max } else { 0 }; 3]
+                }
+            };
+            codes.extend(rgb);
+        }
+    }
+    let samples = if bit_depth <= 8 {
+        Samples::U8(codes.into_iter().map(|code| code as u8).collect())
+    } else {
+        Samples::U16(codes.into_iter().map(|code| code as u16).collect())
+    };
+    Ok(DecodedImage {
+        width,
+        height,
+        channels: 3,
+        bit_depth,
+        samples,
+        color_space: ColorSpace::CineonLog,
+    })
+}
+
+/// Cineon file of an [`image`] of `pattern`, with its samples packed
+/// following `packing`.
+pub fn file(
+    pattern: Pattern,
+    width: u32,
+    height: u32,
+    bit_depth: u8,
+    packing: Packing,
+) -> Result<Vec<u8>, CineonError> {
+    let image = image(pattern, width, height, bit_depth)?;
+    Cineon.encode_with_packing(&Header::default(), &image, packing)
+}
@@ -0,0 +1,406 @@
+use std::convert::TryFrom;
+use std::ops::Range;
+
+use crate::bits::{BitReader, ByteOrder};
+use crate::header::{Header, Interleave, Packing, MAX_ELEMENTS};
+use crate::CineonError;
+
+/// How the samples of an image element are laid out in a row.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct SampleLayout {
+    /// Bits per sample
+    bit_depth: u32,
+    /// Size of the words samples are packed into
+    word_bits: u32,
+    /// Samples per word, zero when samples are tightly packed
+    per_word: u32,
+    /// Whether samples sit in the high bits of a word
+    left_justified: bool,
+}
+
+impl SampleLayout {
+    pub(crate) fn new(bit_depth: u8, packing: &Packing) -> Result<Self, CineonError> {
+        let bit_depth = bit_depth as u32;
+        // Integer samples up to 16 bits, or 32 and 64-bit reals
+        if !matches!(bit_depth, 1..=16 | 32 | 64) {
+            return Err(CineonError::UnsupportedFormat);
+        }
+        let (cell_bits, left_justified) = match packing {
+            Packing::Packed => {
+                return Ok(Self {
+                    bit_depth,
+                    word_bits: 32,
+                    per_word: 0,
+                    left_justified: true,
+                })
+            }
+            Packing::ByteLeft => (8, true),
+            Packing::ByteRight => (8, false),
+            Packing::WordLeft => (16, true),
+            Packing::WordRight => (16, false),
+            Packing::LongWordRight => (32, false),
+            Packing::LongWordLeft | Packing::PackAsManyAsPossible | Packing::Undefined => {
+                (32, true)
+            }
+        };
+        // A sample wider than a cell spans as many cells as needed
+        let word_bits = cell_bits * bit_depth.div_ceil(cell_bits);
+        Ok(Self {
+            bit_depth,
+            word_bits,
+            per_word: word_bits / bit_depth,
+            left_justified,
+        })
+    }
+
+    /// Bytes taken by a row of `samples` samples, rows being aligned
+    /// to 32-bit boundaries.
+    pub(crate) fn row_bytes(&self, samples: u64) -> u64 {
+        // Cannot overflow, as there are less than 2^36 samples in a row
+        let bits = if self.per_word == 0 {
+            samples * self.bit_depth as u64
+        } else {
+            samples.div_ceil(self.per_word as u64) * self.word_bits as u64
+        };
+        4 * bits.div_ceil(32)
+    }
+
+    /// Unpacks `samples` samples from `row`, starting from the sample at
+    /// index `start`, handing their bits to `sink` in order.
+    pub(crate) fn unpack<F: FnMut(u64)>(
+        &self,
+        row: &[u8],
+        start: u32,
+        samples: u32,
+        byte_order: ByteOrder,
+        mut sink: F,
+    ) -> Result<(), CineonError> {
+        if self.per_word == 0 {
+            // Tightly packed samples are read from 32-bit words
+            let first_bit = start as usize * self.bit_depth as usize;
+            let row = row
+                .get(4 * (first_bit / 32)..)
+                .ok_or(CineonError::ParserError)?;
+            let mut reader = BitReader::new(row, byte_order);
+            reader.skip_bits((first_bit % 32) as u32)?;
+            for _ in 0..samples {
+                sink(reader.read_bits(self.bit_depth)?);
+            }
+            return Ok(());
+        }
+        let word_bytes = (self.word_bits / 8) as usize;
+        let row = row
+            .get((start / self.per_word) as usize * word_bytes..)
+            .ok_or(CineonError::ParserError)?;
+        let mut reader = BitReader::with_word_bits(row, byte_order, self.word_bits)?;
+        let unused = self.word_bits - self.per_word * self.bit_depth;
+        let mut in_word = start % self.per_word;
+        let mut remaining = samples;
+        while remaining > 0 {
+            if !self.left_justified {
+                reader.skip_bits(unused)?;
+            }
+            reader.skip_bits(in_word * self.bit_depth)?;
+            let count = remaining.min(self.per_word - in_word);
+            for _ in 0..count {
+                sink(reader.read_bits(self.bit_depth)?);
+            }
+            reader.align_to_word();
+            remaining -= count;
+            in_word = 0;
+        }
+        Ok(())
+    }
+}
+
+/// Padding in bytes, where the undefined value (all bits set) means none.
+#[inline(always)]
+fn padding(value: Option<u32>) -> usize {
+    match value {
+        Some(u32::MAX) | None => 0,
+        Some(padding) => padding as usize,
+    }
+}
+
+/// Geometry and packing of a single image element.
+#[derive(Debug, Clone, Copy)]
+struct ElementLayout {
+    width: u32,
+    height: u32,
+    samples: SampleLayout,
+    /// Bytes taken by a line of the element, padding excluded
+    row_bytes: usize,
+}
+
+/// Locates the rows of every image element within the image data, so that
+/// they can be unpacked in any order.
+#[derive(Debug, Clone)]
+pub(crate) struct DataLayout {
+    interleave: Interleave,
+    elements: Vec<ElementLayout>,
+    line_padding: usize,
+    channel_padding: usize,
+    byte_order: ByteOrder,
+    /// Bytes spanned by the image data, up to the end of its last line
+    size: usize,
+}
+
+impl DataLayout {
+    pub(crate) fn new(header: &Header, byte_order: ByteOrder) -> Result<Self, CineonError> {
+        let count = header.image_info.number_of_elements as usize;
+        if count == 0 || count > MAX_ELEMENTS {
+            return Err(CineonError::UnsupportedFormat);
+        }
+        let channels = &header.image_info.channel[..count];
+        let packing = &header.data_format_info.packing;
+        let interleave = match header.data_format_info.interleave {
+            Interleave::Undefined => Interleave::Pixel,
+            interleave => interleave,
+        };
+
+        let first = &channels[0];
+        let same_height = channels
+            .iter()
+            .all(|channel| channel.lines_per_element == first.lines_per_element);
+        let same_geometry = same_height
+            && channels.iter().all(|channel| {
+                channel.pixels_per_line == first.pixels_per_line
+                    && channel.bit_depth == first.bit_depth
+            });
+        match interleave {
+            // Samples of all elements alternate within each row
+            Interleave::Pixel if !same_geometry => return Err(CineonError::UnsupportedFormat),
+            // Each row holds a line of every element in turn
+            Interleave::Line if !same_height => return Err(CineonError::UnsupportedFormat),
+            _ => {}
+        }
+
+        let elements = channels
+            .iter()
+            .map(|channel| {
+                let samples = SampleLayout::new(channel.bit_depth, packing)?;
+                let row_samples = if interleave == Interleave::Pixel {
+                    channel.pixels_per_line as u64 * count as u64
+                } else {
+                    channel.pixels_per_line as u64
+                };
+                Ok(ElementLayout {
+                    width: channel.pixels_per_line,
+                    height: channel.lines_per_element,
+                    samples,
+                    row_bytes: usize::try_from(samples.row_bytes(row_samples))
+                        .map_err(|_| CineonError::InvalidSize)?,
+                })
+            })
+            .collect::<Result<Vec<_>, CineonError>>()?;
+
+        let mut layout = Self {
+            interleave,
+            elements,
+            line_padding: padding(header.data_format_info.line_padding),
+            channel_padding: padding(header.data_format_info.channel_padding),
+            byte_order,
+            size: 0,
+        };
+        // Every line lies within the image data, so that offsets computed
+        // later cannot overflow
+        layout.size = (0..count)
+            .filter(|element| layout.elements[*element].height > 0)
+            .map(|element| {
+                let last = layout.elements[element].height - 1;
+                layout
+                    .offset(element, last)
+                    .and_then(|offset| offset.checked_add(layout.elements[element].row_bytes))
+            })
+            .try_fold(0, |size, end| end.map(|end| size.max(end)))
+            .ok_or(CineonError::InvalidSize)?;
+        Ok(layout)
+    }
+
+    /// Checks that `data` holds every line of the image, so that decoded
+    /// samples, up to 16 times as many as data bits, can be allocated for
+    /// the whole image.
+    pub(crate) fn check(&self, data: &[u8]) -> Result<(), CineonError> {
+        if data.len() < self.size {
+            return Err(CineonError::InvalidSize);
+        }
+        Ok(())
+    }
+
+    /// Number of rows covering every element.
+    pub(crate) fn height(&self) -> u32 {
+        self.elements
+            .iter()
+            .map(|element| element.height)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Byte offset of line `y` of `element`, unless it overflows.
+    fn offset(&self, element: usize, y: u32) -> Option<usize> {
+        let stride = |layout: &ElementLayout| layout.row_bytes.checked_add(self.line_padding);
+        let sum = |strides: &[ElementLayout]| {
+            strides
+                .iter()
+                .try_fold(0usize, |sum, layout| sum.checked_add(stride(layout)?))
+        };
+        let y = y as usize;
+        match self.interleave {
+            Interleave::Pixel => y.checked_mul(stride(&self.elements[0])?),
+            Interleave::Line => y
+                .checked_mul(sum(&self.elements)?)?
+                .checked_add(sum(&self.elements[..element])?),
+            _ => {
+                let before = self.elements[..element]
+                    .iter()
+                    .try_fold(0usize, |sum, layout| {
+                        (layout.height as usize)
+                            .checked_mul(stride(layout)?)?
+                            .checked_add(self.channel_padding)?
+                            .checked_add(sum)
+                    })?;
+                before.checked_add(y.checked_mul(stride(&self.elements[element])?)?)
+            }
+        }
+    }
+
+    /// Bytes of line `y` of `element`.
+    fn line<'d>(&self, data: &'d [u8], element: usize, y: u32) -> Result<&'d [u8], CineonError> {
+        let offset = self.offset(element, y).ok_or(CineonError::InvalidSize)?;
+        data.get(offset..offset + self.elements[element].row_bytes)
+            .ok_or(CineonError::ParserError)
+    }
+
+    /// Unpacks row `y` of `element` alone, handing each sample to `sink`
+    /// together with its column.
+    pub(crate) fn unpack_element_row<F: FnMut(usize, u64)>(
+        &self,
+        data: &[u8],
+        element: usize,
+        y: u32,
+        mut sink: F,
+    ) -> Result<(), CineonError> {
+        if self.interleave == Interleave::Pixel {
+            return self.unpack_row(data, y, 0..u32::MAX, |e, x, sample| {
+                if e == element {
+                    sink(x, sample)
+                }
+            });
+        }
+        let layout = &self.elements[element];
+        if y >= layout.height {
+            return Ok(());
+        }
+        let mut x = 0;
+        layout.samples.unpack(
+            self.line(data, element, y)?,
+            0,
+            layout.width,
+            self.byte_order,
+            |sample| {
+                sink(x, sample);
+                x += 1;
+            },
+        )
+    }
+
+    /// Unpacks the `columns` of row `y` of every element large enough to
+    /// have them, handing each sample to `sink` together with its element
+    /// and column.
+    pub(crate) fn unpack_row<F: FnMut(usize, usize, u64)>(
+        &self,
+        data: &[u8],
+        y: u32,
+        columns: Range<u32>,
+        mut sink: F,
+    ) -> Result<(), CineonError> {
+        let line = |element: usize| self.line(data, element, y);
+        if self.interleave == Interleave::Pixel {
+            let first = &self.elements[0];
+            let count = self.elements.len();
+            let end = columns.end.min(first.width);
+            if columns.start >= end {
+                return Ok(());
+            }
+            let mut position = columns.start as usize * count;
+            return first.samples.unpack(
+                line(0)?,
+                columns.start * count as u32,
+                (end - columns.start) * count as u32,
+                self.byte_order,
+                |sample| {
+                    sink(position % count, position / count, sample);
+                    position += 1;
+                },
+            );
+        }
+        for (element, layout) in self.elements.iter().enumerate() {
+            let end = columns.end.min(layout.width);
+            if y >= layout.height || columns.start >= end {
+                continue;
+            }
+            let mut x = columns.start as usize;
+            layout.samples.unpack(
+                line(element)?,
+                columns.start,
+                end - columns.start,
+                self.byte_order,
+                |sample| {
+                    sink(element, x, sample);
+                    x += 1;
+                },
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bits::BitWriter;
+
+    #[test]
+    fn ten_bit_longword_packing() {
+        let mut writer = BitWriter::new(ByteOrder::Big);
+        for value in 0..4u64 {
+            writer.write_bits(value * 300, 10).unwrap();
+            if value == 2 {
+                writer.align_to_word();
+            }
+        }
+        let row = writer.into_bytes();
+
+        let layout = SampleLayout::new(10, &Packing::LongWordLeft).unwrap();
+        assert_eq!(layout.row_bytes(4), row.len() as u64);
+        let mut samples = Vec::new();
+        layout
+            .unpack(&row, 0, 4, ByteOrder::Big, |sample| samples.push(sample))
+            .unwrap();
+        assert_eq!(samples, vec![0, 300, 600, 900]);
+
+        samples.clear();
+        layout
+            .unpack(&row, 2, 2, ByteOrder::Big, |sample| samples.push(sample))
+            .unwrap();
+        assert_eq!(samples, vec![600, 900]);
+    }
+
+    #[test]
+    fn real_samples() {
+        let mut writer = BitWriter::new(ByteOrder::Little);
+        writer.write_bits(1.5f32.to_bits() as u64, 32).unwrap();
+        writer.write_bits((-2.25f32).to_bits() as u64, 32).unwrap();
+        let row = writer.into_bytes();
+
+        let layout = SampleLayout::new(32, &Packing::LongWordLeft).unwrap();
+        let mut samples = Vec::new();
+        layout
+            .unpack(&row, 1, 1, ByteOrder::Little, |sample| {
+                samples.push(f32::from_bits(sample as u32))
+            })
+            .unwrap();
+        assert_eq!(samples, vec![-2.25]);
+        assert!(SampleLayout::new(24, &Packing::LongWordLeft).is_err());
+    }
+}
@@ -0,0 +1,144 @@
+//! Parsers of the vendor specific payloads of the user area, chosen by
+//! sniffing its content.
+
+use std::any::Any;
+use std::fmt;
+
+use crate::header::{postage_stamp, POSTAGE_STAMP_SIZE};
+use crate::image::DecodedImage;
+use crate::CineonError;
+
+/// Parser of a kind of user area payload
+pub trait UserDataParser: Send + Sync {
+    /// Name of the payload, telling which parser produced a [`UserData`].
+    fn name(&self) -> &'static str;
+
+    /// Whether `data`, the whole user area, holds a payload this parser
+    /// understands.
+    fn sniff(&self, data: &[u8]) -> bool;
+
+    /// Parses the payload in `data`, the whole user area.
+    fn parse(&self, data: &[u8]) -> Result<Box<dyn Any + Send + Sync>, CineonError>;
+}
+
+/// Payload of the user area, as parsed by a [`UserDataParser`]
+pub struct UserData {
+    name: &'static str,
+    value: Box<dyn Any + Send + Sync>,
+}
+
+impl UserData {
+    /// Name of the parser which produced the payload.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Whether the payload is a `T`.
+    pub fn is<T: Any>(&self) -> bool {
+        self.value.is::<T>()
+    }
+
+    /// Payload as a `T`, `None` when it is of another type.
+    pub fn downcast_ref<T: Any>(&self) -> Option<&T> {
+        self.value.downcast_ref()
+    }
+
+    /// Payload as a `T`, or the user data itself when it is of another
+    /// type.
+    pub fn downcast<T: Any>(self) -> Result<T, Self> {
+        let name = self.name;
+        match self.value.downcast() {
+            Ok(value) => Ok(*value),
+            Err(value) => Err(Self { name, value }),
+        }
+    }
+}
+
+impl fmt::Debug for UserData {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("UserData")
+            .field("name", &self.name)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Parsers of user area payloads, tried in registration order
+#[derive(Default)]
+pub struct UserDataRegistry {
+    parsers: Vec<Box<dyn UserDataParser>>,
+}
+
+impl UserDataRegistry {
+    /// Registry without parsers.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `parser`, tried after the ones already registered.
+    pub fn register(mut self, parser: impl UserDataParser + 'static) -> Self {
+        self.parsers.push(Box::new(parser));
+        self
+    }
+
+    /// Adds the parsers of the layouts documented by the specification,
+    /// tried after the ones already registered: the [`PostageStampParser`].
+    pub fn with_builtin(self) -> Self {
+        self.register(PostageStampParser)
+    }
+
+    /// Names of the registered parsers, in registration order.
+    pub fn names(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.parsers.iter().map(|parser| parser.name())
+    }
+
+    /// Payload of the user area `data`, parsed by the first parser
+    /// sniffing it, or `None` when no parser does.
+    pub fn parse(&self, data: &[u8]) -> Option<Result<UserData, CineonError>> {
+        let parser = self.parsers.iter().find(|parser| parser.sniff(data))?;
+        Some(parser.parse(data).map(|value| UserData {
+            name: parser.name(),
+            value,
+        }))
+    }
+}
+
+impl fmt::Debug for UserDataRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.names()).finish()
+    }
+}
+
+/// Postage stamp stored at the beginning of the user area
+#[derive(Debug, Clone, PartialEq)]
+pub struct PostageStamp {
+    /// 8-bit RGB thumbnail, oriented for display
+    pub image: DecodedImage,
+    /// Bytes of the user area following the thumbnail
+    pub remainder: Vec<u8>,
+}
+
+/// Parser of the standard user area layout, a [`PostageStamp`] of 96x64
+/// pixels
+///
+/// As any user area large enough is taken as holding one, it is best tried
+/// after the parsers of vendor payloads.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PostageStampParser;
+
+impl UserDataParser for PostageStampParser {
+    fn name(&self) -> &'static str {
+        "postage stamp"
+    }
+
+    fn sniff(&self, data: &[u8]) -> bool {
+        data.len() >= POSTAGE_STAMP_SIZE
+    }
+
+    fn parse(&self, data: &[u8]) -> Result<Box<dyn Any + Send + Sync>, CineonError> {
+        let image = postage_stamp(data).ok_or(CineonError::ParserError)?;
+        Ok(Box::new(PostageStamp {
+            image,
+            remainder: data[POSTAGE_STAMP_SIZE..].to_vec(),
+        }))
+    }
+}
@@ -0,0 +1,264 @@
+//! Checks of headers against the Kodak 4.5 specification.
+
+use std::convert::TryFrom;
+
+use crate::diff::FieldDifference;
+use crate::header::{
+    Header, Interleave, Orientation, Packing, GENERIC_SIZE, INDUSTRY_SIZE, MAGIC_COOKIE,
+    MAX_ELEMENTS,
+};
+
+/// Severity of a finding of the validation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// Value outside the specification, which readers commonly accept
+    Warning,
+    /// Value making the image unreadable, or read wrongly
+    Error,
+}
+
+/// Value of a header field breaking the specification
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+    /// Severity
+    pub severity: Severity,
+    /// Path of the field, e.g. `image_info.channel[0].bit_depth`
+    pub field: String,
+    /// Description of the issue
+    pub message: String,
+}
+
+/// Findings of the validation of a header, in header order
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ValidationReport {
+    /// Findings
+    pub findings: Vec<Finding>,
+}
+
+impl ValidationReport {
+    /// Whether no finding is an error.
+    pub fn is_valid(&self) -> bool {
+        self.errors().next().is_none()
+    }
+
+    /// Findings which are errors.
+    pub fn errors(&self) -> impl Iterator<Item = &Finding> {
+        self.findings
+            .iter()
+            .filter(|finding| finding.severity == Severity::Error)
+    }
+
+    /// Findings which are warnings.
+    pub fn warnings(&self) -> impl Iterator<Item = &Finding> {
+        self.findings
+            .iter()
+            .filter(|finding| finding.severity == Severity::Warning)
+    }
+
+    /// Adds a finding about `field`.
+    fn push(&mut self, severity: Severity, field: impl Into<String>, message: String) {
+        self.findings.push(Finding {
+            severity,
+            field: field.into(),
+            message,
+        });
+    }
+}
+
+/// Checks every field of `header` against the Kodak 4.5 specification:
+/// section sizes and offsets, element count, dimensions, bit depths and
+/// codes of the enumerations.
+pub fn validate(header: &Header) -> ValidationReport {
+    use Severity::*;
+
+    let mut report = ValidationReport::default();
+    let file_info = &header.file_info;
+    if file_info.magic_number != MAGIC_COOKIE {
+        report.push(
+            Error,
+            "file_info.magic_number",
+            format!("{:#010x} is not the magic number", file_info.magic_number),
+        );
+    }
+    if file_info.image_offset == u32::MAX {
+        report.push(
+            Warning,
+            "file_info.image_offset",
+            "the offset is undefined".to_string(),
+        );
+    } else {
+        // The industry section may be missing
+        let industry_size = if file_info.industry_size != 0 {
+            INDUSTRY_SIZE
+        } else {
+            0
+        };
+        let header_size = GENERIC_SIZE as u64 + industry_size as u64 + file_info.user_size as u64;
+        if (file_info.image_offset as u64) < header_size {
+            report.push(
+                Error,
+                "file_info.image_offset",
+                format!(
+                    "{} points inside the {} bytes of header",
+                    file_info.image_offset, header_size
+                ),
+            );
+        }
+        if file_info.file_size != 0 && file_info.file_size <= file_info.image_offset {
+            report.push(
+                Error,
+                "file_info.file_size",
+                format!(
+                    "{} leaves no room for the image data at {}",
+                    file_info.file_size, file_info.image_offset
+                ),
+            );
+        }
+    }
+    for (field, size, expected) in [
+        (
+            "file_info.generic_size",
+            file_info.generic_size,
+            GENERIC_SIZE,
+        ),
+        (
+            "file_info.industry_size",
+            file_info.industry_size,
+            INDUSTRY_SIZE,
+        ),
+    ] {
+        if size != expected && !(field == "file_info.industry_size" && size == 0) {
+            report.push(
+                Warning,
+                field,
+                format!(
+                    "{} differs from the {} bytes of the section",
+                    size, expected
+                ),
+            );
+        }
+    }
+    if file_info.version.trim() != "V4.5" {
+        report.push(
+            Warning,
+            "file_info.version",
+            format!("{:?} is not V4.5", file_info.version),
+        );
+    }
+
+    let image_info = &header.image_info;
+    if image_info.image_orientation == Orientation::Undefined {
+        report.push(
+            Warning,
+            "image_info.image_orientation",
+            "the orientation is undefined".to_string(),
+        );
+    }
+    let number_of_elements = image_info.number_of_elements;
+    if !(1..=MAX_ELEMENTS as u8).contains(&number_of_elements) {
+        report.push(
+            Error,
+            "image_info.number_of_elements",
+            format!("{} is not from 1 to {}", number_of_elements, MAX_ELEMENTS),
+        );
+    }
+    let first = &image_info.channel[0];
+    for (element, channel) in image_info.elements().enumerate() {
+        let field = |name: &str| format!("image_info.channel[{}].{}", element, name);
+        match channel.bit_depth {
+            8 | 10 | 12 | 16 => {}
+            1..=32 => report.push(
+                Warning,
+                field("bit_depth"),
+                format!("{} bits are not 8, 10, 12 or 16", channel.bit_depth),
+            ),
+            bit_depth => report.push(
+                Error,
+                field("bit_depth"),
+                format!("{} bits are not from 1 to 32", bit_depth),
+            ),
+        }
+        if channel.pixels_per_line == 0 || channel.lines_per_element == 0 {
+            report.push(
+                Error,
+                field("pixels_per_line"),
+                format!(
+                    "{}x{} holds no pixels",
+                    channel.pixels_per_line, channel.lines_per_element
+                ),
+            );
+        } else if (channel.pixels_per_line, channel.lines_per_element)
+            != (first.pixels_per_line, first.lines_per_element)
+        {
+            report.push(
+                Warning,
+                field("pixels_per_line"),
+                "the size differs from the one of the first element".to_string(),
+            );
+        }
+        if channel.min_data > channel.max_data {
+            report.push(
+                Warning,
+                field("min_data"),
+                format!(
+                    "{} is above the maximum of {}",
+                    channel.min_data, channel.max_data
+                ),
+            );
+        }
+    }
+
+    let data_format_info = &header.data_format_info;
+    if data_format_info.interleave == Interleave::Undefined {
+        report.push(
+            Warning,
+            "data_format_info.interleave",
+            "the interleave is undefined".to_string(),
+        );
+    }
+    if data_format_info.packing == Packing::Undefined {
+        report.push(
+            Warning,
+            "data_format_info.packing",
+            "the packing is undefined".to_string(),
+        );
+    }
+    report
+}
+
+/// Fixes the section sizes and offsets of `header`, read from a file of
+/// `actual_file_len` bytes, so that it can be rewritten cleanly, and
+/// returns the fields changed with their old and new values.
+///
+/// The generic and industry sizes are set to the ones of the
+/// specification, a missing industry section being kept, and the user size
+/// to the size of the user area read. An image offset which is undefined,
+/// points inside the header or past the end of the file is moved right
+/// after the header, and the file size is set to `actual_file_len`.
+pub fn repair(header: &mut Header, actual_file_len: u64) -> Vec<FieldDifference> {
+    let original = header.clone();
+    let user_size = header
+        .user_info
+        .as_ref()
+        .map_or(0, |user_info| user_info.len() as u32);
+
+    let file_info = &mut header.file_info;
+    file_info.generic_size = GENERIC_SIZE;
+    if file_info.industry_size != 0 {
+        file_info.industry_size = INDUSTRY_SIZE;
+    }
+    file_info.user_size = user_size;
+    let header_size = GENERIC_SIZE + file_info.industry_size + user_size;
+    let image_offset = file_info.image_offset;
+    if image_offset == u32::MAX
+        || image_offset < header_size
+        || image_offset as u64 >= actual_file_len
+    {
+        file_info.image_offset = header_size;
+    }
+    // Files too large for the field keep their size
+    if let Ok(file_size) = u32::try_from(actual_file_len) {
+        file_info.file_size = file_size;
+    }
+    original.diff(header)
+}
@@ -0,0 +1,142 @@
+//! Forensic watermarks hidden in the least significant bits of samples.
+//!
+//! The bits of a [`Watermark`] are repeated over a sparse set of samples
+//! picked by a pseudorandom sequence seeded with a secret key, so that
+//! they can only be located, and thus read or detected, with the same key.
+
+use std::collections::HashSet;
+
+use crate::image::{DecodedImage, Samples};
+use crate::CineonError;
+
+/// Bits of a watermark
+const PAYLOAD_BITS: usize = 64;
+/// Samples carrying each bit of a watermark
+const REPETITIONS: usize = 16;
+/// Share of the bits of a watermark that must agree for detection
+const DETECTION_THRESHOLD: f64 = 0.9;
+
+/// Identifiers embedded in an image
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Watermark {
+    /// Facility the image was handed over to
+    pub facility_id: u32,
+    /// Job the image belongs to
+    pub job_id: u32,
+}
+
+impl Watermark {
+    fn to_bits(self) -> u64 {
+        (self.facility_id as u64) << 32 | self.job_id as u64
+    }
+
+    fn from_bits(bits: u64) -> Self {
+        Self {
+            facility_id: (bits >> 32) as u32,
+            job_id: bits as u32,
+        }
+    }
+}
+
+/// Watermark read from an image
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Extracted {
+    /// Identifiers carried by the majority of the samples
+    pub watermark: Watermark,
+    /// Share of samples agreeing with the majority, from 0.5 for noise to
+    /// 1.0 for an untouched watermark
+    pub confidence: f64,
+}
+
+/// SplitMix64 generator, seeded with the key.
+struct Sequence(u64);
+
+impl Iterator for Sequence {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        Some(z ^ (z >> 31))
+    }
+}
+
+/// Distinct indices of the samples carrying the watermark, bit after bit.
+fn positions(samples: usize, key: u64) -> Result<Vec<usize>, CineonError> {
+    let count = PAYLOAD_BITS * REPETITIONS;
+    // Keep the set sparse, so that the picks are quick and invisible
+    if samples < 4 * count {
+        return Err(CineonError::UnsupportedFormat);
+    }
+    let mut seen = HashSet::with_capacity(count);
+    Ok(Sequence(key)
+        .map(|value| (value % samples as u64) as usize)
+        .filter(|index| seen.insert(*index))
+        .take(count)
+        .collect())
+}
+
+/// Embeds `watermark` in the least significant bits of the integer samples
+/// of `image`, at the positions chosen by `key`.
+pub fn embed(image: &mut DecodedImage, key: u64, watermark: Watermark) -> Result<(), CineonError> {
+    let positions = positions(image.samples.len(), key)?;
+    let bits = watermark.to_bits();
+    let bit = |position: usize| (bits >> (position / REPETITIONS) & 1) as u8;
+    match &mut image.samples {
+        Samples::U8(samples) => {
+            for (n, index) in positions.into_iter().enumerate() {
+                samples[index] = samples[index] & !1 | bit(n);
+            }
+        }
+        Samples::U16(samples) => {
+            for (n, index) in positions.into_iter().enumerate() {
+                samples[index] = samples[index] & !1 | bit(n) as u16;
+            }
+        }
+        _ => return Err(CineonError::UnsupportedFormat),
+    }
+    Ok(())
+}
+
+/// Least significant bits of the integer samples at `positions`.
+fn read_bits(image: &DecodedImage, positions: &[usize]) -> Result<Vec<bool>, CineonError> {
+    match &image.samples {
+        Samples::U8(samples) => Ok(positions.iter().map(|i| samples[*i] & 1 == 1).collect()),
+        Samples::U16(samples) => Ok(positions.iter().map(|i| samples[*i] & 1 == 1).collect()),
+        _ => Err(CineonError::UnsupportedFormat),
+    }
+}
+
+/// Reads the watermark at the positions chosen by `key`, each bit taking
+/// the value of the majority of its samples.
+pub fn extract(image: &DecodedImage, key: u64) -> Result<Extracted, CineonError> {
+    let read = read_bits(image, &positions(image.samples.len(), key)?)?;
+    let mut bits = 0;
+    let mut agreeing = 0;
+    for (bit, values) in read.chunks(REPETITIONS).enumerate() {
+        let ones = values.iter().filter(|value| **value).count();
+        if 2 * ones > REPETITIONS {
+            bits |= 1 << bit;
+        }
+        agreeing += ones.max(REPETITIONS - ones);
+    }
+    Ok(Extracted {
+        watermark: Watermark::from_bits(bits),
+        confidence: agreeing as f64 / read.len() as f64,
+    })
+}
+
+/// Whether `image` carries `watermark` at the positions chosen by `key`,
+/// tolerating a few flipped bits.
+pub fn detect(image: &DecodedImage, key: u64, watermark: Watermark) -> Result<bool, CineonError> {
+    let read = read_bits(image, &positions(image.samples.len(), key)?)?;
+    let bits = watermark.to_bits();
+    let matching = read
+        .iter()
+        .enumerate()
+        .filter(|(n, value)| (bits >> (n / REPETITIONS) & 1 == 1) == **value)
+        .count();
+    Ok(matching as f64 / read.len() as f64 >= DETECTION_THRESHOLD)
+}
@@ -0,0 +1,235 @@
+use crate::bits::{BitWriter, ByteOrder};
+use crate::header::{
+    DataFormatInfo, FileInformation, FilmInfo, Header, ImageInfo, OriginationInfo, Packing,
+    INDUSTRY_SIZE, MAGIC_COOKIE,
+};
+use crate::image::{DecodedImage, Samples};
+use crate::CineonError;
+
+/// Bit pattern of an undefined real value
+pub(crate) const UNDEFINED_F32: u32 = 0x7F80_0000;
+
+/// Serializes headers field by field, in big-endian byte order.
+pub(crate) struct HeaderWriter {
+    output: Vec<u8>,
+}
+
+impl HeaderWriter {
+    pub(crate) fn new() -> Self {
+        Self { output: Vec::new() }
+    }
+
+    pub(crate) fn into_bytes(self) -> Vec<u8> {
+        self.output
+    }
+
+    fn write_u8(&mut self, value: u8) {
+        self.output.push(value);
+    }
+
+    fn write_u32(&mut self, value: u32) {
+        self.output.extend_from_slice(&value.to_be_bytes());
+    }
+
+    fn write_i32(&mut self, value: i32) {
+        self.output.extend_from_slice(&value.to_be_bytes());
+    }
+
+    fn write_f32(&mut self, value: f32) {
+        self.output.extend_from_slice(&value.to_be_bytes());
+    }
+
+    /// Writes a cleaned string NUL-padded or truncated to `length` bytes.
+    fn write_string(&mut self, value: &str, length: usize) {
+        let value = clean_string(value);
+        let mut end = value.len().min(length);
+        while !value.is_char_boundary(end) {
+            end -= 1;
+        }
+        self.output.extend_from_slice(&value.as_bytes()[..end]);
+        self.reserved(length - end);
+    }
+
+    fn reserved(&mut self, length: usize) {
+        self.output.resize(self.output.len() + length, 0);
+    }
+
+    pub(crate) fn write_header(&mut self, header: &Header) {
+        self.write_file_info(&header.file_info);
+        self.write_image_info(&header.image_info);
+        self.write_data_format_info(&header.data_format_info);
+        self.write_origination_info(&header.origination_info);
+        match &header.film_info {
+            Some(film_info) => self.write_film_info(film_info),
+            None => self.write_undefined_film_info(),
+        }
+        if let Some(user_info) = &header.user_info {
+            self.output.extend_from_slice(user_info);
+        }
+    }
+
+    fn write_file_info(&mut self, file_info: &FileInformation) {
+        self.write_u32(MAGIC_COOKIE);
+        self.write_u32(file_info.image_offset);
+        self.write_u32(file_info.generic_size);
+        self.write_u32(file_info.industry_size);
+        self.write_u32(file_info.user_size);
+        self.write_u32(file_info.file_size);
+        self.write_string(&file_info.version, 8);
+        self.write_string(&file_info.filename, 100);
+        self.write_string(&file_info.creation_date, 12);
+        self.write_string(&file_info.creation_time, 12);
+        self.reserved(36);
+    }
+
+    fn write_image_info(&mut self, image_info: &ImageInfo) {
+        self.write_u8(image_info.image_orientation.into());
+        self.write_u8(image_info.number_of_elements);
+        self.reserved(2);
+        for channel in image_info.channel.iter() {
+            self.write_u8(channel.metric.into());
+            self.write_u8(channel.designator.into());
+            self.write_u8(channel.bit_depth);
+            self.reserved(1);
+            self.write_u32(channel.pixels_per_line);
+            self.write_u32(channel.lines_per_element);
+            self.write_f32(channel.min_data);
+            self.write_f32(channel.min_quantity);
+            self.write_f32(channel.max_data);
+            self.write_f32(channel.max_quantity);
+        }
+        for pair in [
+            image_info.white_point,
+            image_info.red_primary,
+            image_info.green_primary,
+            image_info.blue_primary,
+        ]
+        .iter()
+        {
+            for value in pair.iter() {
+                self.write_f32(value.unwrap_or(f32::INFINITY));
+            }
+        }
+        self.write_string(&image_info.label_text, 200);
+        self.reserved(28);
+    }
+
+    fn write_data_format_info(&mut self, data_format_info: &DataFormatInfo) {
+        self.write_u8(data_format_info.interleave.into());
+        self.write_u8(data_format_info.packing.into());
+        self.write_u8(data_format_info.data_sign as u8);
+        self.write_u8(data_format_info.image_sense as u8);
+        self.write_u32(data_format_info.line_padding.unwrap_or(u32::MAX));
+        self.write_u32(data_format_info.channel_padding.unwrap_or(u32::MAX));
+        self.reserved(20);
+    }
+
+    fn write_origination_info(&mut self, origination_info: &OriginationInfo) {
+        self.write_i32(origination_info.x_offset);
+        self.write_i32(origination_info.y_offset);
+        self.write_string(&origination_info.source_image_file_name, 100);
+        self.write_string(&origination_info.source_date, 12);
+        self.write_string(&origination_info.source_time, 12);
+        self.write_string(&origination_info.input_device, 64);
+        self.write_string(&origination_info.input_device_model_number, 32);
+        self.write_string(&origination_info.input_device_serial_number, 32);
+        self.write_f32(origination_info.x_device_pitch.unwrap_or(f32::INFINITY));
+        self.write_f32(origination_info.y_device_pitch.unwrap_or(f32::INFINITY));
+        self.write_f32(origination_info.gamma.unwrap_or(f32::INFINITY));
+        self.reserved(40);
+    }
+
+    fn write_film_info(&mut self, film_info: &FilmInfo) {
+        self.write_u8(film_info.film_manufacturing_id_code);
+        self.write_u8(film_info.film_type);
+        self.write_u8(film_info.perfs_offset);
+        self.reserved(1);
+        self.write_u32(film_info.prefix);
+        self.write_u32(film_info.count);
+        self.write_string(&film_info.format, 32);
+        self.write_u32(film_info.frame_position);
+        self.write_f32(film_info.frame_rate.unwrap_or(f32::INFINITY));
+        self.write_string(&film_info.frame_id, 32);
+        self.write_string(&film_info.slate_info, 200);
+        self.reserved(740);
+    }
+
+    /// Writes an industry section whose fields are all undefined.
+    fn write_undefined_film_info(&mut self) {
+        self.output.extend_from_slice(&[0xff; 3]);
+        self.reserved(1);
+        self.write_u32(u32::MAX);
+        self.write_u32(u32::MAX);
+        self.reserved(32);
+        self.write_u32(u32::MAX);
+        self.write_u32(UNDEFINED_F32);
+        self.reserved(INDUSTRY_SIZE as usize - 52);
+    }
+}
+
+/// Keeps the text before the first NUL, without control characters and
+/// surrounding whitespace.
+fn clean_string(value: &str) -> String {
+    value
+        .split('\0')
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .filter(|c| !c.is_control())
+        .collect::<String>()
+        .trim()
+        .to_owned()
+}
+
+/// Packs the samples of an image pixel interleaved following `packing`,
+/// every row starting on a new 32-bit word.
+pub(crate) fn pack_pixels(image: &DecodedImage, packing: &Packing) -> Result<Vec<u8>, CineonError> {
+    let bit_depth = image.bit_depth as u32;
+    let fits = |max: u32| (1..=max).contains(&bit_depth);
+    let sample = |index: usize| match &image.samples {
+        Samples::U8(samples) if fits(8) => Ok(samples[index] as u64),
+        Samples::U16(samples) if fits(16) => Ok(samples[index] as u64),
+        _ => Err(CineonError::UnsupportedFormat),
+    };
+    let row_len = image.width as usize * image.channels as usize;
+    if image.samples.len() < row_len * image.height as usize {
+        return Err(CineonError::OutputError);
+    }
+    // Cells samples are packed into, none when samples are tightly packed
+    let (cell_bits, left_justified) = match packing {
+        Packing::Packed => (0, true),
+        Packing::ByteLeft => (8, true),
+        Packing::ByteRight => (8, false),
+        Packing::WordLeft => (16, true),
+        Packing::WordRight => (16, false),
+        Packing::LongWordLeft | Packing::PackAsManyAsPossible => (32, true),
+        Packing::LongWordRight => (32, false),
+        Packing::Undefined => return Err(CineonError::UnsupportedFormat),
+    };
+    // A sample wider than a cell spans as many cells as needed
+    let word_bits = match cell_bits {
+        0 => 32,
+        _ => cell_bits * bit_depth.div_ceil(cell_bits),
+    };
+    let (per_word, unused) = match cell_bits {
+        0 => (usize::MAX, 0),
+        _ => ((word_bits / bit_depth) as usize, word_bits % bit_depth),
+    };
+
+    let mut output = Vec::new();
+    for row in 0..image.height as usize {
+        let mut writer = BitWriter::with_word_bits(ByteOrder::Big, word_bits)?;
+        for x in 0..row_len {
+            if !left_justified && x % per_word == 0 {
+                writer.write_bits(0, unused)?;
+            }
+            writer.write_bits(sample(row * row_len + x)?, bit_depth)?;
+            if (x + 1) % per_word == 0 {
+                writer.align_to_word();
+            }
+        }
+        output.extend(writer.into_bytes());
+        output.resize(output.len().next_multiple_of(4), 0);
+    }
+    Ok(output)
+}